@@ -401,6 +401,15 @@ impl AgentContext {
         let handle = self.requests.remove(token);
         let mut handle = self.multi.remove2(handle).map_err(Error::from_any)?;
 
+        // Attach the detailed message reported by curl, if any, which is
+        // sometimes the only way to tell errors apart.
+        let result = result.map_err(|mut e| {
+            if let Some(message) = handle.take_error_buf() {
+                e.set_extra(message);
+            }
+            e
+        });
+
         handle.get_mut().set_result(result.map_err(Error::from_any));
 
         Ok(())
@@ -1,6 +1,7 @@
 //! Provides types for working with request and response bodies.
 
 use futures_lite::io::{AsyncRead, BlockOn};
+use http::HeaderValue;
 use std::{
     borrow::Cow,
     fmt,
@@ -15,6 +16,9 @@ mod sync;
 #[allow(unreachable_pub)]
 pub use sync::Body;
 
+#[cfg(feature = "form")]
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
 /// Contains the body of an asynchronous HTTP request or response.
 ///
 /// This type is used to encapsulate the underlying stream or region of memory
@@ -28,7 +32,12 @@ pub use sync::Body;
 /// implements.
 ///
 /// For synchronous requests, use [`Body`] instead.
-pub struct AsyncBody(Inner);
+pub struct AsyncBody(
+    Inner,
+    /// Content type implied by how the body was constructed, such as for a
+    /// multipart form. Used if a request does not specify one explicitly.
+    Option<HeaderValue>,
+);
 
 /// All possible body implementations.
 enum Inner {
@@ -48,7 +57,7 @@ impl AsyncBody {
     /// An empty body represents the *absence* of a body, which is semantically
     /// different than the presence of a body of zero length.
     pub const fn empty() -> Self {
-        Self(Inner::Empty)
+        Self(Inner::Empty, None)
     }
 
     /// Create a new body from a potentially static byte buffer.
@@ -76,7 +85,7 @@ impl AsyncBody {
         B: AsRef<[u8]> + 'static,
     {
         castaway::match_type!(bytes, {
-            Cursor<Cow<'static, [u8]>> as bytes => Self(Inner::Buffer(bytes)),
+            Cursor<Cow<'static, [u8]>> as bytes => Self(Inner::Buffer(bytes), None),
             &'static [u8] as bytes => Self::from_static_impl(bytes),
             &'static str as bytes => Self::from_static_impl(bytes.as_bytes()),
             Vec<u8> as bytes => Self::from(bytes),
@@ -87,7 +96,39 @@ impl AsyncBody {
 
     #[inline]
     fn from_static_impl(bytes: &'static [u8]) -> Self {
-        Self(Inner::Buffer(Cursor::new(Cow::Borrowed(bytes))))
+        Self(Inner::Buffer(Cursor::new(Cow::Borrowed(bytes))), None)
+    }
+
+    /// Create a body containing the given value serialized as
+    /// `application/x-www-form-urlencoded` data.
+    ///
+    /// When used as a request body, the `Content-Type` header of the request is
+    /// set to `application/x-www-form-urlencoded` unless the request already
+    /// specifies one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::AsyncBody;
+    ///
+    /// let body = AsyncBody::from_form(&[("name", "Jane Doe"), ("lang", "en")])?;
+    ///
+    /// assert_eq!(body.len(), Some(21));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Availability
+    ///
+    /// This method is only available when the [`form`](index.html#form)
+    /// feature is enabled.
+    #[cfg(feature = "form")]
+    pub fn from_form<T>(form: &T) -> Result<Self, serde_urlencoded::ser::Error>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        serde_urlencoded::to_string(form).map(|form| {
+            Self::from(form).with_content_type(HeaderValue::from_static(FORM_CONTENT_TYPE))
+        })
     }
 
     /// Create a streaming body that reads from the given reader.
@@ -100,7 +141,7 @@ impl AsyncBody {
     where
         R: AsyncRead + Send + Sync + 'static,
     {
-        Self(Inner::Reader(Box::pin(read), None))
+        Self(Inner::Reader(Box::pin(read), None), None)
     }
 
     /// Create a streaming body with a known length.
@@ -116,7 +157,7 @@ impl AsyncBody {
     where
         R: AsyncRead + Send + Sync + 'static,
     {
-        Self(Inner::Reader(Box::pin(read), Some(length)))
+        Self(Inner::Reader(Box::pin(read), Some(length)), None)
     }
 
     /// Report if this body is empty.
@@ -166,6 +207,29 @@ impl AsyncBody {
         }
     }
 
+    /// Create a copy of this body from the start of its content, if the body
+    /// is stored in memory. Returns `None` for streaming bodies.
+    pub(crate) fn try_clone(&self) -> Option<Self> {
+        let inner = match &self.0 {
+            Inner::Empty => Inner::Empty,
+            Inner::Buffer(cursor) => Inner::Buffer(Cursor::new(cursor.get_ref().clone())),
+            Inner::Reader(_, _) => return None,
+        };
+
+        Some(Self(inner, self.1.clone()))
+    }
+
+    /// Get the content type implied by this body, if any.
+    pub(crate) fn content_type(&self) -> Option<&HeaderValue> {
+        self.1.as_ref()
+    }
+
+    /// Set the content type implied by this body.
+    pub(crate) fn with_content_type(mut self, content_type: HeaderValue) -> Self {
+        self.1 = Some(content_type);
+        self
+    }
+
     /// Turn this asynchronous body into a synchronous one. This is how the
     /// response body is implemented for the synchronous API.
     ///
@@ -174,13 +238,18 @@ impl AsyncBody {
     /// generally if the underlying reader only supports blocking under a
     /// specific runtime.
     pub(crate) fn into_sync(self) -> sync::Body {
-        match self.0 {
+        let body = match self.0 {
             Inner::Empty => sync::Body::empty(),
             Inner::Buffer(cursor) => sync::Body::from_bytes_static(cursor.into_inner()),
             Inner::Reader(reader, Some(len)) => {
                 sync::Body::from_reader_sized(BlockOn::new(reader), len)
             }
             Inner::Reader(reader, None) => sync::Body::from_reader(BlockOn::new(reader)),
+        };
+
+        match self.1 {
+            Some(content_type) => body.with_content_type(content_type),
+            None => body,
         }
     }
 }
@@ -213,7 +282,7 @@ impl From<()> for AsyncBody {
 
 impl From<Vec<u8>> for AsyncBody {
     fn from(body: Vec<u8>) -> Self {
-        Self(Inner::Buffer(Cursor::new(Cow::Owned(body))))
+        Self(Inner::Buffer(Cursor::new(Cow::Owned(body))), None)
     }
 }
 
@@ -1,5 +1,6 @@
 use super::AsyncBody;
 use futures_lite::{future::yield_now, io::AsyncWriteExt};
+use http::HeaderValue;
 use sluice::pipe::{pipe, PipeWriter};
 use std::{
     borrow::Cow,
@@ -17,7 +18,12 @@ use std::{
 /// implements [`Read`], which [`Body`] itself also implements.
 ///
 /// For asynchronous requests, use [`AsyncBody`] instead.
-pub struct Body(Inner);
+pub struct Body(
+    Inner,
+    /// Content type implied by how the body was constructed, such as for a
+    /// multipart form. Used if a request does not specify one explicitly.
+    Option<HeaderValue>,
+);
 
 enum Inner {
     Empty,
@@ -31,7 +37,7 @@ impl Body {
     /// An empty body represents the *absence* of a body, which is semantically
     /// different than the presence of a body of zero length.
     pub const fn empty() -> Self {
-        Self(Inner::Empty)
+        Self(Inner::Empty, None)
     }
 
     /// Create a new body from a potentially static byte buffer.
@@ -59,13 +65,43 @@ impl Body {
         B: AsRef<[u8]> + 'static,
     {
         castaway::match_type!(bytes, {
-            Cursor<Cow<'static, [u8]>> as bytes => Self(Inner::Buffer(bytes)),
+            Cursor<Cow<'static, [u8]>> as bytes => Self(Inner::Buffer(bytes), None),
             Vec<u8> as bytes => Self::from(bytes),
             String as bytes => Self::from(bytes.into_bytes()),
             bytes => Self::from(bytes.as_ref().to_vec()),
         })
     }
 
+    /// Create a body containing the given value serialized as
+    /// `application/x-www-form-urlencoded` data.
+    ///
+    /// When used as a request body, the `Content-Type` header of the request is
+    /// set to `application/x-www-form-urlencoded` unless the request already
+    /// specifies one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::Body;
+    ///
+    /// let body = Body::from_form(&[("name", "Jane Doe"), ("lang", "en")])?;
+    ///
+    /// assert_eq!(body.len(), Some(21));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Availability
+    ///
+    /// This method is only available when the [`form`](index.html#form)
+    /// feature is enabled.
+    #[cfg(feature = "form")]
+    pub fn from_form<T>(form: &T) -> std::result::Result<Self, serde_urlencoded::ser::Error>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        AsyncBody::from_form(form).map(AsyncBody::into_sync)
+    }
+
     /// Create a streaming body that reads from the given reader.
     ///
     /// The body will have an unknown length. When used as a request body,
@@ -76,7 +112,7 @@ impl Body {
     where
         R: Read + Send + Sync + 'static,
     {
-        Self(Inner::Reader(Box::new(reader), None))
+        Self(Inner::Reader(Box::new(reader), None), None)
     }
 
     /// Create a streaming body with a known length.
@@ -92,7 +128,7 @@ impl Body {
     where
         R: Read + Send + Sync + 'static,
     {
-        Self(Inner::Reader(Box::new(reader), Some(length)))
+        Self(Inner::Reader(Box::new(reader), Some(length)), None)
     }
 
     /// Report if this body is empty.
@@ -154,7 +190,7 @@ impl Body {
     /// copy the bytes from the reader to the writing half of the pipe in a
     /// blocking fashion.
     pub(crate) fn into_async(self) -> (AsyncBody, Option<Writer>) {
-        match self.0 {
+        let (body, writer) = match self.0 {
             Inner::Empty => (AsyncBody::empty(), None),
             Inner::Buffer(cursor) => (AsyncBody::from_bytes_static(cursor.into_inner()), None),
             Inner::Reader(reader, len) => {
@@ -172,8 +208,19 @@ impl Body {
                     }),
                 )
             }
+        };
+
+        match self.1 {
+            Some(content_type) => (body.with_content_type(content_type), writer),
+            None => (body, writer),
         }
     }
+
+    /// Set the content type implied by this body.
+    pub(crate) fn with_content_type(mut self, content_type: HeaderValue) -> Self {
+        self.1 = Some(content_type);
+        self
+    }
 }
 
 impl Read for Body {
@@ -200,7 +247,7 @@ impl From<()> for Body {
 
 impl From<Vec<u8>> for Body {
     fn from(body: Vec<u8>) -> Self {
-        Self(Inner::Buffer(Cursor::new(Cow::Owned(body))))
+        Self(Inner::Buffer(Cursor::new(Cow::Owned(body))), None)
     }
 }
 
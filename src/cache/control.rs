@@ -0,0 +1,404 @@
+//! Parsing of `Cache-Control` directives and freshness calculations as
+//! described in [RFC 9111](https://www.rfc-editor.org/rfc/rfc9111).
+
+use super::CacheEntry;
+use http::{HeaderMap, StatusCode};
+use std::time::{Duration, SystemTime};
+
+/// Status codes that are defined as heuristically cacheable by [RFC 9110,
+/// Section 15.1](https://www.rfc-editor.org/rfc/rfc9110#section-15.1).
+const HEURISTICALLY_CACHEABLE: &[u16] =
+    &[200, 203, 204, 206, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Parsed `Cache-Control` directives from either a request or a response.
+/// Unknown directives are ignored.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct CacheControl {
+    pub(crate) no_store: bool,
+    pub(crate) no_cache: bool,
+    pub(crate) public: bool,
+    pub(crate) must_revalidate: bool,
+    pub(crate) only_if_cached: bool,
+    pub(crate) max_age: Option<Duration>,
+    pub(crate) min_fresh: Option<Duration>,
+
+    /// Present if the `max-stale` directive was given. The inner value is
+    /// `None` if any amount of staleness is acceptable.
+    pub(crate) max_stale: Option<Option<Duration>>,
+}
+
+impl CacheControl {
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        let mut control = Self::default();
+        let mut found = false;
+
+        for value in headers.get_all(http::header::CACHE_CONTROL) {
+            let value = match value.to_str() {
+                Ok(value) => value,
+                Err(_) => continue,
+            };
+
+            for directive in value.split(',') {
+                let (name, argument) = match directive.split_once('=') {
+                    Some((name, argument)) => (name, Some(argument.trim().trim_matches('"'))),
+                    None => (directive, None),
+                };
+                let seconds = argument
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+
+                found = true;
+
+                match name.trim().to_ascii_lowercase().as_str() {
+                    "no-store" => control.no_store = true,
+                    "no-cache" => control.no_cache = true,
+                    "public" => control.public = true,
+                    "must-revalidate" | "proxy-revalidate" => control.must_revalidate = true,
+                    "only-if-cached" => control.only_if_cached = true,
+                    "max-age" => control.max_age = seconds.or(Some(Duration::ZERO)),
+                    "min-fresh" => control.min_fresh = seconds,
+                    "max-stale" => control.max_stale = Some(seconds),
+                    _ => {}
+                }
+            }
+        }
+
+        // HTTP/1.0 caches only understand `Pragma: no-cache`, which is to be
+        // treated like `Cache-Control: no-cache` when the latter is absent.
+        if !found {
+            control.no_cache = headers
+                .get_all(http::header::PRAGMA)
+                .iter()
+                .any(|value| value.as_bytes().eq_ignore_ascii_case(b"no-cache"));
+        }
+
+        control
+    }
+}
+
+/// Check whether a response to a `GET` request may be stored according to
+/// [RFC 9111, Section 3](https://www.rfc-editor.org/rfc/rfc9111#section-3).
+pub(crate) fn is_storable(
+    request_control: &CacheControl,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> bool {
+    let control = CacheControl::from_headers(headers);
+
+    if request_control.no_store || control.no_store {
+        return false;
+    }
+
+    // A response that varies on everything can never be reused.
+    if headers
+        .get_all(http::header::VARY)
+        .iter()
+        .any(|value| value.as_bytes().trim_ascii() == b"*")
+    {
+        return false;
+    }
+
+    // Partial responses are not supported.
+    if status == StatusCode::PARTIAL_CONTENT {
+        return false;
+    }
+
+    control.max_age.is_some()
+        || control.public
+        || headers.contains_key(http::header::EXPIRES)
+        || HEURISTICALLY_CACHEABLE.contains(&status.as_u16())
+}
+
+/// Calculate how long a stored response is fresh for, as described in [RFC
+/// 9111, Section 4.2.1](https://www.rfc-editor.org/rfc/rfc9111#section-4.2.1).
+pub(crate) fn freshness_lifetime(entry: &CacheEntry) -> Duration {
+    let headers = entry.headers();
+    let control = CacheControl::from_headers(headers);
+
+    if let Some(max_age) = control.max_age {
+        return max_age;
+    }
+
+    let date = parse_date(headers, http::header::DATE).unwrap_or(entry.response_time);
+
+    if let Some(expires) = headers.get(http::header::EXPIRES) {
+        // Invalid dates, such as "0", represent a time in the past.
+        return expires
+            .to_str()
+            .ok()
+            .and_then(|s| httpdate::parse_http_date(s).ok())
+            .and_then(|expires| expires.duration_since(date).ok())
+            .unwrap_or_default();
+    }
+
+    // Use a heuristic of 10% of the time since the resource was last modified,
+    // as suggested by RFC 9111, Section 4.2.2.
+    if control.public || HEURISTICALLY_CACHEABLE.contains(&entry.status().as_u16()) {
+        if let Some(last_modified) = parse_date(headers, http::header::LAST_MODIFIED) {
+            if let Ok(since) = date.duration_since(last_modified) {
+                return since / 10;
+            }
+        }
+    }
+
+    Duration::ZERO
+}
+
+/// Calculate the current age of a stored response, as described in [RFC 9111,
+/// Section 4.2.3](https://www.rfc-editor.org/rfc/rfc9111#section-4.2.3).
+pub(crate) fn current_age(entry: &CacheEntry, now: SystemTime) -> Duration {
+    let headers = entry.headers();
+
+    let age_value = headers
+        .get(http::header::AGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .map(Duration::from_secs)
+        .unwrap_or_default();
+
+    let apparent_age = parse_date(headers, http::header::DATE)
+        .and_then(|date| entry.response_time.duration_since(date).ok())
+        .unwrap_or_default();
+
+    let response_delay = entry
+        .response_time
+        .duration_since(entry.request_time)
+        .unwrap_or_default();
+
+    let corrected_initial_age = apparent_age.max(age_value + response_delay);
+    let resident_time = now.duration_since(entry.response_time).unwrap_or_default();
+
+    corrected_initial_age + resident_time
+}
+
+/// Check whether a stored response can be reused without revalidating it,
+/// taking both the response and the request directives into account.
+pub(crate) fn is_fresh(
+    entry: &CacheEntry,
+    request_control: &CacheControl,
+    now: SystemTime,
+) -> bool {
+    let response_control = CacheControl::from_headers(entry.headers());
+
+    if request_control.no_cache || response_control.no_cache {
+        return false;
+    }
+
+    let age = current_age(entry, now);
+    let mut lifetime = freshness_lifetime(entry);
+
+    if let Some(max_age) = request_control.max_age {
+        lifetime = lifetime.min(max_age);
+    }
+
+    if let Some(min_fresh) = request_control.min_fresh {
+        lifetime = lifetime.saturating_sub(min_fresh);
+    }
+
+    if lifetime > age {
+        return true;
+    }
+
+    // Serving stale responses is only allowed if the client explicitly asked
+    // for it and the server did not forbid it.
+    match request_control.max_stale {
+        Some(_) if response_control.must_revalidate => false,
+        Some(None) => true,
+        Some(Some(max_stale)) => age - lifetime <= max_stale,
+        None => false,
+    }
+}
+
+fn parse_date(headers: &HeaderMap, name: http::header::HeaderName) -> Option<SystemTime> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{HeaderValue, Version};
+    use test_case::test_case;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+
+        headers
+    }
+
+    fn entry(pairs: &[(&'static str, &str)], response_time: SystemTime) -> CacheEntry {
+        CacheEntry::new(
+            StatusCode::OK,
+            Version::HTTP_11,
+            headers(pairs),
+            HeaderMap::new(),
+            Vec::new(),
+            response_time,
+            response_time,
+        )
+    }
+
+    #[test]
+    fn parse_directives() {
+        let control = CacheControl::from_headers(&headers(&[
+            ("cache-control", "No-Cache, max-age=\"60\""),
+            ("cache-control", "max-stale, must-revalidate"),
+        ]));
+
+        assert!(control.no_cache);
+        assert!(control.must_revalidate);
+        assert_eq!(control.max_age, Some(Duration::from_secs(60)));
+        assert_eq!(control.max_stale, Some(None));
+        assert!(!control.no_store);
+    }
+
+    #[test]
+    fn pragma_no_cache_without_cache_control() {
+        assert!(CacheControl::from_headers(&headers(&[("pragma", "no-cache")])).no_cache);
+        assert!(
+            !CacheControl::from_headers(&headers(&[
+                ("pragma", "no-cache"),
+                ("cache-control", "max-age=5"),
+            ]))
+            .no_cache
+        );
+    }
+
+    #[test_case(200, &[], true)]
+    #[test_case(200, &[("cache-control", "no-store")], false)]
+    #[test_case(200, &[("vary", "*")], false)]
+    #[test_case(201, &[], false)]
+    #[test_case(201, &[("cache-control", "max-age=10")], true)]
+    #[test_case(500, &[("expires", "Wed, 21 Oct 2015 07:28:00 GMT")], true)]
+    #[test_case(206, &[("cache-control", "max-age=10")], false)]
+    fn storable(status: u16, pairs: &[(&'static str, &str)], expected: bool) {
+        assert_eq!(
+            is_storable(
+                &CacheControl::default(),
+                StatusCode::from_u16(status).unwrap(),
+                &headers(pairs)
+            ),
+            expected
+        );
+    }
+
+    #[test]
+    fn max_age_takes_precedence_over_expires() {
+        let now = SystemTime::now();
+        let entry = entry(
+            &[
+                ("cache-control", "max-age=30"),
+                (
+                    "expires",
+                    &httpdate::fmt_http_date(now + Duration::from_secs(600)),
+                ),
+            ],
+            now,
+        );
+
+        assert_eq!(freshness_lifetime(&entry), Duration::from_secs(30));
+    }
+
+    #[test]
+    fn invalid_expires_is_already_stale() {
+        let entry = entry(&[("expires", "0")], SystemTime::now());
+
+        assert_eq!(freshness_lifetime(&entry), Duration::ZERO);
+    }
+
+    #[test]
+    fn heuristic_freshness_from_last_modified() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let entry = entry(
+            &[
+                ("date", &httpdate::fmt_http_date(now)),
+                (
+                    "last-modified",
+                    &httpdate::fmt_http_date(now - Duration::from_secs(1000)),
+                ),
+            ],
+            now,
+        );
+
+        assert_eq!(freshness_lifetime(&entry), Duration::from_secs(100));
+    }
+
+    #[test]
+    fn age_includes_age_header_and_resident_time() {
+        let response_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let entry = entry(&[("age", "20")], response_time);
+
+        assert_eq!(
+            current_age(&entry, response_time + Duration::from_secs(5)),
+            Duration::from_secs(25)
+        );
+    }
+
+    #[test]
+    fn freshness_respects_request_directives() {
+        let now = SystemTime::now();
+        let entry = entry(
+            &[("cache-control", "max-age=60")],
+            now - Duration::from_secs(30),
+        );
+
+        assert!(is_fresh(&entry, &CacheControl::default(), now));
+        assert!(!is_fresh(
+            &entry,
+            &CacheControl {
+                max_age: Some(Duration::from_secs(10)),
+                ..Default::default()
+            },
+            now
+        ));
+        assert!(!is_fresh(
+            &entry,
+            &CacheControl {
+                min_fresh: Some(Duration::from_secs(40)),
+                ..Default::default()
+            },
+            now
+        ));
+        assert!(!is_fresh(
+            &entry,
+            &CacheControl {
+                no_cache: true,
+                ..Default::default()
+            },
+            now
+        ));
+    }
+
+    #[test]
+    fn max_stale_allows_stale_responses() {
+        let now = SystemTime::now();
+        let entry = entry(
+            &[("cache-control", "max-age=10")],
+            now - Duration::from_secs(30),
+        );
+
+        assert!(!is_fresh(&entry, &CacheControl::default(), now));
+        assert!(is_fresh(
+            &entry,
+            &CacheControl {
+                max_stale: Some(Some(Duration::from_secs(60))),
+                ..Default::default()
+            },
+            now
+        ));
+        assert!(!is_fresh(
+            &entry,
+            &CacheControl {
+                max_stale: Some(Some(Duration::from_secs(5))),
+                ..Default::default()
+            },
+            now
+        ));
+    }
+}
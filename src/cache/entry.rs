@@ -0,0 +1,364 @@
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode, Version};
+use std::{
+    fmt, io,
+    time::{Duration, SystemTime},
+};
+
+/// A response stored in a cache, along with the metadata required to decide
+/// whether it can be reused for future requests.
+///
+/// Cache entries are created by the client when a storable response is
+/// received and handed to a [`CacheStore`](super::CacheStore) for safekeeping.
+/// Custom store implementations that need to persist entries outside of
+/// memory can use [`CacheEntry::to_bytes`] and [`CacheEntry::from_bytes`] to
+/// convert entries to and from a self-contained binary representation.
+#[derive(Clone)]
+pub struct CacheEntry {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+
+    /// Values of the request headers nominated by the `Vary` response header
+    /// at the time the response was stored.
+    vary_headers: HeaderMap,
+    body: Vec<u8>,
+    pub(crate) request_time: SystemTime,
+    pub(crate) response_time: SystemTime,
+}
+
+impl CacheEntry {
+    pub(crate) fn new(
+        status: StatusCode,
+        version: Version,
+        headers: HeaderMap,
+        vary_headers: HeaderMap,
+        body: Vec<u8>,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) -> Self {
+        Self {
+            status,
+            version,
+            headers,
+            vary_headers,
+            body,
+            request_time,
+            response_time,
+        }
+    }
+
+    /// Get the status code of the stored response.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Get the HTTP version of the stored response.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Get the headers of the stored response.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    /// Get the body of the stored response.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Get the time at which the request that produced this response was sent.
+    pub fn request_time(&self) -> SystemTime {
+        self.request_time
+    }
+
+    /// Get the time at which this response was received.
+    pub fn response_time(&self) -> SystemTime {
+        self.response_time
+    }
+
+    /// Check whether the request headers nominated by `Vary` match the headers
+    /// of the given request.
+    pub(crate) fn matches_vary(&self, request_headers: &HeaderMap) -> bool {
+        vary_names(&self.headers).all(|name| {
+            self.vary_headers
+                .get_all(&name)
+                .iter()
+                .eq(request_headers.get_all(&name).iter())
+        })
+    }
+
+    /// Get the approximate number of bytes of memory used by this entry.
+    pub(crate) fn size(&self) -> usize {
+        let headers = |map: &HeaderMap| -> usize {
+            map.iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum()
+        };
+
+        headers(&self.headers) + headers(&self.vary_headers) + self.body.len()
+    }
+
+    pub(crate) fn extend_body(&mut self, bytes: &[u8]) {
+        self.body.extend_from_slice(bytes);
+    }
+
+    /// Update the stored headers using a `304 Not Modified` response, as
+    /// described in [RFC 9111, Section
+    /// 4.3.4](https://www.rfc-editor.org/rfc/rfc9111#section-4.3.4).
+    pub(crate) fn freshen(
+        &mut self,
+        headers: &HeaderMap,
+        request_time: SystemTime,
+        response_time: SystemTime,
+    ) {
+        for name in headers.keys() {
+            // The 304 response describes itself, not the stored body.
+            if name == http::header::CONTENT_LENGTH {
+                continue;
+            }
+
+            self.headers.remove(name);
+
+            for value in headers.get_all(name) {
+                self.headers.append(name.clone(), value.clone());
+            }
+        }
+
+        self.request_time = request_time;
+        self.response_time = response_time;
+    }
+
+    /// Serialize this entry into a self-contained binary representation.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.body.len() + 512);
+
+        bytes.extend_from_slice(
+            format!(
+                "{} {:?} {} {}\n",
+                self.status.as_u16(),
+                self.version,
+                unix_millis(self.request_time),
+                unix_millis(self.response_time),
+            )
+            .as_bytes(),
+        );
+
+        write_headers(&mut bytes, &self.headers);
+        write_headers(&mut bytes, &self.vary_headers);
+
+        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Deserialize an entry previously serialized with
+    /// [`CacheEntry::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Self> {
+        let mut reader = LineReader(bytes);
+
+        let status_line = reader.line()?;
+        let mut parts = status_line.split(|&b| b == b' ');
+        let mut next_part = || {
+            parts
+                .next()
+                .and_then(|part| std::str::from_utf8(part).ok())
+                .ok_or_else(invalid_data)
+        };
+
+        let status = next_part()?
+            .parse::<u16>()
+            .ok()
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .ok_or_else(invalid_data)?;
+        let version = match next_part()? {
+            "HTTP/0.9" => Version::HTTP_09,
+            "HTTP/1.0" => Version::HTTP_10,
+            "HTTP/1.1" => Version::HTTP_11,
+            "HTTP/2.0" => Version::HTTP_2,
+            "HTTP/3.0" => Version::HTTP_3,
+            _ => return Err(invalid_data()),
+        };
+        let request_time = parse_unix_millis(next_part()?)?;
+        let response_time = parse_unix_millis(next_part()?)?;
+
+        let headers = reader.headers()?;
+        let vary_headers = reader.headers()?;
+
+        Ok(Self {
+            status,
+            version,
+            headers,
+            vary_headers,
+            body: reader.0.to_vec(),
+            request_time,
+            response_time,
+        })
+    }
+}
+
+impl fmt::Debug for CacheEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheEntry")
+            .field("status", &self.status)
+            .field("version", &self.version)
+            .field("headers", &self.headers)
+            .field("body", &format_args!("[{} bytes]", self.body.len()))
+            .field("response_time", &self.response_time)
+            .finish()
+    }
+}
+
+/// Get the names of the request headers nominated by the `Vary` headers of a
+/// response.
+pub(crate) fn vary_names(headers: &HeaderMap) -> impl Iterator<Item = HeaderName> + '_ {
+    headers
+        .get_all(http::header::VARY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+}
+
+fn write_headers(bytes: &mut Vec<u8>, headers: &HeaderMap) {
+    for (name, value) in headers {
+        bytes.extend_from_slice(name.as_str().as_bytes());
+        bytes.extend_from_slice(b": ");
+        bytes.extend_from_slice(value.as_bytes());
+        bytes.push(b'\n');
+    }
+
+    bytes.push(b'\n');
+}
+
+fn unix_millis(time: SystemTime) -> u128 {
+    time.duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+fn parse_unix_millis(s: &str) -> io::Result<SystemTime> {
+    s.parse::<u64>()
+        .map(|millis| SystemTime::UNIX_EPOCH + Duration::from_millis(millis))
+        .map_err(|_| invalid_data())
+}
+
+fn invalid_data() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed cache entry")
+}
+
+struct LineReader<'a>(&'a [u8]);
+
+impl<'a> LineReader<'a> {
+    fn line(&mut self) -> io::Result<&'a [u8]> {
+        let end = self
+            .0
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(invalid_data)?;
+        let line = &self.0[..end];
+
+        self.0 = &self.0[end + 1..];
+
+        Ok(line)
+    }
+
+    /// Read a block of headers terminated by an empty line.
+    fn headers(&mut self) -> io::Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+
+        loop {
+            let line = self.line()?;
+
+            if line.is_empty() {
+                return Ok(headers);
+            }
+
+            let split = line
+                .windows(2)
+                .position(|w| w == b": ")
+                .ok_or_else(invalid_data)?;
+            let name = HeaderName::from_bytes(&line[..split]).map_err(|_| invalid_data())?;
+            let value = HeaderValue::from_bytes(&line[split + 2..]).map_err(|_| invalid_data())?;
+
+            headers.append(name, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry() -> CacheEntry {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("text/plain"));
+        headers.append("set-thing", HeaderValue::from_static("a"));
+        headers.append("set-thing", HeaderValue::from_static("b"));
+        headers.insert("vary", HeaderValue::from_static("Accept-Language, Accept"));
+
+        let mut vary_headers = HeaderMap::new();
+        vary_headers.insert("accept-language", HeaderValue::from_static("en"));
+
+        CacheEntry::new(
+            StatusCode::NOT_FOUND,
+            Version::HTTP_2,
+            headers,
+            vary_headers,
+            b"hello\nworld\n\n".to_vec(),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_600_000_000_123),
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_600_000_000_456),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let entry = entry();
+        let decoded = CacheEntry::from_bytes(&entry.to_bytes()).unwrap();
+
+        assert_eq!(decoded.status(), entry.status());
+        assert_eq!(decoded.version(), entry.version());
+        assert_eq!(decoded.headers(), entry.headers());
+        assert_eq!(decoded.vary_headers, entry.vary_headers);
+        assert_eq!(decoded.body(), entry.body());
+        assert_eq!(decoded.request_time(), entry.request_time());
+        assert_eq!(decoded.response_time(), entry.response_time());
+    }
+
+    #[test]
+    fn truncated_bytes_are_rejected() {
+        let bytes = entry().to_bytes();
+
+        assert!(CacheEntry::from_bytes(&bytes[..20]).is_err());
+        assert!(CacheEntry::from_bytes(b"").is_err());
+    }
+
+    #[test]
+    fn vary_matching() {
+        let entry = entry();
+        let mut request_headers = HeaderMap::new();
+
+        assert!(!entry.matches_vary(&request_headers));
+
+        request_headers.insert("accept-language", HeaderValue::from_static("en"));
+        assert!(entry.matches_vary(&request_headers));
+
+        request_headers.insert("accept", HeaderValue::from_static("*/*"));
+        assert!(!entry.matches_vary(&request_headers));
+    }
+
+    #[test]
+    fn freshen_replaces_headers() {
+        let mut entry = entry();
+        let mut headers = HeaderMap::new();
+        headers.insert("set-thing", HeaderValue::from_static("c"));
+        headers.insert("content-length", HeaderValue::from_static("0"));
+
+        let now = SystemTime::now();
+        entry.freshen(&headers, now, now);
+
+        assert_eq!(entry.headers().get_all("set-thing").iter().count(), 1);
+        assert_eq!(entry.headers()["set-thing"], "c");
+        assert!(!entry.headers().contains_key("content-length"));
+        assert_eq!(entry.response_time(), now);
+    }
+}
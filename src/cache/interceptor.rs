@@ -0,0 +1,321 @@
+//! Interceptor that serves responses from a cache store where possible and
+//! stores cacheable responses as they are received.
+
+use super::{
+    CacheEntry, CacheStatus, CacheStore,
+    control::{self, CacheControl},
+    entry::vary_names,
+};
+use crate::{
+    body::AsyncBody,
+    error::{Error, ErrorKind},
+    interceptor::{Context, Interceptor, InterceptorFuture},
+};
+use futures_lite::io::AsyncRead;
+use http::{HeaderMap, HeaderValue, Method, Request, Response, StatusCode, Uri};
+use std::{
+    io, mem,
+    pin::Pin,
+    sync::Arc,
+    task::{Context as TaskContext, Poll},
+    time::SystemTime,
+};
+
+/// Default maximum size of a response body to store, in bytes.
+pub(crate) const DEFAULT_MAX_ENTRY_SIZE: u64 = 8 * 1024 * 1024;
+
+pub(crate) struct CacheInterceptor {
+    store: Arc<dyn CacheStore>,
+
+    /// Maximum size of a response body to store, in bytes.
+    max_entry_size: u64,
+}
+
+impl CacheInterceptor {
+    pub(crate) fn new(store: Arc<dyn CacheStore>, max_entry_size: u64) -> Self {
+        Self {
+            store,
+            max_entry_size,
+        }
+    }
+}
+
+impl Interceptor for CacheInterceptor {
+    type Err = Error;
+
+    fn intercept<'a>(
+        &'a self,
+        mut request: Request<AsyncBody>,
+        ctx: Context<'a>,
+    ) -> InterceptorFuture<'a, Self::Err> {
+        Box::pin(async move {
+            let key = cache_key(request.uri());
+
+            // Only responses to GET requests are cached. Other requests pass
+            // through, but a successful unsafe request invalidates anything
+            // stored for the target resource.
+            if request.method() != Method::GET {
+                let is_unsafe = !matches!(
+                    *request.method(),
+                    Method::HEAD | Method::OPTIONS | Method::TRACE
+                );
+                let uri = request.uri().clone();
+                let mut response = ctx.send(request).await?;
+
+                if is_unsafe
+                    && (response.status().is_success() || response.status().is_redirection())
+                {
+                    self.invalidate(&uri, &key, response.headers());
+                }
+
+                response.extensions_mut().insert(CacheStatus::Miss);
+
+                return Ok(response);
+            }
+
+            let request_control = CacheControl::from_headers(request.headers());
+
+            // Requests that are already conditional or ask for a partial
+            // response are managed by the caller, so stay out of the way.
+            if request_control.no_store || is_conditional(request.headers()) {
+                let mut response = ctx.send(request).await?;
+                response.extensions_mut().insert(CacheStatus::Miss);
+                return Ok(response);
+            }
+
+            let now = SystemTime::now();
+            let entry = self
+                .store
+                .get(&key)
+                .filter(|entry| entry.matches_vary(request.headers()));
+
+            if let Some(entry) = entry.as_ref() {
+                if control::is_fresh(entry, &request_control, now) {
+                    tracing::debug!("serving response for {} from cache", key);
+                    return Ok(cached_response(entry, now, CacheStatus::Hit));
+                }
+            }
+
+            if request_control.only_if_cached {
+                let mut response = Response::builder()
+                    .status(StatusCode::GATEWAY_TIMEOUT)
+                    .body(AsyncBody::empty())
+                    .map_err(|e| Error::new(ErrorKind::Unknown, e))?;
+                response.extensions_mut().insert(CacheStatus::Miss);
+                return Ok(response);
+            }
+
+            // If we have a stale entry, ask the server whether it is still
+            // valid using whatever validators it gave us.
+            let revalidating = match entry.as_ref() {
+                Some(entry) => add_validators(entry, request.headers_mut()),
+                None => false,
+            };
+
+            let vary_source = request.headers().clone();
+            let request_time = SystemTime::now();
+            let mut response = ctx.send(request).await?;
+            let response_time = SystemTime::now();
+
+            if revalidating && response.status() == StatusCode::NOT_MODIFIED {
+                let mut entry = entry.unwrap();
+
+                tracing::debug!("cached response for {} revalidated", key);
+                entry.freshen(response.headers(), request_time, response_time);
+
+                let mut cached = cached_response(&entry, response_time, CacheStatus::Revalidated);
+                let extensions = mem::take(response.extensions_mut());
+                cached.extensions_mut().extend(extensions);
+                cached.extensions_mut().insert(CacheStatus::Revalidated);
+
+                self.store.put(&key, entry);
+
+                return Ok(cached);
+            }
+
+            response.extensions_mut().insert(CacheStatus::Miss);
+
+            if !control::is_storable(&request_control, response.status(), response.headers()) {
+                return Ok(response);
+            }
+
+            // Don't bother copying a body that is known to be too large.
+            if response
+                .body()
+                .len()
+                .is_some_and(|len| len > self.max_entry_size)
+            {
+                tracing::debug!("response for {} is too large to cache", key);
+                return Ok(response);
+            }
+
+            let mut vary_headers = HeaderMap::new();
+            for name in vary_names(response.headers()) {
+                for value in vary_source.get_all(&name) {
+                    vary_headers.append(name.clone(), value.clone());
+                }
+            }
+
+            let entry = CacheEntry::new(
+                response.status(),
+                response.version(),
+                response.headers().clone(),
+                vary_headers,
+                Vec::new(),
+                request_time,
+                response_time,
+            );
+
+            // Store the response once the caller has read the entire body.
+            Ok(response.map(|body| {
+                let len = body.len();
+                let writer = CacheWriter {
+                    body,
+                    remaining: self.max_entry_size,
+                    pending: Some(PendingEntry {
+                        store: self.store.clone(),
+                        key,
+                        entry,
+                    }),
+                };
+
+                match len {
+                    Some(len) => AsyncBody::from_reader_sized(writer, len),
+                    None => AsyncBody::from_reader(writer),
+                }
+            }))
+        })
+    }
+}
+
+impl CacheInterceptor {
+    /// Remove stored responses affected by an unsafe request, as described in
+    /// [RFC 9111, Section
+    /// 4.4](https://www.rfc-editor.org/rfc/rfc9111#section-4.4).
+    fn invalidate(&self, uri: &Uri, key: &str, headers: &HeaderMap) {
+        self.store.remove(key);
+
+        for name in [http::header::LOCATION, http::header::CONTENT_LOCATION] {
+            let target = headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<Uri>().ok());
+
+            // Only invalidate URIs with the same origin to prevent a server
+            // from evicting other servers' responses.
+            if let Some(target) = target {
+                if target.scheme() == uri.scheme() && target.authority() == uri.authority() {
+                    self.store.remove(&cache_key(&target));
+                }
+            }
+        }
+    }
+}
+
+fn cache_key(uri: &Uri) -> String {
+    uri.to_string()
+}
+
+fn is_conditional(headers: &HeaderMap) -> bool {
+    headers.contains_key(http::header::IF_NONE_MATCH)
+        || headers.contains_key(http::header::IF_MODIFIED_SINCE)
+        || headers.contains_key(http::header::IF_MATCH)
+        || headers.contains_key(http::header::IF_UNMODIFIED_SINCE)
+        || headers.contains_key(http::header::RANGE)
+}
+
+/// Add conditional headers to a request based on the validators of a stored
+/// response. Returns `false` if the entry has no validators.
+fn add_validators(entry: &CacheEntry, headers: &mut HeaderMap) -> bool {
+    let mut added = false;
+
+    if let Some(etag) = entry.headers().get(http::header::ETAG) {
+        headers.insert(http::header::IF_NONE_MATCH, etag.clone());
+        added = true;
+    }
+
+    if let Some(last_modified) = entry.headers().get(http::header::LAST_MODIFIED) {
+        headers.insert(http::header::IF_MODIFIED_SINCE, last_modified.clone());
+        added = true;
+    }
+
+    added
+}
+
+/// Construct a response from a stored entry.
+fn cached_response(
+    entry: &CacheEntry,
+    now: SystemTime,
+    status: CacheStatus,
+) -> Response<AsyncBody> {
+    let mut response = Response::new(AsyncBody::from(entry.body().to_vec()));
+
+    *response.status_mut() = entry.status();
+    *response.version_mut() = entry.version();
+    *response.headers_mut() = entry.headers().clone();
+
+    let age = control::current_age(entry, now).as_secs();
+    response
+        .headers_mut()
+        .insert(http::header::AGE, HeaderValue::from(age));
+
+    response.extensions_mut().insert(status);
+    response
+}
+
+struct PendingEntry {
+    store: Arc<dyn CacheStore>,
+    key: String,
+    entry: CacheEntry,
+}
+
+/// Response body wrapper that copies the body as it is read and stores the
+/// complete response in the cache once the end of the body is reached.
+struct CacheWriter {
+    body: AsyncBody,
+
+    /// Number of bytes that may still be copied before the body is too large
+    /// to store.
+    remaining: u64,
+
+    /// The entry to store, or `None` if already stored or abandoned.
+    pending: Option<PendingEntry>,
+}
+
+impl AsyncRead for CacheWriter {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let result = futures_lite::ready!(Pin::new(&mut this.body).poll_read(cx, buf));
+
+        match &result {
+            Ok(0) => {
+                if let Some(pending) = this.pending.take() {
+                    pending.store.put(&pending.key, pending.entry);
+                }
+            }
+            Ok(n) => {
+                if let Some(pending) = this.pending.as_mut() {
+                    match this.remaining.checked_sub(*n as u64) {
+                        Some(remaining) => {
+                            this.remaining = remaining;
+                            pending.entry.extend_body(&buf[..*n]);
+                        }
+                        // Stop copying bodies that turn out to be too large.
+                        None => {
+                            tracing::debug!("response for {} is too large to cache", pending.key);
+                            this.pending = None;
+                        }
+                    }
+                }
+            }
+            // A body that failed to download completely must not be cached.
+            Err(_) => this.pending = None,
+        }
+
+        Poll::Ready(result)
+    }
+}
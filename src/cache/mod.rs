@@ -0,0 +1,88 @@
+//! Types for caching HTTP responses.
+//!
+//! # Response caching
+//!
+//! By default every request is sent to the server, even if an identical
+//! request was just made. A client can instead be configured to keep a private
+//! cache of responses using
+//! [`HttpClientBuilder::cache`](crate::HttpClientBuilder::cache), in which case
+//! responses are stored and reused following the rules described in [RFC
+//! 9111](https://www.rfc-editor.org/rfc/rfc9111):
+//!
+//! - Only responses to `GET` requests are stored, and only if permitted by the
+//!   `Cache-Control` directives of both the request and the response.
+//! - A stored response is reused while it is fresh, as determined by
+//!   `Cache-Control: max-age`, `Expires`, or, in their absence, a heuristic
+//!   based on `Last-Modified`. Responses are only reused for requests with
+//!   matching values for the headers listed in `Vary`.
+//! - Once stale, a stored response is revalidated with the server using
+//!   `If-None-Match` and `If-Modified-Since`. If the server responds with `304
+//!   Not Modified`, the stored response is updated and returned in full.
+//! - A successful unsafe request, such as a `POST`, evicts any stored response
+//!   for the same URI.
+//!
+//! A response is stored once its body has been read to the end. Responses
+//! whose body is only partially read or fails to download are not stored.
+//!
+//! Requests can control how the cache is used by setting `Cache-Control`
+//! request headers, such as `no-cache` to force revalidation or
+//! `only-if-cached` to avoid the network entirely. Requests that already carry
+//! conditional headers or a `Range` header bypass the cache.
+//!
+//! Whether a response came from the cache can be checked using
+//! [`ResponseExt::cache_status`](crate::ResponseExt::cache_status).
+//!
+//! # Storage
+//!
+//! Where cached responses are kept is decided by a [`CacheStore`]. Two
+//! implementations are provided: [`MemoryStore`], which keeps entries up to a
+//! maximum total size in memory, and [`DiskStore`], which persists entries
+//! inside a directory. Custom storage can be provided by implementing
+//! [`CacheStore`].
+//!
+//! Responses with bodies larger than 8 MiB are not stored, which can be changed
+//! using
+//! [`HttpClientBuilder::cache_max_entry_size`](crate::HttpClientBuilder::cache_max_entry_size).
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use isahc::{cache::MemoryStore, prelude::*, HttpClient};
+//!
+//! let client = HttpClient::builder()
+//!     .cache(MemoryStore::new(64 * 1024 * 1024))
+//!     .build()?;
+//!
+//! let mut response = client.get("https://example.org")?;
+//! response.consume()?;
+//!
+//! // The second request may be answered from the cache.
+//! let response = client.get("https://example.org")?;
+//! println!("cache status: {:?}", response.cache_status());
+//! # Ok::<(), isahc::Error>(())
+//! ```
+
+mod control;
+mod entry;
+pub(crate) mod interceptor;
+mod store;
+
+pub use self::{
+    entry::CacheEntry,
+    store::{CacheStore, DiskStore, MemoryStore},
+};
+
+/// Describes how a response was produced when a cache is in use.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum CacheStatus {
+    /// The response was served from the cache without contacting the server.
+    Hit,
+
+    /// The response was received from the server.
+    Miss,
+
+    /// A stored response was confirmed to still be valid by the server and
+    /// was served from the cache.
+    Revalidated,
+}
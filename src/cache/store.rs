@@ -0,0 +1,335 @@
+use super::CacheEntry;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// A storage backend for cached responses.
+///
+/// Entries are identified by an opaque string key computed by the client. A
+/// store is free to discard entries at any time, such as to stay within a size
+/// limit; the client treats a missing entry as a cache miss.
+///
+/// Since a store is shared by all requests made by a client, implementations
+/// must be safe to use concurrently.
+pub trait CacheStore: Send + Sync {
+    /// Get the entry stored under the given key, if any.
+    fn get(&self, key: &str) -> Option<CacheEntry>;
+
+    /// Store an entry under the given key, replacing any existing entry.
+    fn put(&self, key: &str, entry: CacheEntry);
+
+    /// Remove the entry stored under the given key, if any.
+    fn remove(&self, key: &str);
+}
+
+/// A cache store that keeps entries in memory, evicting the least recently
+/// used entries once the entries stored exceed a maximum total size.
+///
+/// The size of an entry is the size of its body plus the size of its headers.
+/// Entries larger than the maximum size of the store are not stored.
+pub struct MemoryStore {
+    capacity: usize,
+    inner: Mutex<MemoryStoreInner>,
+}
+
+#[derive(Default)]
+struct MemoryStoreInner {
+    /// Monotonic counter used to track when each entry was last used.
+    tick: u64,
+    entries: HashMap<String, (u64, CacheEntry)>,
+
+    /// Keys indexed by the tick they were last used at, oldest first.
+    recency: BTreeMap<u64, String>,
+
+    /// Total size of all stored entries, in bytes.
+    size: usize,
+}
+
+impl MemoryStore {
+    /// Create a new in-memory store that holds at most `capacity` bytes of
+    /// entries.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Get the number of entries currently stored.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().entries.len()
+    }
+
+    /// Check whether the store contains no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the total size of the entries currently stored, in bytes.
+    pub fn size(&self) -> usize {
+        self.inner.lock().unwrap().size
+    }
+
+    /// Remove all entries from the store.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.entries.clear();
+        inner.recency.clear();
+        inner.size = 0;
+    }
+}
+
+impl MemoryStoreInner {
+    fn remove(&mut self, key: &str) {
+        if let Some((used, entry)) = self.entries.remove(key) {
+            self.recency.remove(&used);
+            self.size -= entry.size();
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        let tick = self.tick;
+
+        if let Some((used, _)) = self.entries.get_mut(key) {
+            self.recency.remove(used);
+            self.recency.insert(tick, key.to_owned());
+            *used = tick;
+        }
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.touch(key);
+        inner.entries.get(key).map(|(_, entry)| entry.clone())
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        let mut inner = self.inner.lock().unwrap();
+
+        inner.remove(key);
+
+        let size = entry.size();
+
+        if size > self.capacity {
+            return;
+        }
+
+        inner.tick += 1;
+        let tick = inner.tick;
+
+        inner.entries.insert(key.to_owned(), (tick, entry));
+        inner.recency.insert(tick, key.to_owned());
+        inner.size += size;
+
+        while inner.size > self.capacity {
+            match inner.recency.first_key_value() {
+                Some((_, oldest)) => {
+                    let oldest = oldest.clone();
+                    inner.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        self.inner.lock().unwrap().remove(key);
+    }
+}
+
+impl fmt::Debug for MemoryStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryStore")
+            .field("capacity", &self.capacity)
+            .field("len", &self.len())
+            .field("size", &self.size())
+            .finish()
+    }
+}
+
+/// A cache store that persists each entry as a file inside a directory.
+///
+/// Entries survive across processes, and multiple clients may share the same
+/// directory. Entries are written to a temporary file first and then moved
+/// into place, so readers never observe a partially written entry. Files that
+/// cannot be read or decoded are treated as missing.
+#[derive(Debug)]
+pub struct DiskStore {
+    dir: PathBuf,
+}
+
+impl DiskStore {
+    /// Create a store that keeps entries in the given directory, creating the
+    /// directory if it does not already exist.
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+
+        fs::create_dir_all(&dir)?;
+
+        Ok(Self { dir })
+    }
+
+    /// Get the directory entries are stored in.
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}", fnv1a(key.as_bytes())))
+    }
+
+    fn write(&self, key: &str, entry: &CacheEntry) -> io::Result<()> {
+        let path = self.path_for(key);
+        let tmp_path = path.with_extension(format!("tmp{}", fastrand::u32(..)));
+
+        // The key is stored alongside the entry to guard against hash
+        // collisions.
+        let mut file = fs::File::create(&tmp_path)?;
+        file.write_all(key.as_bytes())?;
+        file.write_all(b"\n")?;
+        file.write_all(&entry.to_bytes())?;
+        drop(file);
+
+        fs::rename(&tmp_path, &path).inspect_err(|_| {
+            let _ = fs::remove_file(&tmp_path);
+        })
+    }
+}
+
+impl CacheStore for DiskStore {
+    fn get(&self, key: &str) -> Option<CacheEntry> {
+        let bytes = fs::read(self.path_for(key)).ok()?;
+        let entry = bytes
+            .strip_prefix(key.as_bytes())
+            .and_then(|rest| rest.strip_prefix(b"\n"))?;
+
+        match CacheEntry::from_bytes(entry) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                tracing::warn!("failed to decode cache entry for {}: {}", key, e);
+                None
+            }
+        }
+    }
+
+    fn put(&self, key: &str, entry: CacheEntry) {
+        if let Err(e) = self.write(key, &entry) {
+            tracing::warn!("failed to write cache entry for {}: {}", key, e);
+        }
+    }
+
+    fn remove(&self, key: &str) {
+        let _ = fs::remove_file(self.path_for(key));
+    }
+}
+
+/// 64-bit FNV-1a hash, used to derive stable file names from keys.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::{HeaderMap, StatusCode, Version};
+    use std::time::SystemTime;
+
+    static_assertions::assert_impl_all!(MemoryStore: Send, Sync);
+    static_assertions::assert_impl_all!(DiskStore: Send, Sync);
+
+    fn entry(body: &str) -> CacheEntry {
+        CacheEntry::new(
+            StatusCode::OK,
+            Version::HTTP_11,
+            HeaderMap::new(),
+            HeaderMap::new(),
+            body.as_bytes().to_vec(),
+            SystemTime::now(),
+            SystemTime::now(),
+        )
+    }
+
+    #[test]
+    fn memory_store_evicts_least_recently_used() {
+        let store = MemoryStore::new(2);
+
+        store.put("a", entry("a"));
+        store.put("b", entry("b"));
+
+        // Use "a" so that "b" becomes the oldest.
+        assert!(store.get("a").is_some());
+
+        store.put("c", entry("c"));
+
+        assert_eq!(store.len(), 2);
+        assert!(store.get("a").is_some());
+        assert!(store.get("b").is_none());
+        assert!(store.get("c").is_some());
+    }
+
+    #[test]
+    fn memory_store_is_bounded_by_size() {
+        let store = MemoryStore::new(10);
+
+        store.put("a", entry("12345"));
+        store.put("b", entry("1234"));
+        assert_eq!(store.size(), 9);
+
+        // Storing "c" requires evicting "a" to make room.
+        store.put("c", entry("123"));
+        assert_eq!(store.size(), 7);
+        assert!(store.get("a").is_none());
+        assert!(store.get("b").is_some());
+
+        // Entries larger than the store are never stored.
+        store.put("d", entry("12345678901"));
+        assert!(store.get("d").is_none());
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn memory_store_replace_and_remove() {
+        let store = MemoryStore::new(2);
+
+        store.put("a", entry("1"));
+        store.put("a", entry("2"));
+
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.size(), 1);
+        assert_eq!(store.get("a").unwrap().body(), b"2");
+
+        store.remove("a");
+
+        assert!(store.is_empty());
+        assert_eq!(store.size(), 0);
+    }
+
+    #[test]
+    fn disk_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskStore::new(dir.path().join("cache")).unwrap();
+
+        assert!(store.get("a").is_none());
+
+        store.put("a", entry("hello"));
+        assert_eq!(store.get("a").unwrap().body(), b"hello");
+
+        // A second store on the same directory sees the same entries.
+        let other = DiskStore::new(store.path()).unwrap();
+        assert_eq!(other.get("a").unwrap().body(), b"hello");
+
+        store.remove("a");
+        assert!(other.get("a").is_none());
+    }
+}
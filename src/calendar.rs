@@ -0,0 +1,120 @@
+//! Conversions between dates in the proleptic Gregorian calendar and days
+//! since the Unix epoch, as needed for parsing and formatting timestamps.
+//!
+//! See <https://howardhinnant.github.io/date_algorithms.html> for how these
+//! work.
+
+/// Get the number of days since 1970-01-01 of the given date, or `None` if the
+/// date is invalid or too far away to be represented.
+pub(crate) fn days_from_civil(year: i64, month: u32, day: u32) -> Option<i64> {
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    let year = if month <= 2 {
+        year.checked_sub(1)?
+    } else {
+        year
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = (i64::from(month) + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era.checked_mul(146097)?
+        .checked_add(day_of_era)?
+        .checked_sub(719468)
+}
+
+/// Get the number of days in the given month of the given year.
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+/// Get the date that is the given number of days since 1970-01-01, as a year,
+/// month and day, or `None` if the date is too far away to be represented.
+pub(crate) fn civil_from_days(days: i64) -> Option<(i64, u32, u32)> {
+    let days = days.checked_add(719468)?;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = era
+        .checked_mul(400)?
+        .checked_add(year_of_era + i64::from(month <= 2))?;
+
+    Some((year, month, day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn known_dates() {
+        assert_eq!(days_from_civil(1970, 1, 1), Some(0));
+        assert_eq!(days_from_civil(2000, 3, 1), Some(11017));
+        assert_eq!(days_from_civil(1969, 12, 31), Some(-1));
+        assert_eq!(days_from_civil(0, 1, 1), Some(-719528));
+
+        assert_eq!(civil_from_days(0), Some((1970, 1, 1)));
+        assert_eq!(civil_from_days(11017), Some((2000, 3, 1)));
+        assert_eq!(civil_from_days(-1), Some((1969, 12, 31)));
+    }
+
+    #[test]
+    fn round_trip() {
+        for days in (-1_000_000..1_000_000).step_by(997) {
+            let (year, month, day) = civil_from_days(days).unwrap();
+
+            assert_eq!(days_from_civil(year, month, day), Some(days));
+        }
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        assert_eq!(days_from_civil(2000, 0, 1), None);
+        assert_eq!(days_from_civil(2000, 13, 1), None);
+        assert_eq!(days_from_civil(2000, 1, 0), None);
+        assert_eq!(days_from_civil(2000, 1, 32), None);
+        assert_eq!(days_from_civil(2000, 4, 31), None);
+    }
+
+    #[test]
+    fn february_length_depends_on_leap_year() {
+        assert_eq!(days_from_civil(2024, 2, 29), Some(19782));
+        assert_eq!(days_from_civil(2024, 2, 30), None);
+        assert_eq!(days_from_civil(2024, 2, 31), None);
+
+        assert_eq!(days_from_civil(2023, 2, 28), Some(19416));
+        assert_eq!(days_from_civil(2023, 2, 29), None);
+
+        assert_eq!(days_from_civil(1900, 2, 29), None);
+        assert!(days_from_civil(2000, 2, 29).is_some());
+    }
+
+    #[test]
+    fn out_of_range_dates_do_not_overflow() {
+        assert_eq!(days_from_civil(i64::MAX, 12, 31), None);
+        assert_eq!(days_from_civil(i64::MIN, 1, 1), None);
+        assert_eq!(civil_from_days(i64::MAX), None);
+    }
+}
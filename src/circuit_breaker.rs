@@ -0,0 +1,404 @@
+use crate::{
+    body::AsyncBody,
+    config::{CircuitBreaker, CircuitState, rate_limit::authority_key},
+    error::{Error, ErrorKind},
+    interceptor::{Context, Interceptor, InterceptorFuture},
+};
+use http::Request;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+    time::Instant,
+};
+
+/// Number of servers to track before healthy circuits are pruned.
+const PRUNE_THRESHOLD: usize = 256;
+
+/// Interceptor that fails requests immediately while the circuit for their
+/// server is open.
+pub(crate) struct CircuitBreakerInterceptor {
+    breaker: CircuitBreaker,
+    circuits: Mutex<HashMap<String, Circuit>>,
+}
+
+/// Circuit state for a single server.
+struct Circuit {
+    state: State,
+
+    /// Incremented on every state change, so that outcomes of requests
+    /// admitted in an earlier state can be ignored.
+    generation: u64,
+}
+
+enum State {
+    Closed {
+        /// Outcomes of the most recent requests, `true` for failures.
+        outcomes: VecDeque<bool>,
+        failures: usize,
+    },
+    Open {
+        until: Instant,
+    },
+    HalfOpen {
+        in_flight: usize,
+        successes: usize,
+    },
+}
+
+impl State {
+    fn closed() -> Self {
+        Self::Closed {
+            outcomes: VecDeque::new(),
+            failures: 0,
+        }
+    }
+
+    /// Whether this state can be forgotten without changing the behavior of
+    /// the circuit, since a new circuit would start out the same.
+    fn is_healthy(&self) -> bool {
+        matches!(self, Self::Closed { failures: 0, .. })
+    }
+
+    fn kind(&self) -> CircuitState {
+        match self {
+            Self::Closed { .. } => CircuitState::Closed,
+            Self::Open { .. } => CircuitState::Open,
+            Self::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Outcome {
+    Success,
+    Failure,
+
+    /// The request failed for a reason unrelated to the server's health, or
+    /// was cancelled.
+    Ignored,
+}
+
+impl CircuitBreakerInterceptor {
+    pub(crate) fn new(breaker: CircuitBreaker) -> Self {
+        Self {
+            breaker,
+            circuits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Check whether a request may be sent, returning the generation it was
+    /// admitted in.
+    fn admit(&self, key: &str) -> Option<u64> {
+        let mut circuits = self.circuits.lock().unwrap();
+
+        if circuits.len() >= PRUNE_THRESHOLD && !circuits.contains_key(key) {
+            circuits.retain(|_, circuit| !circuit.state.is_healthy());
+        }
+
+        let circuit = circuits.entry(key.to_owned()).or_insert_with(|| Circuit {
+            state: State::closed(),
+            generation: 0,
+        });
+        let mut transition = None;
+
+        if let State::Open { until } = circuit.state {
+            if Instant::now() < until {
+                return None;
+            }
+
+            transition = Some(self.transition(
+                circuit,
+                State::HalfOpen {
+                    in_flight: 0,
+                    successes: 0,
+                },
+            ));
+        }
+
+        let admitted = match &mut circuit.state {
+            State::Closed { .. } => Some(circuit.generation),
+            State::HalfOpen {
+                in_flight,
+                successes,
+            } if *in_flight + *successes < self.breaker.probes() => {
+                *in_flight += 1;
+                Some(circuit.generation)
+            }
+            _ => None,
+        };
+
+        drop(circuits);
+
+        if let Some((from, to)) = transition {
+            self.breaker.notify(key, from, to);
+        }
+
+        admitted
+    }
+
+    /// Record the outcome of a request admitted in the given generation.
+    fn record(&self, key: &str, generation: u64, outcome: Outcome) {
+        let mut circuits = self.circuits.lock().unwrap();
+        let circuit = match circuits.get_mut(key) {
+            Some(circuit) if circuit.generation == generation => circuit,
+            _ => return,
+        };
+
+        let next = match &mut circuit.state {
+            State::Closed { outcomes, failures } => {
+                if outcome == Outcome::Ignored {
+                    return;
+                }
+
+                outcomes.push_back(outcome == Outcome::Failure);
+                *failures += usize::from(outcome == Outcome::Failure);
+
+                if outcomes.len() > self.breaker.window_size() && outcomes.pop_front() == Some(true)
+                {
+                    *failures -= 1;
+                }
+
+                if !self.breaker.should_open(outcomes.len(), *failures) {
+                    return;
+                }
+
+                self.open_state()
+            }
+            State::HalfOpen {
+                in_flight,
+                successes,
+            } => {
+                *in_flight -= 1;
+
+                match outcome {
+                    Outcome::Failure => self.open_state(),
+                    Outcome::Success => {
+                        *successes += 1;
+
+                        if *successes < self.breaker.probes() {
+                            return;
+                        }
+
+                        State::closed()
+                    }
+                    Outcome::Ignored => return,
+                }
+            }
+            State::Open { .. } => return,
+        };
+
+        let (from, to) = self.transition(circuit, next);
+        drop(circuits);
+
+        self.breaker.notify(key, from, to);
+    }
+
+    fn open_state(&self) -> State {
+        State::Open {
+            until: Instant::now() + self.breaker.cooldown_duration(),
+        }
+    }
+
+    fn transition(&self, circuit: &mut Circuit, state: State) -> (CircuitState, CircuitState) {
+        let from = std::mem::replace(&mut circuit.state, state).kind();
+        circuit.generation += 1;
+
+        (from, circuit.state.kind())
+    }
+}
+
+impl Interceptor for CircuitBreakerInterceptor {
+    type Err = Error;
+
+    fn intercept<'a>(
+        &'a self,
+        request: Request<AsyncBody>,
+        ctx: Context<'a>,
+    ) -> InterceptorFuture<'a, Self::Err> {
+        Box::pin(async move {
+            let key = match authority_key(request.uri()) {
+                Some(key) => key,
+                None => return ctx.send(request).await,
+            };
+
+            let generation = match self.admit(&key) {
+                Some(generation) => generation,
+                None => {
+                    tracing::debug!(authority = %key, "circuit open, failing request");
+                    return Err(ErrorKind::CircuitOpen.into());
+                }
+            };
+
+            // Make sure the outcome is recorded even if the request is
+            // cancelled, so that a half-open probe slot is not leaked.
+            let mut guard = RecordGuard {
+                interceptor: self,
+                key,
+                generation,
+                outcome: Outcome::Ignored,
+            };
+
+            let result = ctx.send(request).await;
+
+            guard.outcome = match &result {
+                Ok(response) if response.status().is_server_error() => Outcome::Failure,
+                Ok(_) => Outcome::Success,
+                Err(e) if e.is_network() || e.is_timeout() => Outcome::Failure,
+                Err(_) => Outcome::Ignored,
+            };
+
+            result
+        })
+    }
+}
+
+struct RecordGuard<'a> {
+    interceptor: &'a CircuitBreakerInterceptor,
+    key: String,
+    generation: u64,
+    outcome: Outcome,
+}
+
+impl Drop for RecordGuard<'_> {
+    fn drop(&mut self) {
+        self.interceptor
+            .record(&self.key, self.generation, self.outcome);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn interceptor(breaker: CircuitBreaker) -> CircuitBreakerInterceptor {
+        CircuitBreakerInterceptor::new(breaker)
+    }
+
+    #[test]
+    fn opens_after_failure_rate_exceeds_threshold() {
+        let interceptor = interceptor(
+            CircuitBreaker::new()
+                .window(4)
+                .minimum_requests(4)
+                .failure_threshold(0.5),
+        );
+
+        for outcome in [Outcome::Success, Outcome::Success, Outcome::Failure] {
+            let generation = interceptor.admit("a").unwrap();
+            interceptor.record("a", generation, outcome);
+        }
+
+        // Ignored outcomes do not count towards the window.
+        let generation = interceptor.admit("a").unwrap();
+        interceptor.record("a", generation, Outcome::Ignored);
+        assert!(interceptor.admit("a").is_some());
+
+        interceptor.record("a", generation, Outcome::Failure);
+        assert!(interceptor.admit("a").is_none());
+
+        // Other servers are unaffected.
+        assert!(interceptor.admit("b").is_some());
+    }
+
+    #[test]
+    fn old_failures_slide_out_of_window() {
+        let interceptor = interceptor(
+            CircuitBreaker::new()
+                .window(2)
+                .minimum_requests(2)
+                .failure_threshold(1.0),
+        );
+
+        for outcome in [Outcome::Failure, Outcome::Success, Outcome::Failure] {
+            let generation = interceptor.admit("a").unwrap();
+            interceptor.record("a", generation, outcome);
+        }
+
+        assert!(interceptor.admit("a").is_some());
+    }
+
+    #[test]
+    fn half_open_allows_limited_probes() {
+        let interceptor = interceptor(
+            CircuitBreaker::new()
+                .minimum_requests(1)
+                .cooldown(Duration::ZERO)
+                .half_open_probes(2),
+        );
+
+        let generation = interceptor.admit("a").unwrap();
+        interceptor.record("a", generation, Outcome::Failure);
+
+        let probe1 = interceptor.admit("a").unwrap();
+        let probe2 = interceptor.admit("a").unwrap();
+        assert_eq!(probe1, probe2);
+        assert!(interceptor.admit("a").is_none());
+
+        // A cancelled probe frees up its slot.
+        interceptor.record("a", probe2, Outcome::Ignored);
+        let probe2 = interceptor.admit("a").unwrap();
+
+        interceptor.record("a", probe1, Outcome::Success);
+        assert!(interceptor.admit("a").is_none());
+        interceptor.record("a", probe2, Outcome::Success);
+
+        let generation = interceptor.admit("a").unwrap();
+        assert!(generation > probe2);
+    }
+
+    #[test]
+    fn healthy_circuits_are_pruned() {
+        let interceptor = interceptor(
+            CircuitBreaker::new()
+                .window(4)
+                .minimum_requests(1)
+                .failure_threshold(1.0),
+        );
+
+        let generation = interceptor.admit("open").unwrap();
+        interceptor.record("open", generation, Outcome::Failure);
+
+        for outcome in [Outcome::Success, Outcome::Failure] {
+            let generation = interceptor.admit("failing").unwrap();
+            interceptor.record("failing", generation, outcome);
+        }
+
+        for i in 2..PRUNE_THRESHOLD {
+            let key = i.to_string();
+            let generation = interceptor.admit(&key).unwrap();
+            interceptor.record(&key, generation, Outcome::Success);
+        }
+
+        assert_eq!(interceptor.circuits.lock().unwrap().len(), PRUNE_THRESHOLD);
+
+        interceptor.admit("new").unwrap();
+
+        let circuits = interceptor.circuits.lock().unwrap();
+        assert_eq!(circuits.len(), 3);
+        assert!(circuits.contains_key("open"));
+        assert!(circuits.contains_key("failing"));
+        assert!(circuits.contains_key("new"));
+    }
+
+    #[test]
+    fn failed_probe_reopens_circuit() {
+        let interceptor = interceptor(
+            CircuitBreaker::new()
+                .minimum_requests(1)
+                .cooldown(Duration::ZERO),
+        );
+
+        let generation = interceptor.admit("a").unwrap();
+        interceptor.record("a", generation, Outcome::Failure);
+
+        let probe = interceptor.admit("a").unwrap();
+        interceptor.record("a", probe, Outcome::Failure);
+
+        // Stale outcomes from before the circuit reopened are ignored.
+        interceptor.record("a", generation, Outcome::Success);
+
+        let probe2 = interceptor.admit("a").unwrap();
+        assert!(probe2 > probe);
+    }
+}
@@ -3,6 +3,7 @@
 use crate::{
     agent::{self, AgentBuilder},
     body::{AsyncBody, Body},
+    conditional::Validators,
     config::{
         client::ClientConfig,
         request::{RequestConfig, WithRequestConfig},
@@ -77,6 +78,11 @@ pub struct HttpClientBuilder {
     interceptors: Vec<InterceptorObj>,
     default_headers: HeaderMap<HeaderValue>,
     error: Option<Error>,
+    cache: Option<Arc<dyn crate::cache::CacheStore>>,
+    cache_max_entry_size: u64,
+    circuit_breaker: Option<crate::config::CircuitBreaker>,
+    rate_limits: Vec<crate::config::RateLimit>,
+    hsts: Option<crate::hsts::HstsStore>,
 
     #[cfg(feature = "cookies")]
     cookie_jar: Option<crate::cookies::CookieJar>,
@@ -104,9 +110,21 @@ impl HttpClientBuilder {
                 // not enable redirect following, it just implements support for
                 // it, if a request asks for it.
                 InterceptorObj::new(crate::redirect::RedirectInterceptor),
+                // Add retry support. This comes right after redirects so that
+                // each hop of a redirect chain is retried individually. Like
+                // redirects, this does nothing unless a retry policy is set.
+                InterceptorObj::new(crate::retry::RetryInterceptor),
+                // Add hedging support inside of retries, so that a retry is
+                // only attempted once every hedged request has failed.
+                InterceptorObj::new(crate::hedge::HedgeInterceptor),
             ],
             default_headers: HeaderMap::new(),
             error: None,
+            cache: None,
+            cache_max_entry_size: crate::cache::interceptor::DEFAULT_MAX_ENTRY_SIZE,
+            circuit_breaker: None,
+            rate_limits: Vec::new(),
+            hsts: None,
 
             #[cfg(feature = "cookies")]
             cookie_jar: None,
@@ -151,6 +169,120 @@ impl HttpClientBuilder {
         self.cookie_jar(Default::default())
     }
 
+    /// Enable caching of responses for all requests using this client, keeping
+    /// cached responses in the given store.
+    ///
+    /// See the [`cache`](crate::cache) module for details on which responses
+    /// are cached and for how long.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{cache::MemoryStore, prelude::*, HttpClient};
+    ///
+    /// let client = HttpClient::builder()
+    ///     // Keep up to 64 MiB of responses in memory.
+    ///     .cache(MemoryStore::new(64 * 1024 * 1024))
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn cache(mut self, store: impl crate::cache::CacheStore + 'static) -> Self {
+        self.cache = Some(Arc::new(store));
+        self
+    }
+
+    /// Set the maximum size in bytes of a response body that may be stored in
+    /// the cache.
+    ///
+    /// Responses with larger bodies are still returned as usual, but are not
+    /// stored. The body is no longer copied once it exceeds this size, so
+    /// large downloads do not need to be kept in memory. The default is 8 MiB.
+    /// This has no effect unless a cache is enabled using
+    /// [`HttpClientBuilder::cache`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{cache::MemoryStore, prelude::*, HttpClient};
+    ///
+    /// let client = HttpClient::builder()
+    ///     .cache(MemoryStore::new(64 * 1024 * 1024))
+    ///     .cache_max_entry_size(1024 * 1024)
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn cache_max_entry_size(mut self, size: u64) -> Self {
+        self.cache_max_entry_size = size;
+        self
+    }
+
+    /// Enable a circuit breaker that makes requests to servers that appear to
+    /// be down fail immediately, instead of each request waiting for the
+    /// connection to fail or time out.
+    ///
+    /// See [`CircuitBreaker`](crate::config::CircuitBreaker) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{config::CircuitBreaker, prelude::*, HttpClient};
+    ///
+    /// let client = HttpClient::builder()
+    ///     .circuit_breaker(CircuitBreaker::new())
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn circuit_breaker(mut self, breaker: crate::config::CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Enable HTTP Strict Transport Security using the given store.
+    ///
+    /// `Strict-Transport-Security` headers received from servers are recorded
+    /// in the store, and requests to `http` URIs of hosts in the store are
+    /// upgraded to `https` before being sent, including when following
+    /// redirects. See the [`hsts`](crate::hsts) module for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{hsts::HstsStore, prelude::*, HttpClient};
+    ///
+    /// let client = HttpClient::builder()
+    ///     .hsts(HstsStore::new())
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn hsts(mut self, store: crate::hsts::HstsStore) -> Self {
+        self.hsts = Some(store);
+        self
+    }
+
+    /// Limit the number of requests sent by this client using the given rate
+    /// limit.
+    ///
+    /// Requests exceeding the limit wait asynchronously until they are
+    /// permitted rather than failing. This method can be called multiple
+    /// times, in which case a request must be permitted by every limit before
+    /// it is sent. See [`RateLimit`](crate::config::RateLimit) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{config::RateLimit, prelude::*, HttpClient};
+    /// use std::time::Duration;
+    ///
+    /// let client = HttpClient::builder()
+    ///     .rate_limit(RateLimit::new().requests_per(5, Duration::from_secs(1)))
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn rate_limit(mut self, limit: crate::config::RateLimit) -> Self {
+        self.rate_limits.push(limit);
+        self
+    }
+
     /// Add a request interceptor to the client.
     ///
     /// # Availability
@@ -314,6 +446,142 @@ impl HttpClientBuilder {
         self
     }
 
+    /// Use a custom resolver to look up the addresses to connect to, instead
+    /// of curl's built-in name resolution.
+    ///
+    /// The resolver is consulted before each request is sent, including every
+    /// retry and every redirect that is followed, and the addresses it returns
+    /// are used to connect to the request's host. If the resolver fails, the
+    /// request fails with an error of kind
+    /// [`ErrorKind::NameResolution`](crate::error::ErrorKind::NameResolution).
+    ///
+    /// If the resolver returns addresses using a port other than the one
+    /// requested, connections are made to that port instead, which allows
+    /// service discovery systems to pick the port of a service. Since only one
+    /// port can be used, any addresses using a different port than the first
+    /// address are ignored.
+    ///
+    /// Requests to IP addresses, requests using a custom
+    /// [`Dialer`](crate::config::Dialer), and requests sent through a proxy,
+    /// including proxies set using the proxy environment variables, are not
+    /// resolved, as the proxy looks up the host itself. Entries added using
+    /// [`HttpClientBuilder::dns_resolve`] are still used for hosts that are
+    /// not resolved, but the resolver takes precedence. Requests configured to use
+    /// [DNS over HTTPS](crate::config::Configurable::dns_over_https) are
+    /// resolved using that instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{
+    ///     net::{Resolve, StaticResolver, SystemResolver},
+    ///     prelude::*,
+    ///     HttpClient,
+    /// };
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let client = HttpClient::builder()
+    ///     .resolver(
+    ///         StaticResolver::new()
+    ///             .add("internal.example.org", 443, Ipv4Addr::new(10, 0, 0, 1))
+    ///             .or(SystemResolver),
+    ///     )
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn resolver(mut self, resolver: impl crate::net::Resolve + 'static) -> Self {
+        self.client_config.resolver = Some(crate::net::SharedResolver::new(resolver));
+        self
+    }
+
+    /// Remember alternative services advertised by servers using the `Alt-Svc`
+    /// header in the given cache, and connect to them for later requests.
+    ///
+    /// By default, `Alt-Svc` headers are ignored. See
+    /// [`AltSvcCache`](crate::config::AltSvcCache) for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{
+    ///     config::{AltSvcCache, VersionNegotiation},
+    ///     prelude::*,
+    ///     HttpClient,
+    /// };
+    ///
+    /// let client = HttpClient::builder()
+    ///     .alt_svc(AltSvcCache::new())
+    ///     .version_negotiation(VersionNegotiation::prefer_http3())
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn alt_svc(mut self, cache: crate::config::AltSvcCache) -> Self {
+        self.client_config.alt_svc = Some(cache);
+        self
+    }
+
+    /// Choose the client certificate to present for each request using a
+    /// function, for clients that need to authenticate differently depending
+    /// on the server being called, such as when calling multiple tenants
+    /// requiring mutual TLS.
+    ///
+    /// The function is called with the URI of every request when it is about
+    /// to be sent, including every retry and every redirect that is followed.
+    /// If it returns an identity, that identity is used for the request
+    /// instead of any identity set for the client using
+    /// [`TlsConfigBuilder::identity`]. If it returns `None`, the identity from
+    /// the TLS configuration of the client is used, if any. An identity set in
+    /// the TLS configuration of an individual request is more specific, and
+    /// so always takes precedence; the function is not called for such
+    /// requests.
+    ///
+    /// Connections are only reused for requests presenting the same client
+    /// certificate, so a connection authenticated using one identity is never
+    /// used to send a request on behalf of another.
+    ///
+    /// The function is called on the thread sending the request, and should
+    /// return quickly. Identities are cheap to clone, so it is best to create
+    /// them all up front and look them up in the function.
+    ///
+    /// [`TlsConfigBuilder::identity`]: crate::tls::TlsConfigBuilder::identity
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{prelude::*, tls::{Identity, PrivateKey}, HttpClient};
+    /// use std::collections::HashMap;
+    ///
+    /// let identity = |name| Identity::from_pem_file(
+    ///     format!("{}.pem", name),
+    ///     Some(PrivateKey::from_pem_file(format!("{}.key", name), None)),
+    /// );
+    ///
+    /// let identities = HashMap::from([
+    ///     ("tenant-a.example.org", identity("tenant-a")),
+    ///     ("tenant-b.example.org", identity("tenant-b")),
+    /// ]);
+    ///
+    /// let client = HttpClient::builder()
+    ///     .identity_resolver(move |uri| {
+    ///         uri.host().and_then(|host| identities.get(host)).cloned()
+    ///     })
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    ///
+    /// # Availability
+    ///
+    /// This method is only available when the [`tls`](index.html#tls) feature
+    /// is enabled.
+    #[cfg(feature = "tls")]
+    pub fn identity_resolver<F>(mut self, resolver: F) -> Self
+    where
+        F: Fn(&http::Uri) -> Option<crate::tls::Identity> + Send + Sync + 'static,
+    {
+        self.client_config.identity_resolver = Some(crate::tls::IdentityResolver::new(resolver));
+        self
+    }
+
     /// Add a default header to be passed with every request.
     ///
     /// If a default header value is already defined for the given key, then a
@@ -440,6 +708,12 @@ impl HttpClientBuilder {
             return Err(err);
         }
 
+        // Upgrade requests to HTTPS before anything else inspects their URI,
+        // such as when selecting secure cookies.
+        if let Some(store) = self.hsts.clone() {
+            self = self.interceptor_impl(crate::hsts::interceptor::HstsInterceptor::new(store));
+        }
+
         // Add cookie interceptor if enabled.
         #[cfg(feature = "cookies")]
         {
@@ -453,6 +727,29 @@ impl HttpClientBuilder {
             self = self.interceptor_impl(DefaultHeadersInterceptor::from(default_headers));
         }
 
+        // Add the cache interceptor last so that it sees the final request
+        // headers when matching `Vary`.
+        if let Some(store) = self.cache.take() {
+            let max_entry_size = self.cache_max_entry_size;
+            self = self.interceptor_impl(crate::cache::interceptor::CacheInterceptor::new(
+                store,
+                max_entry_size,
+            ));
+        }
+
+        // The circuit breaker and rate limits come after the cache so that
+        // only requests actually sent over the network are affected. Open
+        // circuits fail fast without waiting for a rate limit.
+        if let Some(breaker) = self.circuit_breaker.take() {
+            self = self.interceptor_impl(crate::circuit_breaker::CircuitBreakerInterceptor::new(
+                breaker,
+            ));
+        }
+
+        for limit in std::mem::take(&mut self.rate_limits) {
+            self = self.interceptor_impl(crate::rate_limit::RateLimitInterceptor::new(limit));
+        }
+
         #[cfg(not(feature = "cookies"))]
         let inner = Inner {
             agent: self
@@ -462,6 +759,7 @@ impl HttpClientBuilder {
             client_config: self.client_config,
             request_config: self.request_config,
             interceptors: self.interceptors,
+            hsts: self.hsts,
         };
 
         #[cfg(feature = "cookies")]
@@ -473,6 +771,7 @@ impl HttpClientBuilder {
             client_config: self.client_config,
             request_config: self.request_config,
             interceptors: self.interceptors,
+            hsts: self.hsts,
             cookie_jar: self.cookie_jar,
         };
 
@@ -611,6 +910,9 @@ struct Inner {
     /// Registered interceptors that requests should pass through.
     interceptors: Vec<InterceptorObj>,
 
+    /// Configured HSTS store, if any.
+    hsts: Option<crate::hsts::HstsStore>,
+
     /// Configured cookie jar, if any.
     #[cfg(feature = "cookies")]
     cookie_jar: Option<crate::cookies::CookieJar>,
@@ -639,6 +941,11 @@ impl HttpClient {
         HttpClientBuilder::default()
     }
 
+    /// Get the configured HSTS store for this HTTP client, if any.
+    pub fn hsts(&self) -> Option<&crate::hsts::HstsStore> {
+        self.inner.hsts.as_ref()
+    }
+
     /// Get the configured cookie jar for this HTTP client, if any.
     ///
     /// # Availability
@@ -860,6 +1167,77 @@ impl HttpClient {
         }
     }
 
+    /// Send a conditional GET request to the given URI, returning a response
+    /// only if the resource has changed since the given validators were
+    /// captured.
+    ///
+    /// Any known validators are sent using the `If-None-Match` and
+    /// `If-Modified-Since` request headers. If the server responds with `304
+    /// Not Modified`, then `None` is returned and any validators included in
+    /// the response are updated. Otherwise the response is returned, and if it
+    /// was successful the validators are replaced with those of the new
+    /// version of the resource. Unsuccessful responses are returned as-is
+    /// without changing the validators.
+    ///
+    /// To execute the request asynchronously, see
+    /// [`HttpClient::fetch_if_changed_async`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use isahc::{prelude::*, HttpClient, Validators};
+    ///
+    /// let client = HttpClient::new()?;
+    /// let mut validators = Validators::new();
+    ///
+    /// loop {
+    ///     if let Some(mut response) = client.fetch_if_changed("https://example.org", &mut validators)? {
+    ///         println!("{}", response.text()?);
+    ///     }
+    ///
+    ///     std::thread::sleep(std::time::Duration::from_secs(60));
+    /// }
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    pub fn fetch_if_changed<U>(
+        &self,
+        uri: U,
+        validators: &mut Validators,
+    ) -> Result<Option<Response<Body>>, Error>
+    where
+        http::Uri: TryFrom<U>,
+        <http::Uri as TryFrom<U>>::Error: Into<http::Error>,
+    {
+        match validators.apply(http::Request::get(uri)).body(()) {
+            Ok(request) => self.send(request).map(|response| validators.refresh(response)),
+            Err(e) => Err(Error::from_any(e)),
+        }
+    }
+
+    /// Send a conditional GET request to the given URI asynchronously,
+    /// returning a response only if the resource has changed since the given
+    /// validators were captured.
+    ///
+    /// This is the async version of [`HttpClient::fetch_if_changed`].
+    pub async fn fetch_if_changed_async<U>(
+        &self,
+        uri: U,
+        validators: &mut Validators,
+    ) -> Result<Option<Response<AsyncBody>>, Error>
+    where
+        http::Uri: TryFrom<U>,
+        <http::Uri as TryFrom<U>>::Error: Into<http::Error>,
+    {
+        let request = validators
+            .apply(http::Request::get(uri))
+            .body(())
+            .map_err(Error::from_any)?;
+
+        self.send_async(request)
+            .await
+            .map(|response| validators.refresh(response))
+    }
+
     /// Send an HTTP request and return the HTTP response.
     ///
     /// Upon success, will return a [`Response`] containing the status code,
@@ -1019,6 +1397,16 @@ impl HttpClient {
     ) -> Result<Response<AsyncBody>, Error> {
         // Populate request config, creating if necessary.
         if let Some(config) = request.extensions_mut().get_mut::<RequestConfig>() {
+            #[cfg(feature = "tls")]
+            if config.has_own_identity.is_none() {
+                config.has_own_identity = Some(
+                    config
+                        .tls_config
+                        .as_ref()
+                        .is_some_and(crate::tls::TlsConfig::has_identity),
+                );
+            }
+
             // Merge request configuration with defaults.
             config.merge(&self.inner.request_config);
         } else {
@@ -1027,6 +1415,9 @@ impl HttpClient {
                 .insert(self.inner.request_config.clone());
         }
 
+        // Set conditional headers before any interceptors see the request.
+        crate::conditional::set_headers(&mut request)?;
+
         let ctx = interceptor::Context {
             invoker: Arc::new(self),
             interceptors: &self.inner.interceptors,
@@ -1038,6 +1429,7 @@ impl HttpClient {
     fn create_easy_handle(
         &self,
         mut request: Request<AsyncBody>,
+        socket: Option<crate::net::Socket>,
     ) -> Result<
         (
             curl::easy::Easy2<RequestHandler>,
@@ -1049,6 +1441,15 @@ impl HttpClient {
         let body = std::mem::take(request.body_mut());
         let has_body = !body.is_empty();
         let body_length = body.len();
+
+        // Use the content type implied by the body if the request does not
+        // already specify one.
+        if let Some(content_type) = body.content_type() {
+            request
+                .headers_mut()
+                .entry(http::header::CONTENT_TYPE)
+                .or_insert_with(|| content_type.clone());
+        }
         let (handler, future) = RequestHandler::new(body);
 
         let mut easy = curl::easy::Easy2::new(handler);
@@ -1063,11 +1464,55 @@ impl HttpClient {
 
         easy.signal(false)?;
 
+        // Let the client pick the identity to authenticate this request with,
+        // unless the request sets one itself.
+        #[cfg(feature = "tls")]
+        if let Some(resolver) = self.inner.client_config.identity_resolver.as_ref() {
+            let uri = request.uri().clone();
+            let config = request.extensions_mut().get_mut::<RequestConfig>().unwrap();
+
+            if config.has_own_identity != Some(true) {
+                if let Some(identity) = resolver.resolve(&uri) {
+                    config
+                        .tls_config
+                        .get_or_insert_with(Default::default)
+                        .set_identity(identity);
+                }
+            }
+        }
+
         let request_config = request.extensions().get::<RequestConfig>().unwrap();
 
         request_config.set_opt(&mut easy)?;
         self.inner.client_config.set_opt(&mut easy)?;
 
+        if let Some(resolved) = request.extensions().get::<crate::net::Resolved>() {
+            resolved.set_opt(&mut easy)?;
+        }
+
+        // Pick the proxy to use for this request, if configured.
+        let proxy = match request_config.proxy_config.as_ref() {
+            Some(proxy_config) => {
+                let proxy = proxy_config.select(request.uri());
+
+                // Set both options explicitly so that curl does not fall back
+                // to the environment.
+                easy.proxy(&proxy.as_ref().map(http::Uri::to_string).unwrap_or_default())?;
+                easy.noproxy("")?;
+
+                proxy
+            }
+            None => request_config.proxy.clone().flatten(),
+        };
+
+        easy.get_mut().proxy = proxy;
+
+        // Connect using the socket opened by a custom dialer, if any.
+        if let Some(socket) = socket {
+            crate::net::Connected::new(&socket).set_opt(&mut easy)?;
+            easy.get_mut().socket = Some(socket);
+        }
+
         // Check if we need to disable the Expect header.
         let disable_expect_header = request_config
             .expect_continue
@@ -1180,8 +1625,58 @@ impl crate::interceptor::Invoke for &HttpClient {
                 .automatic_decompression
                 .unwrap_or(false);
 
+            // Look up the addresses to connect to using DNS over HTTPS or the
+            // client's resolver, unless the request is being sent somewhere
+            // specific. Requests sent through a proxy are not resolved, since
+            // the proxy connects to the host instead.
+            let config = request.extensions().get::<RequestConfig>().unwrap();
+            let doh = config.dns_over_https.clone();
+            let resolver = match doh.as_ref() {
+                Some(doh) => Some(doh as &dyn crate::net::Resolve),
+                None => self
+                    .inner
+                    .client_config
+                    .resolver
+                    .as_ref()
+                    .map(|resolver| resolver as &dyn crate::net::Resolve),
+            };
+
+            if let Some(resolver) = resolver.filter(|_| {
+                config.dial.as_ref().is_none_or(Dialer::is_default)
+                    && config.proxy_for(request.uri()).is_none()
+            }) {
+                let resolved = crate::net::resolve_uri(
+                    resolver,
+                    request.uri(),
+                    self.inner.client_config.dns_resolve.as_ref(),
+                )
+                .await?;
+
+                if let Some(resolved) = resolved {
+                    request.extensions_mut().insert(resolved);
+                }
+            }
+
+            // Let a custom dialer open the connection for this request.
+            let config = request.extensions().get::<RequestConfig>().unwrap();
+            let dial = config
+                .dial
+                .as_ref()
+                .and_then(|dialer| dialer.dial(request.uri(), config.connect_timeout));
+
+            let socket = match dial {
+                Some(dial) => Some(dial.await.map_err(|e| {
+                    Error::with_context(
+                        ErrorKind::ConnectionFailed,
+                        Some(format!("failed to dial {}", request.uri())),
+                        e,
+                    )
+                })?),
+                None => None,
+            };
+
             // Create and configure a curl easy handle to fulfil the request.
-            let (easy, future) = self.create_easy_handle(request)?;
+            let (easy, future) = self.create_easy_handle(request, socket)?;
 
             // Send the request to the agent to be executed.
             self.inner.agent.submit_request(easy)?;
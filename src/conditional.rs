@@ -0,0 +1,271 @@
+//! Support for conditional requests.
+
+use crate::{
+    body::AsyncBody,
+    config::{Configurable, request::RequestConfig},
+    error::{Error, ErrorKind},
+};
+use http::{
+    HeaderValue, Request, Response, StatusCode,
+    header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+};
+use std::{
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Validators identifying a particular version of a resource, used to make
+/// conditional requests.
+///
+/// Validators are captured from the `ETag` and `Last-Modified` headers of a
+/// response. When requesting the same resource again, they can be sent back
+/// to the server using
+/// [`Configurable::if_none_match`](crate::config::Configurable::if_none_match)
+/// and
+/// [`Configurable::if_modified_since`](crate::config::Configurable::if_modified_since),
+/// and the server will respond with `304 Not Modified` and no body if the
+/// resource has not changed since.
+///
+/// To poll a resource for changes, the easiest way is to use
+/// [`HttpClient::fetch_if_changed`](crate::HttpClient::fetch_if_changed),
+/// which sends the validators and keeps them up to date automatically.
+///
+/// # Examples
+///
+/// ```no_run
+/// use isahc::{prelude::*, HttpClient, Validators};
+///
+/// let client = HttpClient::new()?;
+/// let mut validators = Validators::new();
+///
+/// // The first fetch always returns a response.
+/// if let Some(mut response) = client.fetch_if_changed("https://example.org", &mut validators)? {
+///     println!("{}", response.text()?);
+/// }
+///
+/// // Later fetches only return a response if the resource has changed.
+/// if client.fetch_if_changed("https://example.org", &mut validators)?.is_none() {
+///     println!("not modified");
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Validators {
+    etag: Option<String>,
+    last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Create an empty set of validators. Requests made using empty validators
+    /// are not conditional.
+    pub const fn new() -> Self {
+        Self {
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Capture the validators of the given response.
+    pub fn from_response<T>(response: &Response<T>) -> Self {
+        let mut validators = Self::new();
+        validators.update(response);
+        validators
+    }
+
+    /// Get the entity tag of the resource, including quotes and any weakness
+    /// indicator, if known.
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    /// Get the time the resource was last modified, if known.
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.last_modified
+    }
+
+    /// Returns true if no validators are known.
+    pub fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+
+    /// Update the validators with any present in the given response, keeping
+    /// any that are not.
+    ///
+    /// This is appropriate for a `304 Not Modified` response, which might only
+    /// include some of the validators. For a new version of a resource, use
+    /// [`Validators::from_response`] to replace them all instead.
+    pub fn update<T>(&mut self, response: &Response<T>) {
+        if let Some(etag) = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+        {
+            self.etag = Some(etag.to_owned());
+        }
+
+        if let Some(time) = response
+            .headers()
+            .get(LAST_MODIFIED)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| httpdate::parse_http_date(value).ok())
+        {
+            self.last_modified = Some(time);
+        }
+    }
+
+    /// Configure a request to only be fulfilled if the resource no longer
+    /// matches these validators.
+    pub(crate) fn apply<C: Configurable>(&self, mut configurable: C) -> C {
+        if let Some(etag) = self.etag.as_ref() {
+            configurable = configurable.if_none_match(etag.as_str());
+        }
+
+        if let Some(time) = self.last_modified {
+            configurable = configurable.if_modified_since(time);
+        }
+
+        configurable
+    }
+
+    /// Process the response to a conditional request made with these
+    /// validators, returning the response only if the resource has changed.
+    pub(crate) fn refresh<T>(&mut self, response: Response<T>) -> Option<Response<T>> {
+        if response.status() == StatusCode::NOT_MODIFIED {
+            self.update(&response);
+            None
+        } else {
+            if response.status().is_success() {
+                *self = Self::from_response(&response);
+            }
+
+            Some(response)
+        }
+    }
+}
+
+/// Add any conditional headers specified in the request configuration, unless
+/// the request already has them.
+pub(crate) fn set_headers(request: &mut Request<AsyncBody>) -> Result<(), Error> {
+    let Some(config) = request.extensions().get::<RequestConfig>() else {
+        return Ok(());
+    };
+
+    let if_none_match = config
+        .if_none_match
+        .as_deref()
+        .map(HeaderValue::from_str)
+        .transpose()
+        .map_err(|e| Error::new(ErrorKind::InvalidRequest, e))?;
+
+    let if_modified_since = config
+        .if_modified_since
+        .map(fmt_http_date)
+        .transpose()
+        .map_err(|e| Error::new(ErrorKind::InvalidRequest, e))?;
+
+    let headers = request.headers_mut();
+
+    if let Some(value) = if_none_match {
+        headers.entry(IF_NONE_MATCH).or_insert(value);
+    }
+
+    if let Some(value) = if_modified_since {
+        headers.entry(IF_MODIFIED_SINCE).or_insert(value);
+    }
+
+    Ok(())
+}
+
+/// Format a time as an HTTP date, which can only represent times from 1970 up
+/// to the end of the year 9999.
+fn fmt_http_date(time: SystemTime) -> Result<HeaderValue, io::Error> {
+    /// Seconds since the epoch of 10000-01-01.
+    const MAX_SECS: u64 = 253_402_300_800;
+
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(elapsed) if elapsed.as_secs() < MAX_SECS => {
+            Ok(HeaderValue::from_str(&httpdate::fmt_http_date(time))
+                .expect("HTTP dates are valid header values"))
+        }
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "time cannot be represented as an HTTP date",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn response(status: u16, headers: &[(&str, &str)]) -> Response<()> {
+        let mut builder = Response::builder().status(status);
+
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+
+        builder.body(()).unwrap()
+    }
+
+    #[test]
+    fn validators_are_captured_from_response() {
+        let validators = Validators::from_response(&response(
+            200,
+            &[
+                ("etag", "W/\"abc\""),
+                ("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ],
+        ));
+
+        assert_eq!(validators.etag(), Some("W/\"abc\""));
+        assert_eq!(
+            validators.last_modified(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1445412480))
+        );
+    }
+
+    #[test]
+    fn not_modified_keeps_missing_validators() {
+        let mut validators = Validators::from_response(&response(
+            200,
+            &[
+                ("etag", "\"v1\""),
+                ("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT"),
+            ],
+        ));
+
+        assert!(
+            validators
+                .refresh(response(304, &[("etag", "\"v2\"")]))
+                .is_none()
+        );
+        assert_eq!(validators.etag(), Some("\"v2\""));
+        assert!(validators.last_modified().is_some());
+    }
+
+    #[test]
+    fn new_version_replaces_validators() {
+        let mut validators = Validators::from_response(&response(
+            200,
+            &[("last-modified", "Wed, 21 Oct 2015 07:28:00 GMT")],
+        ));
+
+        assert!(
+            validators
+                .refresh(response(200, &[("etag", "\"v2\"")]))
+                .is_some()
+        );
+        assert_eq!(validators.etag(), Some("\"v2\""));
+        assert_eq!(validators.last_modified(), None);
+    }
+
+    #[test]
+    fn error_response_keeps_validators() {
+        let mut validators = Validators::from_response(&response(200, &[("etag", "\"v1\"")]));
+
+        assert!(validators.refresh(response(500, &[])).is_some());
+        assert_eq!(validators.etag(), Some("\"v1\""));
+    }
+}
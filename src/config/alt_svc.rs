@@ -0,0 +1,246 @@
+//! Configuration of the Alt-Svc cache.
+
+use super::setopt::{EasyHandle, SetOpt, SetOptError};
+use crate::info::is_http_version_supported;
+use curl_sys::{CURLOPTTYPE_LONG, CURLOPTTYPE_OBJECTPOINT, CURLoption};
+use std::{
+    ffi::CString,
+    fmt, fs, io,
+    os::raw::c_long,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+// Not yet exposed by curl-sys.
+const CURLOPT_ALTSVC_CTRL: CURLoption = CURLOPTTYPE_LONG + 286;
+const CURLOPT_ALTSVC: CURLoption = CURLOPTTYPE_OBJECTPOINT + 287;
+const CURLALTSVC_H1: c_long = 1 << 3;
+const CURLALTSVC_H2: c_long = 1 << 4;
+const CURLALTSVC_H3: c_long = 1 << 5;
+
+/// A cache of alternative services advertised by servers using the `Alt-Svc`
+/// header, as described in [RFC 7838](https://www.rfc-editor.org/rfc/rfc7838).
+///
+/// A server can use `Alt-Svc` to announce that it is also available using a
+/// different protocol, host, or port, such as over HTTP/3. When a client has a
+/// cache, advertised services are remembered, and later requests to the same
+/// origin connect to the alternative instead. Only advertisements received
+/// over HTTPS are used.
+///
+/// Which alternatives are used depends on the
+/// [`VersionNegotiation`](super::VersionNegotiation) of each request.
+/// Alternatives using HTTP/3 are only used with
+/// [`VersionNegotiation::prefer_http3`](super::VersionNegotiation::prefer_http3)
+/// and if HTTP/3 support is available.
+///
+/// Set a cache for a client using
+/// [`HttpClientBuilder::alt_svc`](crate::HttpClientBuilder::alt_svc). Cloning
+/// a cache returns a new reference to the same cache.
+///
+/// # Storage
+///
+/// Each request reads the cache when it is sent, and writes back any changes
+/// when it completes. Changes made by requests running concurrently may
+/// therefore overwrite each other, in which case an advertisement is simply
+/// picked up again from a later response.
+#[derive(Clone)]
+pub struct AltSvcCache(Arc<Inner>);
+
+struct Inner {
+    /// The file the cache is persisted to, if any.
+    file: Option<PathBuf>,
+
+    /// Private directory holding the file of a temporary cache, created the
+    /// first time the cache is used and removed when the cache is dropped.
+    temp_dir: OnceLock<Result<PathBuf, Arc<io::Error>>>,
+}
+
+impl AltSvcCache {
+    /// Create a new, empty cache that only lasts for as long as it is in use.
+    ///
+    /// Curl is only able to keep its cache in a file, so even a temporary
+    /// cache is file-backed: the first time the cache is used, a directory
+    /// with an unpredictable name that only the current user can access is
+    /// created in the system temporary directory to hold it, and is removed
+    /// again once the cache is dropped. Like any cache, the file is read and
+    /// written by every request using it, and concurrent requests may
+    /// overwrite each other's changes, as described under
+    /// [Storage](#storage).
+    pub fn new() -> Self {
+        Self(Arc::new(Inner {
+            file: None,
+            temp_dir: OnceLock::new(),
+        }))
+    }
+
+    /// Use a cache persisted in the given file, so that it survives process
+    /// restarts. The file is created if it does not exist yet.
+    ///
+    /// The file uses the format of curl's Alt-Svc cache, so it can be shared
+    /// with the curl command line tool.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self(Arc::new(Inner {
+            file: Some(path.into()),
+            temp_dir: OnceLock::new(),
+        }))
+    }
+
+    /// Get the file this cache is persisted to, or `None` if the cache is not
+    /// persisted.
+    pub fn path(&self) -> Option<&Path> {
+        self.0.file.as_deref()
+    }
+
+    /// Remove all entries from the cache.
+    pub fn clear(&self) -> io::Result<()> {
+        let path = match (&self.0.file, self.0.temp_dir.get()) {
+            (Some(file), _) => file.clone(),
+            (None, Some(Ok(dir))) => Inner::temp_file(dir),
+            // A temporary cache that was never used is empty.
+            (None, _) => return Ok(()),
+        };
+
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Inner {
+    /// Get the file curl should use for this cache, creating the private
+    /// directory of a temporary cache if necessary.
+    fn file(&self) -> Result<PathBuf, Arc<io::Error>> {
+        match &self.file {
+            Some(file) => Ok(file.clone()),
+            None => self
+                .temp_dir
+                .get_or_init(|| crate::temp::create_private_dir("isahc-altsvc").map_err(Arc::new))
+                .as_deref()
+                .map(Self::temp_file)
+                .map_err(Arc::clone),
+        }
+    }
+
+    fn temp_file(dir: &Path) -> PathBuf {
+        dir.join("altsvc.txt")
+    }
+}
+
+impl Default for AltSvcCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for AltSvcCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AltSvcCache")
+            .field("path", &self.path())
+            .finish()
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Some(Ok(dir)) = self.temp_dir.get() {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+}
+
+impl SetOpt for AltSvcCache {
+    #[allow(unsafe_code)]
+    fn set_opt(&self, easy: &mut EasyHandle) -> Result<(), SetOptError> {
+        let mut protocols = CURLALTSVC_H1 | CURLALTSVC_H2;
+
+        if is_http_version_supported(http::Version::HTTP_3) {
+            protocols |= CURLALTSVC_H3;
+        }
+
+        let path = self
+            .0
+            .file()
+            .map_err(|e| crate::Error::new(crate::error::ErrorKind::ClientInitialization, e))?;
+        let path = CString::new(path.to_string_lossy().into_owned())
+            .map_err(|e| crate::Error::new(crate::error::ErrorKind::ClientInitialization, e))?;
+
+        // Keep a reference to the cache until the handle is dropped, which is
+        // when curl writes the file.
+        easy.get_mut().retained.push(self.0.clone());
+
+        unsafe {
+            for code in [
+                curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_ALTSVC_CTRL, protocols),
+                curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_ALTSVC, path.as_ptr()),
+            ] {
+                if code != curl_sys::CURLE_OK {
+                    return Err(curl::Error::new(code).into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temporary_cache_is_not_persisted() {
+        let cache = AltSvcCache::new();
+
+        assert_eq!(cache.path(), None);
+        assert_ne!(
+            cache.0.file().unwrap(),
+            AltSvcCache::new().0.file().unwrap()
+        );
+    }
+
+    #[test]
+    fn temporary_file_is_removed_on_drop() {
+        let cache = AltSvcCache::new();
+        let path = cache.0.file().unwrap();
+        let dir = path.parent().unwrap().to_path_buf();
+        fs::write(&path, "").unwrap();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        cache.clear().unwrap();
+        assert!(!path.exists());
+        fs::write(&path, "").unwrap();
+
+        let clone = cache.clone();
+        drop(cache);
+        assert!(path.exists());
+
+        drop(clone);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn persistent_file_is_kept() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("altsvc.txt");
+        fs::write(&path, "").unwrap();
+
+        let cache = AltSvcCache::open(&path);
+        assert_eq!(cache.path(), Some(path.as_path()));
+        drop(cache);
+        assert!(path.exists());
+
+        AltSvcCache::open(&path).clear().unwrap();
+        assert!(!path.exists());
+
+        // Clearing an empty cache is not an error.
+        AltSvcCache::open(&path).clear().unwrap();
+    }
+}
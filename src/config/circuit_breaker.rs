@@ -0,0 +1,219 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+type StateChangeFn = dyn Fn(&str, CircuitState, CircuitState) + Send + Sync;
+
+/// The state of a circuit breaker for a single server.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum CircuitState {
+    /// Requests are sent normally while failures are being counted.
+    Closed,
+
+    /// Too many requests have failed recently, and requests fail immediately
+    /// without being sent until the cooldown has elapsed.
+    Open,
+
+    /// The cooldown has elapsed, and a limited number of probe requests are
+    /// being sent to determine whether the server has recovered.
+    HalfOpen,
+}
+
+/// Describes a circuit breaker that stops sending requests to servers that
+/// appear to be down.
+///
+/// The outcomes of recent requests are tracked separately for each authority
+/// (host and port). A request is considered to have failed if it returns a
+/// network error (see [`Error::is_network`](crate::Error::is_network)), a
+/// timeout error, or a response with a 5xx status code. Other errors are not
+/// counted either way.
+///
+/// Once enough recent requests to a server have failed, the circuit for that
+/// server _opens_, and further requests to it fail immediately with a
+/// [`CircuitOpen`](crate::error::ErrorKind::CircuitOpen) error instead of
+/// waiting for connection attempts or timeouts. After a cooldown period the
+/// circuit becomes _half-open_, allowing a few probe requests through. If they
+/// all succeed the circuit closes again, but if any fails the circuit opens for
+/// another cooldown period.
+///
+/// State transitions are logged as `tracing` events, and can also be observed
+/// using [`on_state_change`](Self::on_state_change).
+///
+/// # Examples
+///
+/// ```
+/// use isahc::{config::CircuitBreaker, HttpClient};
+/// use std::time::Duration;
+///
+/// let client = HttpClient::builder()
+///     .circuit_breaker(CircuitBreaker::new()
+///         .failure_threshold(0.5)
+///         .minimum_requests(5)
+///         .cooldown(Duration::from_secs(10)))
+///     .build()?;
+/// # Ok::<(), isahc::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    failure_threshold: f64,
+    window: usize,
+    minimum_requests: usize,
+    cooldown: Duration,
+    half_open_probes: usize,
+    on_state_change: Option<Arc<StateChangeFn>>,
+}
+
+impl CircuitBreaker {
+    /// Create a new circuit breaker with default settings.
+    ///
+    /// By default the circuit opens when at least half of the last 20 requests
+    /// have failed, considering at least 10 requests. The cooldown is 30
+    /// seconds, and one probe request is sent when half-open.
+    pub fn new() -> Self {
+        Self {
+            failure_threshold: 0.5,
+            window: 20,
+            minimum_requests: 10,
+            cooldown: Duration::from_secs(30),
+            half_open_probes: 1,
+            on_state_change: None,
+        }
+    }
+
+    /// Set the fraction of recent requests, between 0 and 1, that must have
+    /// failed for the circuit to open.
+    #[must_use = "builders have no effect if unused"]
+    pub fn failure_threshold(mut self, ratio: f64) -> Self {
+        self.failure_threshold = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set how many of the most recent requests to each server are considered
+    /// when computing the failure rate.
+    #[must_use = "builders have no effect if unused"]
+    pub fn window(mut self, size: usize) -> Self {
+        self.window = size.max(1);
+        self
+    }
+
+    /// Set the minimum number of recent requests to a server before its
+    /// circuit can open, so that a handful of failures right after startup do
+    /// not open it. Values greater than the [`window`](Self::window) size are
+    /// treated as the window size.
+    #[must_use = "builders have no effect if unused"]
+    pub fn minimum_requests(mut self, count: usize) -> Self {
+        self.minimum_requests = count.max(1);
+        self
+    }
+
+    /// Set how long a circuit stays open before probe requests are allowed.
+    #[must_use = "builders have no effect if unused"]
+    pub fn cooldown(mut self, cooldown: Duration) -> Self {
+        self.cooldown = cooldown;
+        self
+    }
+
+    /// Set how many probe requests are allowed while half-open. All of them
+    /// must succeed for the circuit to close.
+    #[must_use = "builders have no effect if unused"]
+    pub fn half_open_probes(mut self, count: usize) -> Self {
+        self.half_open_probes = count.max(1);
+        self
+    }
+
+    /// Set a function to call whenever the circuit for a server changes
+    /// state. The function is called with the authority of the server, the
+    /// previous state, and the new state.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{config::{CircuitBreaker, CircuitState}, HttpClient};
+    ///
+    /// let client = HttpClient::builder()
+    ///     .circuit_breaker(CircuitBreaker::new().on_state_change(|authority, _, to| {
+    ///         if to == CircuitState::Open {
+    ///             eprintln!("{} appears to be down", authority);
+    ///         }
+    ///     }))
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    #[must_use = "builders have no effect if unused"]
+    pub fn on_state_change<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, CircuitState, CircuitState) + Send + Sync + 'static,
+    {
+        self.on_state_change = Some(Arc::new(f));
+        self
+    }
+
+    pub(crate) fn window_size(&self) -> usize {
+        self.window
+    }
+
+    pub(crate) fn cooldown_duration(&self) -> Duration {
+        self.cooldown
+    }
+
+    pub(crate) fn probes(&self) -> usize {
+        self.half_open_probes
+    }
+
+    /// Check whether the given outcomes of recent requests should open the
+    /// circuit.
+    pub(crate) fn should_open(&self, requests: usize, failures: usize) -> bool {
+        requests >= self.minimum_requests.min(self.window)
+            && failures > 0
+            && failures as f64 >= self.failure_threshold * requests as f64
+    }
+
+    pub(crate) fn notify(&self, authority: &str, from: CircuitState, to: CircuitState) {
+        tracing::info!(authority, ?from, ?to, "circuit breaker state changed");
+
+        if let Some(f) = self.on_state_change.as_ref() {
+            f(authority, from, to);
+        }
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for CircuitBreaker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CircuitBreaker")
+            .field("failure_threshold", &self.failure_threshold)
+            .field("window", &self.window)
+            .field("minimum_requests", &self.minimum_requests)
+            .field("cooldown", &self.cooldown)
+            .field("half_open_probes", &self.half_open_probes)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opens_only_after_minimum_requests() {
+        let breaker = CircuitBreaker::new()
+            .minimum_requests(4)
+            .failure_threshold(0.5);
+
+        assert!(!breaker.should_open(3, 3));
+        assert!(!breaker.should_open(4, 1));
+        assert!(breaker.should_open(4, 2));
+    }
+
+    #[test]
+    fn minimum_requests_is_capped_by_window() {
+        let breaker = CircuitBreaker::new().window(5);
+
+        assert!(breaker.should_open(5, 5));
+        assert!(!breaker.should_open(4, 4));
+    }
+}
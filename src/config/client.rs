@@ -1,4 +1,5 @@
 use super::{
+    alt_svc::AltSvcCache,
     dns::{DnsCache, ResolveMap},
     setopt::{EasyHandle, SetOpt, SetOptError},
 };
@@ -10,6 +11,11 @@ pub(crate) struct ClientConfig {
     pub(crate) close_connections: bool,
     pub(crate) dns_cache: Option<DnsCache>,
     pub(crate) dns_resolve: Option<ResolveMap>,
+    pub(crate) alt_svc: Option<AltSvcCache>,
+    pub(crate) resolver: Option<crate::net::SharedResolver>,
+
+    #[cfg(feature = "tls")]
+    pub(crate) identity_resolver: Option<crate::tls::IdentityResolver>,
 }
 
 impl SetOpt for ClientConfig {
@@ -26,6 +32,10 @@ impl SetOpt for ClientConfig {
             map.set_opt(easy)?;
         }
 
+        if let Some(cache) = self.alt_svc.as_ref() {
+            cache.set_opt(easy)?;
+        }
+
         easy.forbid_reuse(self.close_connections)?;
 
         Ok(())
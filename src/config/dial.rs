@@ -2,9 +2,25 @@
 //! are opened.
 
 use super::setopt::{EasyHandle, SetOpt, SetOptError};
+use crate::{net::Socket, task::spawn_blocking};
 use curl::easy::List;
 use http::Uri;
-use std::{convert::TryFrom, fmt, net::SocketAddr, str::FromStr};
+use std::{
+    convert::TryFrom,
+    fmt,
+    future::Future,
+    io,
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    pin::Pin,
+    str::FromStr,
+    sync::Arc,
+    time::Duration,
+};
+
+/// The type of future returned by a custom dialer.
+pub(crate) type DialFuture = Pin<Box<dyn Future<Output = io::Result<Socket>> + Send>>;
+
+type DialFn = dyn Fn(&Uri) -> DialFuture + Send + Sync;
 
 /// An error which can be returned when parsing a dial address.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -23,12 +39,28 @@ impl std::error::Error for DialerParseError {}
 /// A dialer can be created from a URI-like string using [`FromStr`]. The
 /// following URI schemes are supported:
 ///
-/// - `tcp`: Connect to a TCP address and port pair, like `tcp:127.0.0.1:8080`.
+/// - `tcp`: Connect to a TCP host and port pair, like `tcp:127.0.0.1:8080`,
+///   `tcp:[::1]:8080`, or `tcp:backend.internal:8080`. IPv6 addresses must be
+///   enclosed in brackets.
 /// - `unix`: Connect to a Unix socket located on the file system, like
 ///   `unix:/path/to/my.sock`. This is only supported on Unix.
+/// - `unix-abstract`: Connect to a Unix socket in the abstract namespace, like
+///   `unix-abstract:my-socket`. This is only supported on Linux.
+///
+/// Several of these can be given as a comma-separated list, like
+/// `tcp:10.0.0.1:8080,tcp:10.0.0.2:8080`, to create a dialer that tries each
+/// of them in order. Note that connections opened this way are never reused,
+/// so every request pays for a new connection and HTTP/2 multiplexing is not
+/// available. See [`Dialer::failover`] for details.
+///
+/// Each form can also be written with two slashes after the scheme, like
+/// `tcp://127.0.0.1:8080`, which allows a single target to be given as a
+/// [`Uri`]. A list of targets is not a valid URI, and can only be parsed from
+/// a string.
 ///
 /// The [`Default`] dialer uses the hostname and port specified in each request
-/// as normal.
+/// as normal. A dialer can also open connections itself using
+/// [`Dialer::custom`] or [`Dialer::custom_async`].
 ///
 /// # Examples
 ///
@@ -41,6 +73,15 @@ impl std::error::Error for DialerParseError {}
 /// let unix_socket = "unix:/path/to/my.sock".parse::<Dialer>()?;
 /// # Ok::<(), isahc::config::DialerParseError>(())
 /// ```
+///
+/// Fail over to a second host if the first one cannot be reached:
+///
+/// ```
+/// use isahc::config::Dialer;
+///
+/// let dialer = "tcp:primary.internal:8080,tcp:[fd00::2]:8080".parse::<Dialer>()?;
+/// # Ok::<(), isahc::config::DialerParseError>(())
+/// ```
 #[derive(Clone, Debug)]
 pub struct Dialer(Inner);
 
@@ -52,8 +93,32 @@ enum Inner {
 
     #[cfg(unix)]
     UnixSocket(std::path::PathBuf),
+
+    #[cfg(target_os = "linux")]
+    AbstractUnixSocket(Vec<u8>),
+
+    Custom(Custom),
+
+    Failover(Vec<Inner>),
+}
+
+#[derive(Clone)]
+struct Custom(Arc<DialFn>);
+
+impl fmt::Debug for Custom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Custom")
+    }
 }
 
+impl PartialEq for Custom {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Custom {}
+
 impl Dialer {
     /// Connect to the given IP socket.
     ///
@@ -102,6 +167,293 @@ impl Dialer {
     pub fn unix_socket(path: impl Into<std::path::PathBuf>) -> Self {
         Self(Inner::UnixSocket(path.into()))
     }
+
+    /// Connect to a Unix socket in the abstract namespace with the given name.
+    ///
+    /// The name should not include the leading null byte that distinguishes
+    /// abstract socket addresses.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::config::Dialer;
+    ///
+    /// # #[cfg(target_os = "linux")]
+    /// let dialer = Dialer::abstract_unix_socket("my-socket");
+    /// ```
+    ///
+    /// # Availability
+    ///
+    /// This function is only available on Linux.
+    #[cfg(target_os = "linux")]
+    pub fn abstract_unix_socket(name: impl Into<Vec<u8>>) -> Self {
+        Self(Inner::AbstractUnixSocket(name.into()))
+    }
+
+    /// Connect using each of the given dialers in order, using the first one
+    /// that a connection can be established with.
+    ///
+    /// The [`Default`] dialer connects to the host and port of the request.
+    ///
+    /// Since curl only supports connecting to a single address or socket
+    /// itself, connections are established by Isahc ahead of time, in the same
+    /// way as for [`Dialer::custom`]. Each TCP target is given the connect
+    /// timeout of the request, if any, and if none of the targets can be
+    /// connected to, the request fails with the error of the last attempt.
+    ///
+    /// # Performance
+    ///
+    /// Since the connection is opened by Isahc rather than curl, it is only
+    /// used for a single request and closed afterwards, just like connections
+    /// opened by [`Dialer::custom`]. This means that using a failover dialer
+    /// disables connection reuse: every request opens a new connection and
+    /// performs a new TLS handshake, and HTTP/2 connections cannot be shared
+    /// between concurrent requests. Each request also tries the targets in
+    /// order starting from the first, even if it was unreachable for the
+    /// previous request.
+    ///
+    /// Prefer a single target where possible if many requests are sent to the
+    /// same server, or use a load balancer in front of the servers instead.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::config::Dialer;
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let dialer = Dialer::failover([
+    ///     Dialer::ip_socket((Ipv4Addr::new(10, 0, 0, 1), 8080)),
+    ///     Dialer::ip_socket((Ipv4Addr::new(10, 0, 0, 2), 8080)),
+    /// ]);
+    /// ```
+    pub fn failover(dialers: impl IntoIterator<Item = Dialer>) -> Self {
+        let mut targets = Vec::new();
+
+        for dialer in dialers {
+            match dialer.0 {
+                Inner::Failover(nested) => targets.extend(nested),
+                target => targets.push(target),
+            }
+        }
+
+        Self(Inner::Failover(targets))
+    }
+
+    /// Open connections using the given function, which returns a socket that
+    /// is already connected to the server.
+    ///
+    /// The function is called with the URI of the request each time a request
+    /// is sent, and is free to create the socket in any way it likes. This
+    /// makes it possible to set socket options not otherwise supported, to
+    /// bind to a specific network, or to tunnel connections through an
+    /// in-process transport. Any value that can be converted into a [`Socket`]
+    /// can be returned.
+    ///
+    /// The function is expected to block until the socket is connected, and
+    /// so is called on a small pool of background threads shared with name
+    /// resolution. When many connections are opened at once, calls may have
+    /// to wait for a thread to become available. Use [`Dialer::custom_async`]
+    /// for sockets that can be connected asynchronously instead.
+    ///
+    /// If the function returns an error, the request fails with an error of
+    /// kind [`ConnectionFailed`](crate::error::ErrorKind::ConnectionFailed)
+    /// with the returned error as its source.
+    ///
+    /// Each socket is only used for the request it was created for, and is
+    /// closed once the request completes. Connections opened using a custom
+    /// dialer are therefore never reused by other requests.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::config::Dialer;
+    /// use std::net::TcpStream;
+    ///
+    /// let dialer = Dialer::custom(|_uri| {
+    ///     let stream = TcpStream::connect("127.0.0.1:8080")?;
+    ///     stream.set_nodelay(true)?;
+    ///     Ok(stream)
+    /// });
+    /// ```
+    pub fn custom<F, S>(dial: F) -> Self
+    where
+        F: Fn(&Uri) -> io::Result<S> + Send + Sync + 'static,
+        S: Into<Socket>,
+    {
+        let dial = Arc::new(dial);
+
+        Self::from_fn(move |uri| {
+            let dial = dial.clone();
+            let uri = uri.clone();
+
+            spawn_dial(move || dial(&uri).map(Into::into))
+        })
+    }
+
+    /// Open connections using the given asynchronous function, which returns
+    /// a future that resolves to a socket that is already connected to the
+    /// server.
+    ///
+    /// This behaves the same as [`Dialer::custom`], except that the returned
+    /// future is awaited as part of sending the request instead of calling the
+    /// function on a background thread.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::config::Dialer;
+    /// use std::net::TcpStream;
+    ///
+    /// let dialer = Dialer::custom_async(|uri| {
+    ///     let port = uri.port_u16().unwrap_or(80);
+    ///
+    ///     async move {
+    ///         // Connect the socket using your favorite runtime here.
+    ///         TcpStream::connect(("127.0.0.1", port))
+    ///     }
+    /// });
+    /// ```
+    pub fn custom_async<F, Fut, S>(dial: F) -> Self
+    where
+        F: Fn(&Uri) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = io::Result<S>> + Send + 'static,
+        S: Into<Socket>,
+    {
+        Self::from_fn(move |uri| {
+            let future = dial(uri);
+
+            Box::pin(async move { future.await.map(Into::into) })
+        })
+    }
+
+    fn from_fn(dial: impl Fn(&Uri) -> DialFuture + Send + Sync + 'static) -> Self {
+        Self(Inner::Custom(Custom(Arc::new(dial))))
+    }
+}
+
+impl Dialer {
+    /// Check whether this dialer connects to each request's host as normal.
+    pub(crate) fn is_default(&self) -> bool {
+        self.0 == Inner::Default
+    }
+
+    /// Open a socket for a request to the given URI, if this dialer opens its
+    /// own sockets.
+    pub(crate) fn dial(&self, uri: &Uri, connect_timeout: Option<Duration>) -> Option<DialFuture> {
+        match &self.0 {
+            Inner::Custom(Custom(dial)) => Some(dial(uri)),
+            Inner::Failover(targets) => {
+                let targets = targets.clone();
+                let uri = uri.clone();
+
+                Some(Box::pin(async move {
+                    let mut last_error = None;
+
+                    for target in targets {
+                        let result = match target {
+                            Inner::Custom(Custom(dial)) => dial(&uri).await,
+                            target => {
+                                let uri = uri.clone();
+                                spawn_dial(move || connect(&target, &uri, connect_timeout)).await
+                            }
+                        };
+
+                        match result {
+                            Ok(socket) => return Ok(socket),
+                            Err(e) => last_error = Some(e),
+                        }
+                    }
+
+                    Err(last_error.unwrap_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidInput, "no targets to dial")
+                    }))
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Run a blocking dial function on the shared pool of background threads.
+fn spawn_dial(dial: impl FnOnce() -> io::Result<Socket> + Send + 'static) -> DialFuture {
+    let dial = spawn_blocking(dial);
+
+    Box::pin(async move { dial.await? })
+}
+
+/// Connect to a single failover target, blocking until connected.
+fn connect(target: &Inner, uri: &Uri, timeout: Option<Duration>) -> io::Result<Socket> {
+    match target {
+        Inner::Default => {
+            let host = uri.host().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "request has no host")
+            })?;
+            let port = uri.port_u16().unwrap_or_else(|| {
+                if uri.scheme() == Some(&http::uri::Scheme::HTTPS) {
+                    443
+                } else {
+                    80
+                }
+            });
+
+            connect_tcp(&format!("{}:{}", host, port), timeout)
+        }
+
+        // Strip the empty source host and port in the CURLOPT_CONNECT_TO
+        // string to get the target address.
+        Inner::IpSocket(connect_to) => connect_tcp(&connect_to[2..], timeout),
+
+        #[cfg(unix)]
+        Inner::UnixSocket(path) => std::os::unix::net::UnixStream::connect(path).map(Into::into),
+
+        #[cfg(target_os = "linux")]
+        Inner::AbstractUnixSocket(name) => {
+            use std::os::{linux::net::SocketAddrExt, unix::net};
+
+            let addr = net::SocketAddr::from_abstract_name(name)?;
+
+            net::UnixStream::connect_addr(&addr).map(Into::into)
+        }
+
+        Inner::Custom(_) | Inner::Failover(_) => unreachable!(),
+    }
+}
+
+/// Connect to each address a TCP host and port resolves to until one succeeds.
+fn connect_tcp(addr: &str, timeout: Option<Duration>) -> io::Result<Socket> {
+    let mut last_error = None;
+
+    for addr in addr.to_socket_addrs()? {
+        let result = match timeout {
+            Some(timeout) => TcpStream::connect_timeout(&addr, timeout),
+            None => TcpStream::connect(addr),
+        };
+
+        match result {
+            Ok(stream) => return Ok(stream.into()),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            "host did not resolve to any addresses",
+        )
+    }))
+}
+
+/// Parse a TCP host and port pair, returning it in the format
+/// CURLOPT_CONNECT_TO expects.
+fn parse_tcp(s: &str) -> Option<String> {
+    let (host, port) = s.rsplit_once(':')?;
+    let port = port.parse::<u16>().ok()?;
+
+    // IPv6 addresses are only recognized in brackets, which keeps the port
+    // separator unambiguous.
+    let host = url::Host::parse(host).ok()?;
+
+    Some(format!("::{}:{}", host, port))
 }
 
 impl Default for Dialer {
@@ -120,13 +472,36 @@ impl FromStr for Dialer {
     type Err = DialerParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.contains(',') {
+            return s
+                .split(',')
+                .map(str::parse)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Self::failover);
+        }
+
         if s.starts_with("tcp:") {
+            // Allow a trailing slash, which is added when formatting a URI.
             let addr_str = s[4..].trim_start_matches('/');
+            let addr_str = addr_str.strip_suffix('/').unwrap_or(addr_str);
+
+            return parse_tcp(addr_str)
+                .map(|addr| Self(Inner::IpSocket(addr)))
+                .ok_or(DialerParseError(()));
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            if s.starts_with("unix-abstract:") {
+                let name = s[14..].trim_start_matches('/');
+                let name = name.strip_suffix('/').unwrap_or(name);
+
+                if name.is_empty() {
+                    return Err(DialerParseError(()));
+                }
 
-            return addr_str
-                .parse::<SocketAddr>()
-                .map(Self::ip_socket)
-                .map_err(|_| DialerParseError(()));
+                return Ok(Self::abstract_unix_socket(name));
+            }
         }
 
         #[cfg(unix)]
@@ -185,6 +560,11 @@ impl SetOpt for Dialer {
             _ => None,
         })?;
 
+        #[cfg(target_os = "linux")]
+        if let Inner::AbstractUnixSocket(name) = &self.0 {
+            easy.abstract_unix_socket(name)?;
+        }
+
         Ok(())
     }
 }
@@ -215,6 +595,95 @@ mod tests {
         assert_eq!(dialer.0, Inner::UnixSocket("/path/to/my.sock".into()));
     }
 
+    #[test]
+    fn parse_tcp_ipv6_socket_uri() {
+        let dialer = "tcp:[::1]:1200".parse::<Dialer>().unwrap();
+
+        assert_eq!(dialer.0, Inner::IpSocket("::[::1]:1200".into()));
+    }
+
+    #[test]
+    fn parse_tcp_ipv6_without_brackets_is_invalid() {
+        let result = "tcp:::1:1200".parse::<Dialer>();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn parse_tcp_hostname_uri() {
+        let dialer = "tcp:Backend.Internal:8080".parse::<Dialer>().unwrap();
+
+        assert_eq!(dialer.0, Inner::IpSocket("::backend.internal:8080".into()));
+    }
+
+    #[test]
+    fn parse_tcp_uri_without_port_is_invalid() {
+        let result = "tcp:backend.internal".parse::<Dialer>();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn from_tcp_ipv6_socket_uri() {
+        let uri = "tcp://[::1]:1200".parse::<http::Uri>().unwrap();
+        let dialer = Dialer::try_from(uri).unwrap();
+
+        assert_eq!(dialer.0, Inner::IpSocket("::[::1]:1200".into()));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn parse_abstract_unix_socket_uri() {
+        let dialer = "unix-abstract:my-socket".parse::<Dialer>().unwrap();
+
+        assert_eq!(dialer.0, Inner::AbstractUnixSocket(b"my-socket".to_vec()));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn from_abstract_unix_socket_uri() {
+        let uri = "unix-abstract://my-socket".parse::<http::Uri>().unwrap();
+        let dialer = Dialer::try_from(uri).unwrap();
+
+        assert_eq!(dialer.0, Inner::AbstractUnixSocket(b"my-socket".to_vec()));
+    }
+
+    #[test]
+    fn parse_failover_list() {
+        let dialer = "tcp:10.0.0.1:80,tcp:[::2]:80".parse::<Dialer>().unwrap();
+
+        assert_eq!(
+            dialer.0,
+            Inner::Failover(vec![
+                Inner::IpSocket("::10.0.0.1:80".into()),
+                Inner::IpSocket("::[::2]:80".into()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_failover_list_with_invalid_target() {
+        assert!("tcp:10.0.0.1:80,".parse::<Dialer>().is_err());
+        assert!("tcp:10.0.0.1:80,udp:10.0.0.2:80".parse::<Dialer>().is_err());
+    }
+
+    #[test]
+    fn nested_failover_lists_are_flattened() {
+        let dialer = Dialer::failover([
+            "tcp:10.0.0.1:80,tcp:10.0.0.2:80".parse().unwrap(),
+            Dialer::default(),
+        ]);
+
+        assert_eq!(
+            dialer.0,
+            Inner::Failover(vec![
+                Inner::IpSocket("::10.0.0.1:80".into()),
+                Inner::IpSocket("::10.0.0.2:80".into()),
+                Inner::Default,
+            ])
+        );
+    }
+
     #[test]
     #[cfg(unix)]
     fn from_unix_socket_uri() {
@@ -80,6 +80,22 @@ impl ResolveMap {
             .push(format!("{}:{}:{}", host.as_ref(), port, addr.into()));
         self
     }
+
+    /// Add a DNS mapping for a given host and port pair to multiple addresses,
+    /// replacing any mapping added previously for the same pair.
+    pub(crate) fn add_all<I>(mut self, host: &str, port: u16, addrs: I) -> Self
+    where
+        I: IntoIterator<Item = IpAddr>,
+    {
+        let addrs = addrs
+            .into_iter()
+            .map(|addr| addr.to_string())
+            .collect::<Vec<_>>();
+
+        self.0
+            .push(format!("{}:{}:{}", host, port, addrs.join(",")));
+        self
+    }
 }
 
 impl SetOpt for ResolveMap {
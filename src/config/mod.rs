@@ -19,19 +19,28 @@ use crate::{
     net::interface,
 };
 use setopt::{EasyHandle, SetOpt, SetOptError};
-use std::time::Duration;
+use std::{os::raw::c_long, time::Duration};
 
+pub(crate) mod alt_svc;
+pub(crate) mod circuit_breaker;
 pub(crate) mod client;
 pub(crate) mod dial;
 pub(crate) mod dns;
 pub(crate) mod proxy;
+pub(crate) mod rate_limit;
 pub(crate) mod redirect;
 pub(crate) mod request;
+pub(crate) mod retry;
 pub(crate) mod setopt;
 
+pub use alt_svc::AltSvcCache;
+pub use circuit_breaker::{CircuitBreaker, CircuitState};
 pub use dial::{Dialer, DialerParseError};
 pub use dns::{DnsCache, ResolveMap};
+pub use proxy::ProxyConfig;
+pub use rate_limit::RateLimit;
 pub use redirect::RedirectPolicy;
+pub use retry::RetryPolicy;
 
 /// Provides additional methods when building a request for configuring various
 /// execution-related options on how the request should be sent.
@@ -174,6 +183,127 @@ pub trait Configurable: request::WithRequestConfig {
         })
     }
 
+    /// Set a policy for automatically retrying requests that fail with
+    /// transient errors or certain response status codes.
+    ///
+    /// Only requests with an idempotent method are retried. See
+    /// [`RetryPolicy`] for details on how retries are performed.
+    ///
+    /// The default is to not retry requests.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use isahc::{config::RetryPolicy, prelude::*, Request};
+    ///
+    /// // Retry up to 3 times if the server is temporarily unavailable.
+    /// let response = Request::get("https://httpbin.org/status/503")
+    ///     .retry_policy(RetryPolicy::new(3).status_codes([503]))
+    ///     .body(())?
+    ///     .send()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    #[must_use = "builders have no effect if unused"]
+    fn retry_policy(self, policy: RetryPolicy) -> Self {
+        self.with_config(move |config| {
+            config.retry_policy = Some(policy);
+        })
+    }
+
+    /// Send duplicate requests if a response is slow to arrive, and use
+    /// whichever response arrives first.
+    ///
+    /// If the response headers have not been received within `delay` after
+    /// sending a request, an identical request is sent, and so on, until up to
+    /// `max_extra` additional requests are in flight. As soon as any of them
+    /// receives a response, the others are cancelled. This can reduce tail
+    /// latency when talking to replicated backends, at the cost of extra load.
+    ///
+    /// A request that fails with an error does not trigger a new request by
+    /// itself; if all requests in flight fail then the last error is returned.
+    /// Use a [retry policy](Configurable::retry_policy) to retry failed
+    /// requests.
+    ///
+    /// Only requests with an idempotent method and a body that can be sent
+    /// again (such as an empty body or one stored in memory) are hedged.
+    /// Setting `max_extra` to 0 disables hedging, which is the default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use isahc::{prelude::*, Request};
+    /// use std::time::Duration;
+    ///
+    /// // Send up to 2 more requests if a response takes longer than 50ms.
+    /// let response = Request::get("https://example.org")
+    ///     .hedge(Duration::from_millis(50), 2)
+    ///     .body(())?
+    ///     .send()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    #[must_use = "builders have no effect if unused"]
+    fn hedge(self, delay: Duration, max_extra: u32) -> Self {
+        self.with_config(move |config| {
+            config.hedge = Some((delay, max_extra));
+        })
+    }
+
+    /// Make the request conditional on the resource no longer matching the
+    /// given entity tag, by setting the `If-None-Match` request header.
+    ///
+    /// If the resource still matches, the server will respond with `304 Not
+    /// Modified` and no body, which can be checked for using
+    /// [`ResponseExt::not_modified`](crate::ResponseExt::not_modified). The
+    /// tag should be given exactly as received in an `ETag` response header,
+    /// including quotes. A `If-None-Match` header set explicitly on the request
+    /// takes precedence over this option.
+    ///
+    /// See [`Validators`](crate::Validators) for a convenient way to keep track
+    /// of entity tags between requests.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use isahc::{prelude::*, Request};
+    ///
+    /// let response = Request::get("https://example.org")
+    ///     .if_none_match("\"33a64df551425fcc\"")
+    ///     .body(())?
+    ///     .send()?;
+    ///
+    /// if response.not_modified() {
+    ///     println!("resource has not changed");
+    /// }
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    #[must_use = "builders have no effect if unused"]
+    fn if_none_match(self, etag: impl Into<String>) -> Self {
+        let etag = etag.into();
+
+        self.with_config(move |config| {
+            config.if_none_match = Some(etag);
+        })
+    }
+
+    /// Make the request conditional on the resource having been modified after
+    /// the given time, by setting the `If-Modified-Since` request header.
+    ///
+    /// If the resource has not been modified since, the server will respond
+    /// with `304 Not Modified` and no body. HTTP dates have a resolution of one
+    /// second, so any fractional part of the given time is discarded. A
+    /// `If-Modified-Since` header set explicitly on the request takes
+    /// precedence over this option.
+    ///
+    /// HTTP dates can only represent times from 1970 up to the end of the year
+    /// 9999. Sending a request with a time outside that range fails with an
+    /// [`InvalidRequest`](crate::error::ErrorKind::InvalidRequest) error.
+    #[must_use = "builders have no effect if unused"]
+    fn if_modified_since(self, time: std::time::SystemTime) -> Self {
+        self.with_config(move |config| {
+            config.if_modified_since = Some(time);
+        })
+    }
+
     /// Set a cookie jar to use to accept, store, and supply cookies for
     /// incoming responses and outgoing requests.
     ///
@@ -465,6 +595,35 @@ pub trait Configurable: request::WithRequestConfig {
         })
     }
 
+    /// Resolve host names using DNS over HTTPS (DoH), instead of using the
+    /// name resolver of the operating system.
+    ///
+    /// Addresses found using DoH take precedence over any resolver set using
+    /// [`HttpClientBuilder::resolver`](crate::HttpClientBuilder::resolver).
+    /// Requests to IP addresses, requests using a custom [`Dialer`], and
+    /// requests sent through a proxy are not resolved, as the proxy looks up
+    /// the host itself. See [`DnsOverHttps`](crate::net::DnsOverHttps) for
+    /// details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{net::DnsOverHttps, prelude::*, Request};
+    ///
+    /// let request = Request::get("https://example.org")
+    ///     .dns_over_https(DnsOverHttps::new(
+    ///         "https://dns.google/dns-query".parse()?,
+    ///     ))
+    ///     .body(())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[must_use = "builders have no effect if unused"]
+    fn dns_over_https(self, doh: crate::net::DnsOverHttps) -> Self {
+        self.with_config(move |config| {
+            config.dns_over_https = Some(doh);
+        })
+    }
+
     /// Set a proxy to use for requests.
     ///
     /// The proxy protocol is specified by the URI scheme.
@@ -536,6 +695,36 @@ pub trait Configurable: request::WithRequestConfig {
         })
     }
 
+    /// Set the proxies to use for requests, and how to pick the proxy to use
+    /// for each request.
+    ///
+    /// The proxy to use is selected separately for each request based on its
+    /// URI, including for each request made when following redirects. See
+    /// [`ProxyConfig`] for details.
+    ///
+    /// When set, this takes precedence over any proxy set using
+    /// [`Configurable::proxy`] and any hosts set using
+    /// [`Configurable::proxy_blacklist`], and the proxy environment variables
+    /// are no longer used by default. Use [`ProxyConfig::from_env`] to take
+    /// them into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{config::ProxyConfig, prelude::*, HttpClient};
+    ///
+    /// let client = HttpClient::builder()
+    ///     .proxy_config(ProxyConfig::from_env().no_proxy(["localhost"]))
+    ///     .build()?;
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    #[must_use = "builders have no effect if unused"]
+    fn proxy_config(self, proxy_config: ProxyConfig) -> Self {
+        self.with_config(move |config| {
+            config.proxy_config = Some(proxy_config);
+        })
+    }
+
     /// Set one or more HTTP authentication methods to attempt to use when
     /// authenticating with a proxy.
     ///
@@ -714,6 +903,39 @@ pub trait Configurable: request::WithRequestConfig {
         })
     }
 
+    /// Enable or disable capturing information about the secure connection a
+    /// response is received over, such as the certificate chain presented by
+    /// the server and the negotiated TLS version.
+    ///
+    /// When enabled, the information can be accessed using
+    /// [`ResponseExt::tls_info`](crate::ResponseExt::tls_info). See
+    /// [`TlsInfo`](crate::tls::TlsInfo) for details on what is available.
+    ///
+    /// By default this is disabled.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use isahc::{prelude::*, Request};
+    ///
+    /// let response = Request::get("https://example.org")
+    ///     .tls_info(true)
+    ///     .body(())?
+    ///     .send()?;
+    ///
+    /// if let Some(info) = response.tls_info() {
+    ///     println!("negotiated {:?}", info.protocol_version());
+    /// }
+    /// # Ok::<(), isahc::Error>(())
+    /// ```
+    #[cfg(feature = "tls")]
+    #[must_use = "builders have no effect if unused"]
+    fn tls_info(self, enable: bool) -> Self {
+        self.with_config(move |config| {
+            config.tls_info = Some(enable);
+        })
+    }
+
     /// Enable or disable sending HTTP header names in Title-Case instead of
     /// lowercase form.
     ///
@@ -754,6 +976,9 @@ pub trait Configurable: request::WithRequestConfig {
     }
 }
 
+// Not yet exposed by curl-sys.
+const CURL_HTTP_VERSION_3ONLY: c_long = 31;
+
 /// A strategy for selecting what HTTP versions should be used when
 /// communicating with a server.
 ///
@@ -771,6 +996,8 @@ pub struct VersionNegotiation(VersionNegotiationInner);
 #[derive(Clone, Copy, Debug)]
 enum VersionNegotiationInner {
     LatestCompatible,
+    PreferHttp3,
+    Http3Only,
     Strict(curl::easy::HttpVersion),
 }
 
@@ -788,8 +1015,12 @@ impl VersionNegotiation {
     /// Secure connections will begin with a TLS handshake, after which the
     /// highest supported HTTP version listed by the server via ALPN will be
     /// used. Once connected, additional upgrades to newer versions may also
-    /// occur if the server lists support for it. In the future, headers such as
-    /// `Alt-Svc` will be used.
+    /// occur if the server lists support for it. If the client has an
+    /// [`AltSvcCache`], alternative services using HTTP/1.1 or HTTP/2 that are
+    /// advertised by a server using `Alt-Svc` are used as well.
+    ///
+    /// HTTP/3 is never used with this strategy; see
+    /// [`VersionNegotiation::prefer_http3`] instead.
     ///
     /// Insecure connections always use HTTP/1.x since there is no standard
     /// mechanism for a server to declare support for insecure HTTP versions,
@@ -827,17 +1058,44 @@ impl VersionNegotiation {
         ))
     }
 
+    /// Attempt to use HTTP/3 for secure connections, falling back to HTTP/2 or
+    /// HTTP/1.1 if an HTTP/3 connection cannot be established.
+    ///
+    /// An HTTP/3 connection is attempted alongside a regular TCP connection,
+    /// and the first to be established successfully is used. If the client
+    /// has an [`AltSvcCache`], alternative services advertised by a server
+    /// using `Alt-Svc` are used as well, including those using HTTP/3.
+    ///
+    /// The version actually used for a request can be checked using
+    /// [`Response::version`](http::Response::version) or
+    /// [`Metrics::http_version`](crate::Metrics::http_version).
+    ///
+    /// If HTTP/3 support is not available, this behaves the same as
+    /// [`VersionNegotiation::latest_compatible`] instead of failing.
+    pub const fn prefer_http3() -> Self {
+        Self(VersionNegotiationInner::PreferHttp3)
+    }
+
     /// Connect via HTTP/3. Failure to connect will not fall back to old
     /// versions.
+    ///
+    /// If HTTP/3 support is not compiled in, then using this strategy will
+    /// always result in an error.
     pub const fn http3() -> Self {
-        Self(VersionNegotiationInner::Strict(curl::easy::HttpVersion::V3))
+        Self(VersionNegotiationInner::Http3Only)
     }
 }
 
 impl SetOpt for VersionNegotiation {
     fn set_opt(&self, easy: &mut EasyHandle) -> Result<(), SetOptError> {
         match self.0 {
-            VersionNegotiationInner::LatestCompatible => {
+            VersionNegotiationInner::PreferHttp3
+                if is_http_version_supported(http::Version::HTTP_3) =>
+            {
+                easy.http_version(curl::easy::HttpVersion::V3)
+                    .map_err(Into::into)
+            }
+            VersionNegotiationInner::LatestCompatible | VersionNegotiationInner::PreferHttp3 => {
                 // If HTTP/2 support is available, this basically the most
                 // lenient way of using it. Alt-Svc is used to upgrade to newer
                 // versions, and old versions are used if the server doesn't
@@ -852,6 +1110,22 @@ impl SetOpt for VersionNegotiation {
                     Ok(())
                 }
             }
+            VersionNegotiationInner::Http3Only => {
+                // The safe wrapper does not support this option.
+                #[allow(unsafe_code)]
+                let code = unsafe {
+                    curl_sys::curl_easy_setopt(
+                        easy.raw(),
+                        curl_sys::CURLOPT_HTTP_VERSION,
+                        CURL_HTTP_VERSION_3ONLY,
+                    )
+                };
+
+                match code {
+                    curl_sys::CURLE_OK => Ok(()),
+                    code => Err(curl::Error::new(code).into()),
+                }
+            }
             VersionNegotiationInner::Strict(version) => {
                 easy.http_version(version).map_err(Into::into)
             }
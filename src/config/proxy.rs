@@ -1,5 +1,6 @@
 use super::setopt::{EasyHandle, SetOpt, SetOptError};
-use std::iter::FromIterator;
+use http::Uri;
+use std::{fmt, iter::FromIterator, net::IpAddr, sync::Arc};
 
 /// A list of host names that do not require a proxy to get reached, even if one
 /// is specified.
@@ -20,9 +21,496 @@ impl FromIterator<String> for Blacklist {
     }
 }
 
+impl Blacklist {
+    /// Check whether requests to the given host should skip the proxy.
+    pub(crate) fn matches(&self, host: &str) -> bool {
+        self.skip
+            .split(',')
+            .filter_map(NoProxy::parse)
+            .any(|pattern| pattern.matches(host))
+    }
+}
+
 impl SetOpt for Blacklist {
     fn set_opt(&self, easy: &mut EasyHandle) -> Result<(), SetOptError> {
         easy.noproxy(&self.skip)?;
         Ok(())
     }
 }
+
+/// A set of proxies to use for requests, along with rules for picking the
+/// proxy to use for each request.
+///
+/// Each request is sent using the proxy set for the scheme of the request URI
+/// using [`ProxyConfig::http`] or [`ProxyConfig::https`], or the proxy set
+/// using [`ProxyConfig::socks`] if there is no proxy for its scheme. Requests
+/// to hosts matching the no proxy list are always sent directly, and a
+/// [selector](ProxyConfig::selector) can be used to pick a proxy for requests
+/// programmatically.
+///
+/// Set a configuration for a client or request using
+/// [`Configurable::proxy_config`](super::Configurable::proxy_config). The
+/// proxy actually used for a request can be retrieved from the response using
+/// [`ResponseExt::proxy`](crate::ResponseExt::proxy).
+///
+/// # Examples
+///
+/// ```
+/// use isahc::{config::ProxyConfig, prelude::*, HttpClient};
+///
+/// let client = HttpClient::builder()
+///     .proxy_config(
+///         ProxyConfig::new()
+///             .http("http://proxy.internal:3128".parse()?)
+///             .https("http://proxy.internal:3128".parse()?)
+///             .no_proxy(["localhost", ".internal", "10.0.0.0/8"]),
+///     )
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone, Default)]
+pub struct ProxyConfig {
+    http: Option<Uri>,
+    https: Option<Uri>,
+    socks: Option<Uri>,
+    no_proxy: Vec<NoProxy>,
+    selector: Option<Selector>,
+}
+
+type SelectFn = dyn Fn(&Uri) -> Option<Uri> + Send + Sync;
+
+#[derive(Clone)]
+struct Selector(Arc<SelectFn>);
+
+impl ProxyConfig {
+    /// Create a new configuration that does not use any proxies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a configuration from the proxy environment variables of the
+    /// current process.
+    ///
+    /// The following variables are used:
+    ///
+    /// - `HTTP_PROXY`: The proxy to use for `http` requests.
+    /// - `HTTPS_PROXY`: The proxy to use for `https` requests.
+    /// - `ALL_PROXY`: The proxy to use for requests that no other proxy is set
+    ///   for.
+    /// - `NO_PROXY`: A comma-separated list of hosts to never use a proxy for,
+    ///   in the format accepted by [`ProxyConfig::no_proxy`].
+    ///
+    /// Each variable may also be given in lowercase, such as `https_proxy`,
+    /// which takes precedence over the uppercase variable if both are set.
+    /// Variables set to an empty value are treated as if they were not set.
+    /// Proxies without a scheme are assumed to be HTTP proxies, and proxies
+    /// that are not valid URIs are ignored.
+    ///
+    /// Note that `HTTP_PROXY` can be set by the client of a CGI program through
+    /// the `Proxy` request header, so this should not be used in CGI programs.
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let get = |name: &str| {
+            [name.to_owned(), name.to_ascii_uppercase()]
+                .iter()
+                .filter_map(|name| var(name))
+                .find(|value| !value.trim().is_empty())
+        };
+
+        let proxy = |name: &str| {
+            let value = get(name)?;
+            let value = value.trim();
+
+            let result = if value.contains("://") {
+                value.parse::<Uri>()
+            } else {
+                format!("http://{}", value).parse::<Uri>()
+            };
+
+            match result {
+                Ok(uri) => Some(uri),
+                Err(e) => {
+                    tracing::warn!("ignoring invalid proxy in {}: {}", name, e);
+                    None
+                }
+            }
+        };
+
+        let mut config = Self {
+            http: proxy("http_proxy"),
+            https: proxy("https_proxy"),
+            socks: proxy("all_proxy"),
+            ..Self::default()
+        };
+
+        if let Some(no_proxy) = get("no_proxy") {
+            config = config.no_proxy(no_proxy.split(','));
+        }
+
+        config
+    }
+
+    /// Set the proxy to use for `http` requests.
+    #[must_use = "builders have no effect if unused"]
+    pub fn http(mut self, proxy: Uri) -> Self {
+        self.http = Some(proxy);
+        self
+    }
+
+    /// Set the proxy to use for `https` requests.
+    #[must_use = "builders have no effect if unused"]
+    pub fn https(mut self, proxy: Uri) -> Self {
+        self.https = Some(proxy);
+        self
+    }
+
+    /// Set the proxy to use for requests that no proxy is set for using
+    /// [`ProxyConfig::http`] or [`ProxyConfig::https`].
+    ///
+    /// This is typically a SOCKS proxy, such as `socks5h://proxy:1080`, but any
+    /// proxy URI supported by
+    /// [`Configurable::proxy`](super::Configurable::proxy) can be used.
+    #[must_use = "builders have no effect if unused"]
+    pub fn socks(mut self, proxy: Uri) -> Self {
+        self.socks = Some(proxy);
+        self
+    }
+
+    /// Add hosts to never use a proxy for.
+    ///
+    /// Each entry can be one of the following:
+    ///
+    /// - `*`, which disables proxies for all hosts.
+    /// - A host name, like `example.org`, which matches the host itself and
+    ///   all of its subdomains. A leading `.` or `*.` is ignored, so
+    ///   `.example.org` is equivalent.
+    /// - An IP address, like `10.1.2.3` or `::1`, which matches requests to
+    ///   that address.
+    /// - An IP address range in CIDR notation, like `10.0.0.0/8` or
+    ///   `fd00::/8`, which matches requests to any address in the range.
+    ///
+    /// Host names are matched case-insensitively. Host names are not resolved
+    /// before matching, so address ranges only match requests to IP
+    /// addresses. Empty and invalid entries are ignored.
+    #[must_use = "builders have no effect if unused"]
+    pub fn no_proxy<I, T>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        self.no_proxy.extend(
+            hosts
+                .into_iter()
+                .filter_map(|host| NoProxy::parse(host.as_ref())),
+        );
+        self
+    }
+
+    /// Set a function to select the proxy to use for each request.
+    ///
+    /// The function is called with the URI of each request not matching the no
+    /// proxy list. If it returns a proxy, the request is sent using it,
+    /// otherwise a proxy is picked based on the scheme of the request as
+    /// usual.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::config::ProxyConfig;
+    ///
+    /// let config = ProxyConfig::new().selector(|uri| {
+    ///     if uri.host()?.ends_with(".onion") {
+    ///         "socks5h://127.0.0.1:9050".parse().ok()
+    ///     } else {
+    ///         None
+    ///     }
+    /// });
+    /// ```
+    #[must_use = "builders have no effect if unused"]
+    pub fn selector<F>(mut self, selector: F) -> Self
+    where
+        F: Fn(&Uri) -> Option<Uri> + Send + Sync + 'static,
+    {
+        self.selector = Some(Selector(Arc::new(selector)));
+        self
+    }
+
+    /// Select the proxy to use for a request to the given URI, or `None` if
+    /// the request should be sent directly.
+    pub(crate) fn select(&self, uri: &Uri) -> Option<Uri> {
+        let host = unbracketed_host(uri)?;
+
+        if self.no_proxy.iter().any(|pattern| pattern.matches(host)) {
+            return None;
+        }
+
+        if let Some(proxy) = self
+            .selector
+            .as_ref()
+            .and_then(|selector| (selector.0)(uri))
+        {
+            return Some(proxy);
+        }
+
+        let proxy = match uri.scheme_str() {
+            Some("http") => self.http.as_ref(),
+            Some("https") => self.https.as_ref(),
+            _ => None,
+        };
+
+        proxy.or(self.socks.as_ref()).cloned()
+    }
+}
+
+/// Get the host of a URI, without the brackets around IPv6 addresses.
+pub(crate) fn unbracketed_host(uri: &Uri) -> Option<&str> {
+    let host = uri.host()?;
+
+    Some(
+        host.strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host),
+    )
+}
+
+impl fmt::Debug for ProxyConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyConfig")
+            .field("http", &self.http)
+            .field("https", &self.https)
+            .field("socks", &self.socks)
+            .field("no_proxy", &self.no_proxy)
+            .field("selector", &self.selector.is_some())
+            .finish()
+    }
+}
+
+/// An entry in a no proxy list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum NoProxy {
+    /// Matches every host.
+    All,
+
+    /// Matches a host name and its subdomains.
+    Host(String),
+
+    /// Matches IP addresses in a range, given as an address and prefix length.
+    Cidr(IpAddr, u8),
+}
+
+impl NoProxy {
+    fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+
+        if s == "*" {
+            return Some(Self::All);
+        }
+
+        if let Some((addr, prefix)) = s.split_once('/') {
+            let addr = addr.parse::<IpAddr>().ok()?;
+            let prefix = prefix.parse::<u8>().ok()?;
+            let max = if addr.is_ipv4() { 32 } else { 128 };
+
+            return (prefix <= max).then_some(Self::Cidr(addr, prefix));
+        }
+
+        let unbracketed = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .unwrap_or(s);
+
+        match unbracketed.parse::<IpAddr>() {
+            Ok(IpAddr::V4(addr)) => Some(Self::Cidr(addr.into(), 32)),
+            Ok(IpAddr::V6(addr)) => Some(Self::Cidr(addr.into(), 128)),
+            Err(_) => {
+                let host = s.trim_start_matches("*.").trim_start_matches('.');
+
+                (!host.is_empty()).then(|| Self::Host(host.to_ascii_lowercase()))
+            }
+        }
+    }
+
+    fn matches(&self, host: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Host(pattern) => {
+                let host = host.trim_end_matches('.').to_ascii_lowercase();
+
+                host == *pattern
+                    || host
+                        .strip_suffix(pattern.as_str())
+                        .is_some_and(|rest| rest.ends_with('.'))
+            }
+            Self::Cidr(network, prefix) => match (host.parse::<IpAddr>(), network) {
+                (Ok(IpAddr::V4(addr)), IpAddr::V4(network)) => {
+                    let mask = u32::MAX.checked_shl(32 - *prefix as u32).unwrap_or(0);
+
+                    u32::from(addr) & mask == u32::from(*network) & mask
+                }
+                (Ok(IpAddr::V6(addr)), IpAddr::V6(network)) => {
+                    let mask = u128::MAX.checked_shl(128 - *prefix as u32).unwrap_or(0);
+
+                    u128::from(addr) & mask == u128::from(*network) & mask
+                }
+                _ => false,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn from_vars(vars: &[(&str, &str)]) -> ProxyConfig {
+        let vars = vars.iter().copied().collect::<HashMap<_, _>>();
+
+        ProxyConfig::from_vars(|name| vars.get(name).map(|value| value.to_string()))
+    }
+
+    fn select(config: &ProxyConfig, uri: &str) -> Option<String> {
+        config
+            .select(&uri.parse().unwrap())
+            .map(|proxy| proxy.to_string())
+    }
+
+    #[test]
+    fn from_env_prefers_lowercase_variables() {
+        let config = from_vars(&[
+            ("http_proxy", "http://lower:3128"),
+            ("HTTP_PROXY", "http://upper:3128"),
+            ("HTTPS_PROXY", "http://upper:3129"),
+            ("https_proxy", ""),
+            ("ALL_PROXY", "socks5h://socks:1080"),
+        ]);
+
+        assert_eq!(config.http.unwrap(), "http://lower:3128");
+        assert_eq!(config.https.unwrap(), "http://upper:3129");
+        assert_eq!(config.socks.unwrap(), "socks5h://socks:1080");
+    }
+
+    #[test]
+    fn from_env_defaults_to_http_scheme() {
+        let config = from_vars(&[("https_proxy", "proxy:3128"), ("http_proxy", "not a uri")]);
+
+        assert_eq!(config.https.unwrap(), "http://proxy:3128");
+        assert_eq!(config.http, None);
+    }
+
+    #[test]
+    fn from_env_parses_no_proxy() {
+        let config = from_vars(&[("NO_PROXY", "localhost, .internal,10.0.0.0/8,,::1")]);
+
+        assert_eq!(
+            config.no_proxy,
+            [
+                NoProxy::Host("localhost".into()),
+                NoProxy::Host("internal".into()),
+                NoProxy::Cidr("10.0.0.0".parse().unwrap(), 8),
+                NoProxy::Cidr("::1".parse().unwrap(), 128),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_no_proxy_entries_are_ignored() {
+        let config = ProxyConfig::new().no_proxy(["10.0.0.0/33", "foo/8", "", "*."]);
+
+        assert!(config.no_proxy.is_empty());
+    }
+
+    #[test]
+    fn no_proxy_host_matches_subdomains() {
+        let pattern = NoProxy::parse("*.Example.org").unwrap();
+
+        assert!(pattern.matches("example.org"));
+        assert!(pattern.matches("api.EXAMPLE.org"));
+        assert!(pattern.matches("example.org."));
+        assert!(!pattern.matches("badexample.org"));
+        assert!(!pattern.matches("example.org.evil"));
+    }
+
+    #[test]
+    fn no_proxy_cidr_matches_addresses_in_range() {
+        let v4 = NoProxy::parse("192.168.0.0/16").unwrap();
+
+        assert!(v4.matches("192.168.1.2"));
+        assert!(!v4.matches("192.169.0.1"));
+        assert!(!v4.matches("::ffff:192.168.1.2"));
+        assert!(!v4.matches("192.168.example"));
+
+        let v6 = NoProxy::parse("fd00::/8").unwrap();
+
+        assert!(v6.matches("fd12::1"));
+        assert!(!v6.matches("fe80::1"));
+
+        assert!(NoProxy::parse("0.0.0.0/0").unwrap().matches("8.8.8.8"));
+    }
+
+    #[test]
+    fn select_prefers_scheme_specific_proxy() {
+        let config = ProxyConfig::new()
+            .https("http://secure:3128".parse().unwrap())
+            .socks("socks5h://socks:1080".parse().unwrap());
+
+        assert_eq!(
+            select(&config, "https://example.org").unwrap(),
+            "http://secure:3128/"
+        );
+        assert_eq!(
+            select(&config, "http://example.org").unwrap(),
+            "socks5h://socks:1080/"
+        );
+    }
+
+    #[test]
+    fn select_skips_no_proxy_hosts() {
+        let config = ProxyConfig::new()
+            .socks("socks5h://socks:1080".parse().unwrap())
+            .no_proxy(["example.org", "::1"])
+            .selector(|_| Some("http://selected:3128".parse().unwrap()));
+
+        assert_eq!(select(&config, "https://www.example.org"), None);
+        assert_eq!(select(&config, "http://[::1]:8080"), None);
+        assert_eq!(
+            select(&config, "http://example.com").unwrap(),
+            "http://selected:3128/"
+        );
+    }
+
+    #[test]
+    fn select_falls_back_if_selector_returns_none() {
+        let config = ProxyConfig::new()
+            .http("http://plain:3128".parse().unwrap())
+            .selector(|_| None);
+
+        assert_eq!(
+            select(&config, "http://example.org").unwrap(),
+            "http://plain:3128/"
+        );
+        assert_eq!(select(&config, "https://example.org"), None);
+    }
+
+    #[test]
+    fn no_proxy_wildcard_disables_all_proxies() {
+        let config = from_vars(&[("all_proxy", "socks5://socks:1080"), ("no_proxy", "*")]);
+
+        assert_eq!(select(&config, "http://example.org"), None);
+    }
+
+    #[test]
+    fn blacklist_matches_hosts_and_addresses() {
+        let blacklist = ["example.org", "10.0.0.0/8"]
+            .iter()
+            .map(|host| host.to_string())
+            .collect::<Blacklist>();
+
+        assert!(blacklist.matches("www.example.org"));
+        assert!(blacklist.matches("10.1.2.3"));
+        assert!(!blacklist.matches("example.com"));
+    }
+}
@@ -63,8 +63,10 @@ define_request_config! {
     interface: Option<InterfaceString>,
     ip_version: Option<IpVersion>,
     dial: Option<Dialer>,
+    dns_over_https: Option<crate::net::DnsOverHttps>,
     proxy: Option<Option<http::Uri>>,
     proxy_blacklist: Option<proxy::Blacklist>,
+    proxy_config: Option<proxy::ProxyConfig>,
     proxy_authentication: Option<Authentication>,
     proxy_credentials: Option<Credentials>,
     max_upload_speed: Option<u64>,
@@ -77,9 +79,22 @@ define_request_config! {
     #[cfg(feature = "tls")]
     proxy_tls_config: Option<crate::tls::TlsConfig>,
 
+    #[cfg(feature = "tls")]
+    tls_info: Option<bool>,
+
+    // Whether the TLS configuration of the request itself, rather than of the
+    // client, sets an identity, which then takes precedence over the identity
+    // resolver of the client.
+    #[cfg(feature = "tls")]
+    has_own_identity: Option<bool>,
+
     // Used by interceptors
     redirect_policy: Option<RedirectPolicy>,
     auto_referer: Option<bool>,
+    retry_policy: Option<RetryPolicy>,
+    hedge: Option<(Duration, u32)>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<std::time::SystemTime>,
     title_case_headers: Option<bool>,
 }
 
@@ -99,6 +114,26 @@ impl RequestConfig {
             ..Default::default()
         }
     }
+
+    /// Get the proxy a request to the given URI will be sent through, if any.
+    ///
+    /// This mirrors how curl picks a proxy, including falling back to the
+    /// proxy environment variables if no proxy is configured.
+    pub(crate) fn proxy_for(&self, uri: &http::Uri) -> Option<http::Uri> {
+        if let Some(proxy_config) = self.proxy_config.as_ref() {
+            return proxy_config.select(uri);
+        }
+
+        let proxy = match self.proxy.as_ref() {
+            Some(proxy) => proxy.clone()?,
+            None => ProxyConfig::from_env().select(uri)?,
+        };
+
+        match (self.proxy_blacklist.as_ref(), proxy::unbracketed_host(uri)) {
+            (Some(blacklist), Some(host)) if blacklist.matches(host) => None,
+            _ => Some(proxy),
+        }
+    }
 }
 
 impl SetOpt for RequestConfig {
@@ -214,6 +249,14 @@ impl SetOpt for RequestConfig {
             config.set_opt_proxy(easy)?;
         }
 
+        #[cfg(feature = "tls")]
+        if let Some(enable) = self.tls_info {
+            // Certificate info from curl is only used as a fallback for TLS
+            // backends we cannot query directly.
+            easy.certinfo(enable)?;
+            easy.get_mut().capture_tls_info = enable;
+        }
+
         if let Some(enable) = self.enable_metrics {
             easy.progress(enable)?;
         }
@@ -0,0 +1,224 @@
+use crate::error::ErrorKind;
+use http::StatusCode;
+use std::time::Duration;
+
+/// Describes a policy for automatically retrying failed requests.
+///
+/// Only requests using an idempotent method (such as `GET`, `HEAD`, `PUT`, or
+/// `DELETE`) are ever retried, since it is not generally safe to repeat other
+/// kinds of requests without the risk of performing an action on the server
+/// more than once.
+///
+/// Between each attempt the client waits an exponentially increasing amount of
+/// time, optionally with a random jitter added to avoid many clients retrying
+/// in lockstep. If the server includes a `Retry-After` header in a response
+/// that is about to be retried, then the delay requested by the server is used
+/// instead.
+///
+/// In order to retry a request, the request body must be sent again. If the
+/// body cannot be rewound back to the beginning, then a
+/// [`RequestBodyNotRewindable`](crate::error::ErrorKind::RequestBodyNotRewindable)
+/// error is returned instead.
+///
+/// By default no retries are performed.
+///
+/// # Examples
+///
+/// ```
+/// use isahc::{config::RetryPolicy, error::ErrorKind, prelude::*, HttpClient};
+/// use std::time::Duration;
+///
+/// let client = HttpClient::builder()
+///     .retry_policy(RetryPolicy::new(3)
+///         .error_kinds([ErrorKind::ConnectionFailed, ErrorKind::Timeout])
+///         .status_codes([502, 503, 504])
+///         .backoff(Duration::from_millis(200), Duration::from_secs(10)))
+///     .build()?;
+/// # Ok::<(), isahc::Error>(())
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RetryPolicy {
+    max_retries: u32,
+    error_kinds: Vec<ErrorKind>,
+    status_codes: Vec<StatusCode>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+    respect_retry_after: bool,
+}
+
+impl RetryPolicy {
+    /// Create a new policy that retries a request at most `max_retries` times
+    /// after the initial attempt.
+    ///
+    /// The policy starts out retrying on the
+    /// [`ConnectionFailed`](ErrorKind::ConnectionFailed),
+    /// [`NameResolution`](ErrorKind::NameResolution), and
+    /// [`Timeout`](ErrorKind::Timeout) error kinds, as well as on 429 (Too
+    /// Many Requests) and 503 (Service Unavailable) responses. The initial
+    /// backoff is 100 milliseconds, growing up to a maximum of 30 seconds,
+    /// with jitter enabled.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            error_kinds: vec![
+                ErrorKind::ConnectionFailed,
+                ErrorKind::NameResolution,
+                ErrorKind::Timeout,
+            ],
+            status_codes: vec![
+                StatusCode::TOO_MANY_REQUESTS,
+                StatusCode::SERVICE_UNAVAILABLE,
+            ],
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+            respect_retry_after: true,
+        }
+    }
+
+    /// Create a policy that never retries. This is the default.
+    pub fn none() -> Self {
+        Self::new(0)
+    }
+
+    /// Set the kinds of errors that should cause a request to be retried,
+    /// replacing any previously set.
+    #[must_use = "builders have no effect if unused"]
+    pub fn error_kinds<I>(mut self, kinds: I) -> Self
+    where
+        I: IntoIterator<Item = ErrorKind>,
+    {
+        self.error_kinds = kinds.into_iter().collect();
+        self
+    }
+
+    /// Set the response status codes that should cause a request to be
+    /// retried, replacing any previously set.
+    ///
+    /// Values that are not valid status codes are ignored.
+    #[must_use = "builders have no effect if unused"]
+    pub fn status_codes<I>(mut self, codes: I) -> Self
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        self.status_codes = codes
+            .into_iter()
+            .filter_map(|code| StatusCode::from_u16(code).ok())
+            .collect();
+        self
+    }
+
+    /// Set the delay before the first retry and the upper bound that the
+    /// exponentially growing delay is capped at.
+    #[must_use = "builders have no effect if unused"]
+    pub fn backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self
+    }
+
+    /// Enable or disable adding a random jitter to each delay.
+    ///
+    /// When enabled, each delay is chosen randomly between half and the full
+    /// amount of the computed backoff.
+    #[must_use = "builders have no effect if unused"]
+    pub fn jitter(mut self, enable: bool) -> Self {
+        self.jitter = enable;
+        self
+    }
+
+    /// Enable or disable honoring the `Retry-After` response header.
+    ///
+    /// When enabled and a response to be retried includes a `Retry-After`
+    /// header, the client waits as long as the server asked instead of the
+    /// computed backoff. If the server asks to wait longer than the maximum
+    /// backoff, the response is returned as-is without retrying.
+    #[must_use = "builders have no effect if unused"]
+    pub fn respect_retry_after(mut self, enable: bool) -> Self {
+        self.respect_retry_after = enable;
+        self
+    }
+
+    pub(crate) fn max_retries(&self) -> u32 {
+        self.max_retries
+    }
+
+    pub(crate) fn respects_retry_after(&self) -> bool {
+        self.respect_retry_after
+    }
+
+    pub(crate) fn max_backoff(&self) -> Duration {
+        self.max_backoff
+    }
+
+    pub(crate) fn should_retry_error(&self, kind: &ErrorKind) -> bool {
+        self.error_kinds.contains(kind)
+    }
+
+    pub(crate) fn should_retry_status(&self, status: StatusCode) -> bool {
+        self.status_codes.contains(&status)
+    }
+
+    /// Compute how long to wait before the given retry attempt, starting from
+    /// zero.
+    pub(crate) fn backoff_for(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .checked_mul(1 << attempt.min(31))
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff);
+
+        if self.jitter {
+            let half = backoff / 2;
+            half + half.mul_f64(fastrand::f64())
+        } else {
+            backoff
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_exponentially_up_to_max() {
+        let policy = RetryPolicy::new(10)
+            .backoff(Duration::from_millis(100), Duration::from_secs(1))
+            .jitter(false);
+
+        assert_eq!(policy.backoff_for(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff_for(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff_for(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff_for(4), Duration::from_secs(1));
+        assert_eq!(policy.backoff_for(40), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let policy =
+            RetryPolicy::new(10).backoff(Duration::from_millis(100), Duration::from_secs(1));
+
+        for _ in 0..100 {
+            let backoff = policy.backoff_for(1);
+
+            assert!(backoff >= Duration::from_millis(100));
+            assert!(backoff <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn invalid_status_codes_are_ignored() {
+        let policy = RetryPolicy::new(1).status_codes([503, 42]);
+
+        assert!(policy.should_retry_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert_eq!(policy.status_codes.len(), 1);
+    }
+}
@@ -10,6 +10,9 @@ use std::{
 
 use crate::ResponseExt;
 
+#[cfg(feature = "json")]
+pub use crate::json_lines::JsonLineError;
+
 /// A non-exhaustive list of error types that can occur while sending an HTTP
 /// request or receiving an HTTP response.
 ///
@@ -26,6 +29,22 @@ pub enum ErrorKind {
     /// The server certificate could not be validated.
     BadServerCertificate,
 
+    /// The server certificate, or a certificate in its chain, has been revoked
+    /// by its issuer.
+    ///
+    /// Revocation is only checked when configured, such as by using
+    /// [`TlsConfigBuilder::crl_file`](crate::tls::TlsConfigBuilder::crl_file)
+    /// or
+    /// [`TlsConfigBuilder::require_ocsp_stapling`](crate::tls::TlsConfigBuilder::require_ocsp_stapling),
+    /// or if the TLS backend checks revocation by default.
+    CertificateRevoked,
+
+    /// The request was not sent because the circuit breaker for the server is
+    /// open after too many recent failures.
+    ///
+    /// See [`CircuitBreaker`](crate::config::CircuitBreaker) for details.
+    CircuitOpen,
+
     /// The HTTP client failed to initialize.
     ///
     /// This error can occur when trying to create a client with invalid
@@ -75,6 +94,15 @@ pub enum ErrorKind {
     /// hostname simply does not exist.
     NameResolution,
 
+    /// The public key of the server certificate did not match any of the
+    /// configured pinned public keys.
+    ///
+    /// This may indicate that the connection is being intercepted, or that the
+    /// server has switched to a new key. See
+    /// [`TlsConfigBuilder::pinned_public_keys`](crate::tls::TlsConfigBuilder::pinned_public_keys)
+    /// for details.
+    PinnedPublicKeyMismatch,
+
     /// The server made an unrecoverable HTTP protocol violation. This indicates
     /// a bug in the server. Retrying a request that returns this error is
     /// likely to produce the same error.
@@ -115,6 +143,10 @@ impl ErrorKind {
         match self {
             Self::BadClientCertificate => Some("a problem occurred with the local certificate"),
             Self::BadServerCertificate => Some("the server certificate could not be validated"),
+            Self::CertificateRevoked => Some("the server certificate has been revoked"),
+            Self::CircuitOpen => {
+                Some("request not sent because the circuit breaker for the server is open")
+            }
             Self::ClientInitialization => Some("failed to initialize client"),
             Self::ConnectionFailed => Some("failed to connect to the server"),
             Self::InvalidContentEncoding => Some(
@@ -128,6 +160,9 @@ impl ErrorKind {
                 "an error occurred when attempting to establish a TLS connection due to a problem with the TLS configuration",
             ),
             Self::NameResolution => Some("failed to resolve host name"),
+            Self::PinnedPublicKeyMismatch => {
+                Some("the server public key did not match any pinned public key")
+            }
             Self::ProtocolViolation => {
                 Some("the server made an unrecoverable HTTP protocol violation")
             }
@@ -238,12 +273,17 @@ impl Error {
             std::io::Error as error => error.into(),
             curl::Error as error => {
                 Self::with_context(
-                    if error.is_ssl_certproblem() {
+                    if error.code() == curl_sys::CURLE_SSL_PINNEDPUBKEYNOTMATCH {
+                        ErrorKind::PinnedPublicKeyMismatch
+                    } else if is_revoked(&error) {
+                        ErrorKind::CertificateRevoked
+                    } else if error.is_ssl_certproblem() {
                         ErrorKind::BadClientCertificate
                     } else if error.is_peer_failed_verification()
                         || error.is_ssl_cacert()
                         || error.is_ssl_cipher()
                         || error.is_ssl_issuer_error()
+                        || error.code() == curl_sys::CURLE_SSL_INVALIDCERTSTATUS
                     {
                         ErrorKind::BadServerCertificate
                     } else if error.is_interface_failed() {
@@ -256,7 +296,7 @@ impl Error {
                         ErrorKind::InvalidCredentials
                     } else if error.is_url_malformed() {
                         ErrorKind::InvalidRequest
-                    } else if error.is_ssl_cacert_badfile() {
+                    } else if error.is_ssl_cacert_badfile() || error.is_ssl_crl_badfile() {
                         ErrorKind::InvalidTlsConfiguration
                     } else if error.is_couldnt_resolve_host() || error.is_couldnt_resolve_proxy() {
                         ErrorKind::NameResolution
@@ -351,6 +391,7 @@ impl Error {
     pub fn is_server(&self) -> bool {
         match self.kind() {
             ErrorKind::BadServerCertificate
+            | ErrorKind::CertificateRevoked
             | ErrorKind::ProtocolViolation
             | ErrorKind::TooManyRedirects => true,
             _ => false,
@@ -378,6 +419,8 @@ impl Error {
         match self.kind() {
             ErrorKind::BadClientCertificate
             | ErrorKind::BadServerCertificate
+            | ErrorKind::CertificateRevoked
+            | ErrorKind::PinnedPublicKeyMismatch
             | ErrorKind::TlsEngine => true,
             _ => false,
         }
@@ -512,6 +555,21 @@ impl From<http::Error> for Error {
     }
 }
 
+/// Check whether a curl error was caused by a revoked certificate.
+///
+/// Curl does not have a dedicated error code for revocation, so we have to
+/// rely on the message reported by the TLS backend, such as "certificate
+/// revoked" from OpenSSL or "invalid peer certificate: Revoked" from Rustls.
+fn is_revoked(error: &curl::Error) -> bool {
+    (error.is_peer_failed_verification()
+        || error.is_ssl_cacert()
+        || error.code() == curl_sys::CURLE_SSL_INVALIDCERTSTATUS)
+        && error.extra_description().is_some_and(|message| {
+            let message = message.to_ascii_lowercase();
+            message.contains("revoked") || message.contains("revocation reason")
+        })
+}
+
 /// Internal trait object for source errors. This is used to capture additional
 /// methods about the source error value in the vtable.
 trait SourceError: StdError + Send + Sync + 'static {
@@ -538,4 +596,30 @@ mod tests {
     use super::*;
 
     static_assertions::assert_impl_all!(Error: Send, Sync);
+
+    #[test]
+    fn pinned_public_key_mismatch_has_distinct_kind() {
+        let error = Error::from_any(curl::Error::new(curl_sys::CURLE_SSL_PINNEDPUBKEYNOTMATCH));
+
+        assert_eq!(error.kind(), ErrorKind::PinnedPublicKeyMismatch);
+        assert!(error.is_tls());
+    }
+
+    #[test]
+    fn revoked_certificate_has_distinct_kind() {
+        let mut error = curl::Error::new(curl_sys::CURLE_PEER_FAILED_VERIFICATION);
+        error.set_extra(String::from("SSL certificate problem: certificate revoked"));
+        let error = Error::from_any(error);
+
+        assert_eq!(error.kind(), ErrorKind::CertificateRevoked);
+        assert!(error.is_tls());
+
+        let mut error = curl::Error::new(curl_sys::CURLE_SSL_INVALIDCERTSTATUS);
+        error.set_extra(String::from("No OCSP response received"));
+
+        assert_eq!(
+            Error::from_any(error).kind(),
+            ErrorKind::BadServerCertificate
+        );
+    }
 }
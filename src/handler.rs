@@ -5,12 +5,12 @@ use crate::{
     error::{Error, ErrorKind},
     metrics::Metrics,
     parsing::{parse_header, parse_status_line},
-    response::{LocalAddr, RemoteAddr},
+    response::{LocalAddr, Proxy, RemoteAddr},
     trailer::TrailerWriter,
 };
 use async_channel::Sender;
 use curl::easy::{InfoType, ReadError, SeekResult, WriteError};
-use curl_sys::{CURL, CURL_BLOB_NOCOPY, CURLE_OK, CURLoption, curl_blob};
+use curl_sys::{CURL, CURL_BLOB_NOCOPY, CURLE_OK, CURLINFO, CURLINFO_LONG, CURLoption, curl_blob};
 use futures_lite::io::{AsyncRead, AsyncWrite};
 use http::Response;
 use sluice::pipe;
@@ -22,13 +22,17 @@ use std::{
     future::Future,
     io, mem,
     net::SocketAddr,
-    os::raw::{c_char, c_long},
+    os::raw::{c_char, c_int, c_long},
     pin::Pin,
     ptr,
     sync::{Arc, OnceLock},
     task::{Context, Poll, Waker},
 };
 
+// Not yet exposed by curl-sys.
+const CURLINFO_HTTP_VERSION: CURLINFO = CURLINFO_LONG + 46;
+const CURLINFO_USED_PROXY: CURLINFO = CURLINFO_LONG + 66;
+
 /// Stores a copy of the original request body
 pub(crate) struct RequestBody(pub(crate) AsyncBody);
 
@@ -109,6 +113,18 @@ pub(crate) struct RequestHandler {
     /// immediately after the easy handle.
     blobs: HashMap<CURLoption, Arc<[u8]>>,
 
+    /// Other values referenced by options set on the easy handle, such as
+    /// temporary files, which must be kept alive until the handler is dropped.
+    pub(crate) retained: Vec<Arc<dyn std::any::Any + Send + Sync>>,
+
+    /// A socket dialed ahead of time to use for the connection, which is handed
+    /// to curl the first time it opens a socket.
+    pub(crate) socket: Option<crate::net::Socket>,
+
+    /// The proxy configured for the request, which is reported on the response
+    /// if curl ends up using it.
+    pub(crate) proxy: Option<http::Uri>,
+
     /// Raw pointer to the associated curl easy handle. The pointer is not owned
     /// by this struct, but the parent struct to this one, so we know it will be
     /// valid at least for the lifetime of this struct (assuming all other
@@ -117,6 +133,20 @@ pub(crate) struct RequestHandler {
 
     /// If true, do not warn about prematurely closed responses.
     pub(crate) disable_connection_reuse_log: bool,
+
+    /// If true, include information about the secure connection in the
+    /// response.
+    #[cfg(feature = "tls")]
+    pub(crate) capture_tls_info: bool,
+
+    /// Information about the secure connection of the current response, if
+    /// captured.
+    #[cfg(feature = "tls")]
+    tls_info: Option<crate::tls::TlsInfo>,
+
+    /// If true, log the secrets of TLS sessions created by OpenSSL.
+    #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+    pub(crate) log_tls_keys: bool,
 }
 
 // Would be send implicitly except for the raw CURL pointer.
@@ -159,8 +189,17 @@ impl RequestHandler {
             response_trailer_writer: TrailerWriter::new(),
             metrics: None,
             blobs: Default::default(),
+            retained: Vec::new(),
+            socket: None,
+            proxy: None,
             handle: ptr::null_mut(),
             disable_connection_reuse_log: false,
+            #[cfg(feature = "tls")]
+            capture_tls_info: false,
+            #[cfg(feature = "tls")]
+            tls_info: None,
+            #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+            log_tls_keys: false,
         };
 
         // Create a future that resolves when the handler receives the response
@@ -308,6 +347,20 @@ impl RequestHandler {
             builder = builder.extension(RemoteAddr(addr));
         }
 
+        if let Some(proxy) = self
+            .proxy
+            .clone()
+            .filter(|_| self.get_used_proxy())
+            .and_then(Proxy::new)
+        {
+            builder = builder.extension(proxy);
+        }
+
+        #[cfg(feature = "tls")]
+        if let Some(info) = self.tls_info.take() {
+            builder = builder.extension(info);
+        }
+
         // Keep the request body around in case interceptors need access to
         // it. Otherwise we're just going to drop it later.
         builder = builder.extension(RequestBody(mem::take(&mut self.request_body)));
@@ -372,6 +425,24 @@ impl RequestHandler {
         Some(port as u16)
     }
 
+    fn get_used_proxy(&mut self) -> bool {
+        if self.handle.is_null() {
+            return false;
+        }
+
+        let mut used: c_long = 0;
+
+        unsafe {
+            if curl_sys::curl_easy_getinfo(self.handle, CURLINFO_USED_PROXY, &mut used)
+                != curl_sys::CURLE_OK
+            {
+                return false;
+            }
+        }
+
+        used != 0
+    }
+
     fn get_local_addr(&mut self) -> Option<SocketAddr> {
         let ip = self.get_local_ip()?.parse().ok()?;
         let port = self.get_local_port()?;
@@ -421,6 +492,25 @@ impl RequestHandler {
 }
 
 impl curl::easy::Handler for RequestHandler {
+    /// Gets called by curl whenever it needs a socket for a new connection.
+    fn open_socket(
+        &mut self,
+        family: c_int,
+        socktype: c_int,
+        protocol: c_int,
+    ) -> Option<curl_sys::curl_socket_t> {
+        if let Some(socket) = self.socket.take() {
+            return Some(socket.into_raw());
+        }
+
+        // Fall back to opening a socket the way the curl crate does by default.
+        struct DefaultHandler;
+
+        impl curl::easy::Handler for DefaultHandler {}
+
+        DefaultHandler.open_socket(family, socktype, protocol)
+    }
+
     /// Gets called by curl for each line of data in the HTTP response header.
     fn header(&mut self, data: &[u8]) -> bool {
         // Abort the request if it has been canceled.
@@ -468,6 +558,14 @@ impl curl::easy::Handler for RequestHandler {
 
         // Is this the end of the response header?
         if data == b"\r\n" {
+            // The TLS session is only accessible while the connection is still
+            // in use, which may no longer be the case by the time a response
+            // without a body is complete, so capture its info now.
+            #[cfg(feature = "tls")]
+            if self.capture_tls_info {
+                self.tls_info = crate::tls::TlsInfo::capture(self.handle);
+            }
+
             // We will acknowledge the end of the header, but we can't complete
             // our response future yet. If curl decides to follow a redirect,
             // then this current response is not the final response and not the
@@ -597,6 +695,18 @@ impl curl::easy::Handler for RequestHandler {
         true
     }
 
+    /// Gets called by curl when creating a new OpenSSL context.
+    #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+    fn ssl_ctx(&mut self, cx: *mut std::os::raw::c_void) -> Result<(), curl::Error> {
+        if self.log_tls_keys {
+            unsafe {
+                crate::tls::KeyLog::attach(cx);
+            }
+        }
+
+        Ok(())
+    }
+
     /// Gets called by curl whenever it wishes to log a debug message.
     ///
     /// Since we're using the log crate, this callback normalizes the debug info
@@ -688,6 +798,8 @@ impl Drop for RequestHandler {
         }
 
         self.blobs.clear();
+
+        self.retained.clear();
     }
 }
 
@@ -820,6 +932,11 @@ impl Metrics {
                     curl_sys::CURLINFO_REDIRECT_TIME,
                     self.inner.redirect_time.as_ptr(),
                 );
+                curl_sys::curl_easy_getinfo(
+                    handle,
+                    CURLINFO_HTTP_VERSION,
+                    self.inner.http_version.as_ptr(),
+                );
             }
         }
     }
@@ -0,0 +1,104 @@
+use crate::{
+    body::AsyncBody,
+    config::request::RequestConfig,
+    error::{Error, ErrorKind},
+    interceptor::{Context, Interceptor, InterceptorFuture},
+    request::RequestExt,
+    retry::is_idempotent,
+    task::delay,
+};
+use futures_lite::future::poll_fn;
+use http::Request;
+use std::{future::Future, pin::Pin, task::Poll};
+
+/// Interceptor that sends duplicate requests when a response is slow to
+/// arrive, according to the request's hedging configuration.
+pub(crate) struct HedgeInterceptor;
+
+impl Interceptor for HedgeInterceptor {
+    type Err = Error;
+
+    fn intercept<'a>(
+        &'a self,
+        request: Request<AsyncBody>,
+        ctx: Context<'a>,
+    ) -> InterceptorFuture<'a, Self::Err> {
+        Box::pin(async move {
+            let (hedge_delay, max_extra) = match request
+                .extensions()
+                .get::<RequestConfig>()
+                .and_then(|config| config.hedge)
+            {
+                Some((hedge_delay, max_extra)) if max_extra > 0 => (hedge_delay, max_extra),
+                _ => return ctx.send(request).await,
+            };
+
+            if !is_idempotent(request.method()) {
+                return ctx.send(request).await;
+            }
+
+            // Every duplicate needs its own copy of the body, which is only
+            // possible for bodies stored in memory.
+            let body = match request.body().try_clone() {
+                Some(body) => body,
+                None => return ctx.send(request).await,
+            };
+
+            let template = request
+                .to_builder()
+                .body(())
+                .map_err(|e| Error::new(ErrorKind::InvalidRequest, e))?;
+
+            let mut attempts = vec![Box::pin(ctx.send(request))];
+            let mut extra = 0;
+            let mut timer = delay(hedge_delay);
+            let mut last_error = None;
+
+            let result = poll_fn(|cx| {
+                loop {
+                    let mut i = 0;
+
+                    while i < attempts.len() {
+                        match attempts[i].as_mut().poll(cx) {
+                            Poll::Ready(Ok(response)) => return Poll::Ready(Ok(response)),
+                            Poll::Ready(Err(e)) => {
+                                drop(attempts.swap_remove(i));
+                                last_error = Some(e);
+                            }
+                            Poll::Pending => i += 1,
+                        }
+                    }
+
+                    if attempts.is_empty() {
+                        return Poll::Ready(Err(last_error.take().unwrap()));
+                    }
+
+                    if extra >= max_extra || Pin::new(&mut timer).poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+
+                    extra += 1;
+                    timer = delay(hedge_delay);
+
+                    tracing::debug!(extra, "response is slow, sending hedged request");
+
+                    let request = template
+                        .to_builder()
+                        .body(body.try_clone().unwrap_or_default())
+                        .map_err(|e| Error::new(ErrorKind::InvalidRequest, e));
+
+                    match request {
+                        Ok(request) => attempts.push(Box::pin(ctx.send(request))),
+                        Err(e) => return Poll::Ready(Err(e)),
+                    }
+                }
+            })
+            .await;
+
+            // Cancel any attempts that lost the race.
+            drop(attempts);
+
+            result
+        })
+    }
+}
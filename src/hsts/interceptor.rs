@@ -0,0 +1,59 @@
+//! Interceptor that upgrades requests to known HSTS hosts and records
+//! `Strict-Transport-Security` headers.
+
+use super::HstsStore;
+use crate::{
+    body::AsyncBody,
+    error::Error,
+    interceptor::{Context, Interceptor, InterceptorFuture},
+    redirect::EffectiveUri,
+};
+use http::Request;
+
+#[derive(Debug)]
+pub(crate) struct HstsInterceptor {
+    store: HstsStore,
+}
+
+impl HstsInterceptor {
+    pub(crate) fn new(store: HstsStore) -> Self {
+        Self { store }
+    }
+}
+
+impl Interceptor for HstsInterceptor {
+    type Err = Error;
+
+    fn intercept<'a>(
+        &'a self,
+        mut request: Request<AsyncBody>,
+        ctx: Context<'a>,
+    ) -> InterceptorFuture<'a, Self::Err> {
+        Box::pin(async move {
+            let upgraded = self.store.upgrade(request.uri());
+
+            if let Some(uri) = upgraded.as_ref() {
+                tracing::debug!(from = %request.uri(), to = %uri, "upgrading request due to HSTS");
+                *request.uri_mut() = uri.clone();
+            }
+
+            let request_uri = request.uri().clone();
+            let mut response = ctx.send(request).await?;
+
+            // Only the first header is considered, as per RFC 6797.
+            if let Some(value) = response
+                .headers()
+                .get(http::header::STRICT_TRANSPORT_SECURITY)
+            {
+                self.store.record(&request_uri, value);
+            }
+
+            // Let the redirect interceptor know which URI was actually used.
+            if let Some(uri) = upgraded {
+                response.extensions_mut().insert(EffectiveUri(uri));
+            }
+
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,47 @@
+//! Types for HTTP Strict Transport Security (HSTS).
+//!
+//! # Strict transport security
+//!
+//! Servers can declare that they should only ever be accessed over HTTPS by
+//! sending a `Strict-Transport-Security` header, as described in [RFC
+//! 6797](https://www.rfc-editor.org/rfc/rfc6797). Isahc ignores this header by
+//! default, but a client can be configured to honor it using
+//! [`HttpClientBuilder::hsts`](crate::HttpClientBuilder::hsts), in which case:
+//!
+//! - The header is recorded in an [`HstsStore`] whenever it is received over
+//!   HTTPS, including the `includeSubDomains` directive. Headers received over
+//!   plain HTTP, or from a host that is an IP address, are ignored.
+//! - Requests using `http` for a host in the store are upgraded to `https`
+//!   before being sent. This applies to each request made while following
+//!   redirects, and to the URI reported by
+//!   [`ResponseExt::effective_uri`](crate::ResponseExt::effective_uri).
+//! - A header with `max-age=0` removes the host from the store again. Larger
+//!   values than one year are treated as one year.
+//!
+//! # Persistence
+//!
+//! A store can be kept in a file using [`HstsStore::open`], so that it
+//! survives process restarts. The file uses the same format as curl's HSTS
+//! cache, so it can be shared with the curl command line tool.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use isahc::{hsts::HstsStore, prelude::*, HttpClient};
+//!
+//! let store = HstsStore::open("hsts.txt")?;
+//! store.preload("example.org", true);
+//!
+//! let client = HttpClient::builder()
+//!     .hsts(store)
+//!     .build()?;
+//!
+//! // Sent using https instead.
+//! let response = client.get("http://www.example.org")?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub(crate) mod interceptor;
+mod store;
+
+pub use self::store::{HstsEntry, HstsStore};
@@ -0,0 +1,654 @@
+use crate::calendar::{civil_from_days, days_from_civil};
+use http::{HeaderValue, Uri, uri::Scheme};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    io::{self, Write},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The longest time a host is remembered for after receiving a header, as in
+/// common browsers. Larger values are clamped, which also keeps expiry times
+/// within the range of the file format.
+const MAX_AGE: Duration = Duration::from_secs(365 * 86400);
+
+/// A host known to require secure connections.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HstsEntry {
+    host: String,
+    include_subdomains: bool,
+    expires: Option<SystemTime>,
+}
+
+impl HstsEntry {
+    /// Get the host name this entry applies to, in lowercase.
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Whether this entry also applies to all subdomains of the host.
+    pub fn include_subdomains(&self) -> bool {
+        self.include_subdomains
+    }
+
+    /// Get the time this entry expires, or `None` if it was preloaded and
+    /// never expires.
+    pub fn expires(&self) -> Option<SystemTime> {
+        self.expires
+    }
+
+    fn is_expired(&self, now: SystemTime) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// A collection of hosts that must only be accessed over HTTPS, following HTTP
+/// Strict Transport Security.
+///
+/// Stores are designed to be shared across many concurrent requests, so
+/// cloning a store returns a new reference to the same store instead of doing
+/// a deep clone.
+///
+/// See the [module documentation](crate::hsts) for details on how a store is
+/// used by a client.
+#[derive(Clone, Default)]
+pub struct HstsStore {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    entries: RwLock<HashMap<String, HstsEntry>>,
+
+    /// File the store is persisted to, if any.
+    file: Option<PathBuf>,
+
+    /// Serializes writes to the file.
+    file_lock: Mutex<()>,
+
+    /// Set when entries have changed since the file was last written.
+    dirty: AtomicBool,
+}
+
+impl HstsStore {
+    /// Create a new, empty store that is kept in memory.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Open a store persisted in the given file, loading any entries it
+    /// contains. If the file does not exist, it is created once the first
+    /// entry is added.
+    ///
+    /// Adding or removing entries writes the file immediately. Entries whose
+    /// expiration is only extended, which happens on most responses from
+    /// known hosts, are written together with the next other change, by
+    /// [`HstsStore::save`], or once the last reference to the store is
+    /// dropped.
+    ///
+    /// The file uses the format of curl's HSTS cache: one host per line,
+    /// prefixed with a dot if it includes subdomains, followed by a quoted
+    /// expiration time in UTC such as `"20301231 23:59:59"`, or `"unlimited"`.
+    /// Lines that are empty or start with `#` are ignored.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let now = SystemTime::now();
+
+        let entries = match fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .filter_map(|line| {
+                    parse_line(line).or_else(|| {
+                        tracing::warn!(?path, "invalid line in HSTS file: {}", line);
+                        None
+                    })
+                })
+                .filter(|entry| !entry.is_expired(now))
+                .map(|entry| (entry.host.clone(), entry))
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            inner: Arc::new(Inner {
+                entries: RwLock::new(entries),
+                file: Some(path),
+                file_lock: Mutex::new(()),
+                dirty: AtomicBool::new(false),
+            }),
+        })
+    }
+
+    /// Get the file this store is persisted to, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.inner.file.as_deref()
+    }
+
+    /// Add a host that must always be accessed over HTTPS, regardless of the
+    /// headers it sends. Preloaded entries never expire.
+    pub fn preload(&self, host: &str, include_subdomains: bool) {
+        let host = normalize_host(host);
+
+        self.insert(HstsEntry {
+            host,
+            include_subdomains,
+            expires: None,
+        });
+    }
+
+    /// Get the entry for exactly the given host, if any.
+    pub fn get(&self, host: &str) -> Option<HstsEntry> {
+        let host = normalize_host(host);
+
+        self.inner
+            .entries
+            .read()
+            .unwrap()
+            .get(&host)
+            .filter(|entry| !entry.is_expired(SystemTime::now()))
+            .cloned()
+    }
+
+    /// Check whether requests to the given host are upgraded to HTTPS, either
+    /// because of an entry for the host itself or for a parent domain that
+    /// includes subdomains.
+    pub fn contains(&self, host: &str) -> bool {
+        let host = normalize_host(host);
+        let entries = self.inner.entries.read().unwrap();
+        let now = SystemTime::now();
+
+        if entries.get(&host).is_some_and(|e| !e.is_expired(now)) {
+            return true;
+        }
+
+        host.match_indices('.').any(|(i, _)| {
+            entries
+                .get(&host[i + 1..])
+                .is_some_and(|e| e.include_subdomains && !e.is_expired(now))
+        })
+    }
+
+    /// Get a copy of all unexpired entries in the store, sorted by host.
+    pub fn entries(&self) -> Vec<HstsEntry> {
+        let now = SystemTime::now();
+        let mut entries = self
+            .inner
+            .entries
+            .read()
+            .unwrap()
+            .values()
+            .filter(|entry| !entry.is_expired(now))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        entries.sort_by(|a, b| a.host.cmp(&b.host));
+
+        entries
+    }
+
+    /// Remove the entry for the given host, returning it if it existed.
+    pub fn remove(&self, host: &str) -> Option<HstsEntry> {
+        let removed = self
+            .inner
+            .entries
+            .write()
+            .unwrap()
+            .remove(&normalize_host(host));
+
+        if removed.is_some() {
+            self.changed();
+        }
+
+        removed
+    }
+
+    /// Remove all entries from the store.
+    pub fn clear(&self) {
+        self.inner.entries.write().unwrap().clear();
+        self.changed();
+    }
+
+    /// Write the store to its file, if it has one and has changed since it
+    /// was last written.
+    pub fn save(&self) -> io::Result<()> {
+        self.inner.save()
+    }
+
+    /// Get the URI to use instead of the given one, if it must be upgraded to
+    /// HTTPS.
+    pub(crate) fn upgrade(&self, uri: &Uri) -> Option<Uri> {
+        if uri.scheme() != Some(&Scheme::HTTP) {
+            return None;
+        }
+
+        let authority = uri.authority()?;
+
+        if !self.contains(authority.host()) {
+            return None;
+        }
+
+        // The default port changes along with the scheme, but any other
+        // explicit port is kept.
+        let authority = match authority.port_u16() {
+            Some(80) => authority
+                .as_str()
+                .strip_suffix(":80")
+                .unwrap_or(authority.as_str()),
+            _ => authority.as_str(),
+        };
+
+        Uri::builder()
+            .scheme(Scheme::HTTPS)
+            .authority(authority)
+            .path_and_query(uri.path_and_query().map_or("/", |pq| pq.as_str()))
+            .build()
+            .ok()
+    }
+
+    /// Record a `Strict-Transport-Security` header received in response to a
+    /// request for the given URI.
+    pub(crate) fn record(&self, uri: &Uri, header: &HeaderValue) {
+        if uri.scheme() != Some(&Scheme::HTTPS) {
+            return;
+        }
+
+        let Some(host) = uri.host().filter(|host| !is_ip_address(host)) else {
+            return;
+        };
+
+        let Some((max_age, include_subdomains)) = header.to_str().ok().and_then(parse_header)
+        else {
+            tracing::debug!(host, "ignoring invalid Strict-Transport-Security header");
+            return;
+        };
+
+        let host = normalize_host(host);
+
+        if max_age.is_zero() {
+            let mut entries = self.inner.entries.write().unwrap();
+
+            // Preloaded entries are not affected by headers.
+            if entries.get(&host).is_some_and(|e| e.expires.is_some()) {
+                entries.remove(&host);
+                drop(entries);
+                self.changed();
+            }
+
+            return;
+        }
+
+        let now = SystemTime::now();
+        let entry = HstsEntry {
+            host,
+            include_subdomains,
+            expires: Some(now.checked_add(max_age.min(MAX_AGE)).unwrap_or(now)),
+        };
+
+        let mut entries = self.inner.entries.write().unwrap();
+
+        match entries.get_mut(&entry.host) {
+            Some(existing) if existing.expires.is_none() => {}
+            Some(existing) if existing.include_subdomains == include_subdomains => {
+                existing.expires = entry.expires;
+                self.inner.dirty.store(true, Ordering::SeqCst);
+            }
+            _ => {
+                entries.insert(entry.host.clone(), entry);
+                drop(entries);
+                self.changed();
+            }
+        }
+    }
+
+    fn insert(&self, entry: HstsEntry) {
+        self.inner
+            .entries
+            .write()
+            .unwrap()
+            .insert(entry.host.clone(), entry);
+        self.changed();
+    }
+
+    /// Persist a significant change immediately.
+    fn changed(&self) {
+        self.inner.dirty.store(true, Ordering::SeqCst);
+
+        if let Err(e) = self.inner.save() {
+            tracing::warn!(path = ?self.inner.file, "failed to write HSTS file: {}", e);
+        }
+    }
+}
+
+impl Inner {
+    fn save(&self) -> io::Result<()> {
+        let Some(path) = self.file.as_ref() else {
+            return Ok(());
+        };
+
+        let _guard = self.file_lock.lock().unwrap();
+
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        let mut contents = String::from("# HSTS cache, in the format used by curl.\n");
+        let now = SystemTime::now();
+
+        let mut entries = self
+            .entries
+            .read()
+            .unwrap()
+            .values()
+            .filter(|entry| !entry.is_expired(now))
+            .map(format_line)
+            .collect::<Vec<_>>();
+
+        entries.sort();
+
+        for line in entries {
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+
+        let tmp_path = path.with_extension(format!("tmp{}", fastrand::u32(..)));
+
+        let result = fs::File::create(&tmp_path)
+            .and_then(|mut file| file.write_all(contents.as_bytes()))
+            .and_then(|_| fs::rename(&tmp_path, path));
+
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+
+        result
+    }
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            tracing::warn!(path = ?self.file, "failed to write HSTS file: {}", e);
+        }
+    }
+}
+
+impl fmt::Debug for HstsStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HstsStore")
+            .field("path", &self.inner.file)
+            .field("entries", &self.inner.entries.read().unwrap().len())
+            .finish()
+    }
+}
+
+fn normalize_host(host: &str) -> String {
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+fn is_ip_address(host: &str) -> bool {
+    host.starts_with('[') || host.parse::<IpAddr>().is_ok()
+}
+
+/// Parse a `Strict-Transport-Security` header value into its max age and
+/// whether it includes subdomains.
+fn parse_header(value: &str) -> Option<(Duration, bool)> {
+    let mut max_age = None;
+    let mut include_subdomains = false;
+
+    for directive in value.split(';').map(str::trim) {
+        let (name, value) = match directive.split_once('=') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (directive, None),
+        };
+
+        // Directives may not appear more than once.
+        if name.eq_ignore_ascii_case("max-age") {
+            if max_age.is_some() {
+                return None;
+            }
+
+            let value = value?;
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .unwrap_or(value);
+
+            max_age = Some(Duration::from_secs(value.parse().ok()?));
+        } else if name.eq_ignore_ascii_case("includeSubDomains") {
+            if include_subdomains {
+                return None;
+            }
+
+            include_subdomains = true;
+        }
+    }
+
+    max_age.map(|max_age| (max_age, include_subdomains))
+}
+
+fn parse_line(line: &str) -> Option<HstsEntry> {
+    let (host, expires) = line.split_once(char::is_whitespace)?;
+    let expires = expires.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let (host, include_subdomains) = match host.strip_prefix('.') {
+        Some(host) => (host, true),
+        None => (host, false),
+    };
+
+    if host.is_empty() {
+        return None;
+    }
+
+    Some(HstsEntry {
+        host: normalize_host(host),
+        include_subdomains,
+        expires: match expires {
+            "unlimited" => None,
+            time => Some(parse_time(time)?),
+        },
+    })
+}
+
+fn format_line(entry: &HstsEntry) -> String {
+    format!(
+        "{}{} \"{}\"",
+        if entry.include_subdomains { "." } else { "" },
+        entry.host,
+        entry
+            .expires
+            .map_or_else(|| String::from("unlimited"), format_time),
+    )
+}
+
+/// Parse a UTC time in the format `YYYYMMDD HH:MM:SS`.
+fn parse_time(s: &str) -> Option<SystemTime> {
+    let (date, time) = s.split_once(' ')?;
+
+    if date.len() != 8 || time.len() != 8 || !date.is_ascii() || !time.is_ascii() {
+        return None;
+    }
+
+    let year = date[..4].parse().ok()?;
+    let month = date[4..6].parse().ok()?;
+    let day = date[6..].parse().ok()?;
+
+    let mut parts = time.split(':').map(str::parse::<u64>);
+    let (hour, minute, second) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(h)), Some(Ok(m)), Some(Ok(s))) if h < 24 && m < 60 && s < 60 => (h, m, s),
+        _ => return None,
+    };
+
+    let days = u64::try_from(days_from_civil(year, month, day)?).ok()?;
+
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86400 + hour * 3600 + minute * 60 + second))
+}
+
+fn format_time(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    // Any number of days in a u64 of seconds is within range.
+    let (year, month, day) = civil_from_days((secs / 86400) as i64).unwrap();
+    let secs = secs % 86400;
+
+    format!(
+        "{:04}{:02}{:02} {:02}:{:02}:{:02}",
+        year,
+        month,
+        day,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static_assertions::assert_impl_all!(HstsStore: Send, Sync);
+
+    fn https(uri: &str) -> Uri {
+        uri.parse().unwrap()
+    }
+
+    fn header(value: &'static str) -> HeaderValue {
+        HeaderValue::from_static(value)
+    }
+
+    #[test]
+    fn header_directives_are_parsed() {
+        assert_eq!(
+            parse_header("max-age=31536000; includeSubDomains"),
+            Some((Duration::from_secs(31536000), true))
+        );
+        assert_eq!(
+            parse_header("Max-Age=\"60\""),
+            Some((Duration::from_secs(60), false))
+        );
+        assert_eq!(parse_header("includeSubDomains"), None);
+        assert_eq!(parse_header("max-age=1; max-age=2"), None);
+        assert_eq!(parse_header("max-age=abc"), None);
+    }
+
+    #[test]
+    fn recorded_hosts_are_upgraded() {
+        let store = HstsStore::new();
+
+        store.record(
+            &https("https://example.org/"),
+            &header("max-age=60; includeSubDomains"),
+        );
+        store.record(&https("https://other.org/"), &header("max-age=60"));
+
+        assert_eq!(
+            store.upgrade(&https("http://example.org/a?b")),
+            Some(https("https://example.org/a?b"))
+        );
+        assert_eq!(
+            store.upgrade(&https("http://www.Example.org:80")),
+            Some(https("https://www.Example.org/"))
+        );
+        assert_eq!(
+            store.upgrade(&https("http://example.org:8080/")),
+            Some(https("https://example.org:8080/"))
+        );
+        assert_eq!(store.upgrade(&https("http://www.other.org/")), None);
+        assert_eq!(store.upgrade(&https("http://notexample.org/")), None);
+        assert_eq!(store.upgrade(&https("https://example.org/")), None);
+    }
+
+    #[test]
+    fn headers_over_insecure_connections_are_ignored() {
+        let store = HstsStore::new();
+
+        store.record(&https("http://example.org/"), &header("max-age=60"));
+        store.record(&https("https://127.0.0.1/"), &header("max-age=60"));
+        store.record(&https("https://[::1]/"), &header("max-age=60"));
+
+        assert!(store.entries().is_empty());
+    }
+
+    #[test]
+    fn zero_max_age_removes_entry() {
+        let store = HstsStore::new();
+
+        store.record(&https("https://example.org/"), &header("max-age=60"));
+        assert!(store.contains("example.org"));
+
+        store.record(&https("https://example.org/"), &header("max-age=0"));
+        assert!(!store.contains("example.org"));
+
+        // Preloaded entries are unaffected.
+        store.preload("example.org", false);
+        store.record(&https("https://example.org/"), &header("max-age=0"));
+        assert_eq!(store.get("example.org").unwrap().expires(), None);
+    }
+
+    #[test]
+    fn huge_max_age_is_clamped() {
+        let store = HstsStore::new();
+
+        store.record(
+            &https("https://example.org/"),
+            &header("max-age=18446744073709551615"),
+        );
+
+        let expires = store.get("example.org").unwrap().expires().unwrap();
+        assert!(expires <= SystemTime::now() + MAX_AGE);
+        assert!(expires > SystemTime::now() + MAX_AGE - Duration::from_secs(60));
+    }
+
+    #[test]
+    fn time_round_trips() {
+        let time = parse_time("20301231 23:59:58").unwrap();
+
+        assert_eq!(
+            time.duration_since(UNIX_EPOCH).unwrap().as_secs(),
+            1924991998
+        );
+        assert_eq!(format_time(time), "20301231 23:59:58");
+        assert_eq!(parse_time("20301331 00:00:00"), None);
+    }
+
+    #[test]
+    fn store_is_persisted_to_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hsts.txt");
+
+        let store = HstsStore::open(&path).unwrap();
+        store.preload("example.org", true);
+        store.record(&https("https://other.org/"), &header("max-age=60"));
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let mut lines = contents.lines().skip(1);
+        assert_eq!(lines.next(), Some(".example.org \"unlimited\""));
+        assert!(lines.next().unwrap().starts_with("other.org \""));
+        drop(store);
+
+        fs::write(
+            &path,
+            format!("{contents}# comment\n\n.expired.org \"20000101 00:00:00\"\n"),
+        )
+        .unwrap();
+
+        let store = HstsStore::open(&path).unwrap();
+        let hosts = store
+            .entries()
+            .into_iter()
+            .map(|entry| entry.host)
+            .collect::<Vec<_>>();
+        assert_eq!(hosts, ["example.org", "other.org"]);
+        assert!(store.contains("www.example.org"));
+    }
+}
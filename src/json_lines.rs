@@ -0,0 +1,402 @@
+//! Incremental decoding of newline-delimited JSON.
+
+#![cfg(feature = "json")]
+
+use futures_lite::{
+    Stream,
+    io::{AsyncBufRead, AsyncRead, BufReader as AsyncBufReader},
+};
+use serde::de::DeserializeOwned;
+use std::{
+    error::Error as StdError,
+    fmt,
+    io::{self, BufRead, BufReader, Read},
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// Maximum length of a single line in bytes, so that a body without any line
+/// breaks is not buffered in memory in its entirety.
+const MAX_LINE_LENGTH: usize = 16 * 1024 * 1024;
+
+/// Shared state for splitting a byte stream into lines and decoding each line
+/// as a JSON value.
+///
+/// Only the line currently being received is held in memory, and lines longer
+/// than [`MAX_LINE_LENGTH`] are rejected, so memory use is bounded regardless
+/// of the size of the body.
+struct Decoder<T> {
+    /// Bytes of the line currently being received.
+    line: Vec<u8>,
+
+    max_line_length: usize,
+
+    /// Number of the line currently being received, starting at 1.
+    line_number: u64,
+
+    /// Set once the end of the stream or an I/O error has been reached.
+    done: bool,
+
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> Decoder<T> {
+    fn new() -> Self {
+        Self {
+            line: Vec::new(),
+            max_line_length: MAX_LINE_LENGTH,
+            line_number: 1,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Consume bytes from the given buffer, returning the number of bytes
+    /// consumed and the decoded value if a complete line was read.
+    fn push(&mut self, buf: &[u8]) -> (usize, Option<Result<T, JsonLineError>>) {
+        if buf.is_empty() {
+            // End of stream; decode any final line without a terminator.
+            self.done = true;
+            return (0, self.decode());
+        }
+
+        let (chunk, consumed) = match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => (&buf[..i], i + 1),
+            None => (buf, buf.len()),
+        };
+
+        if self.line.len() + chunk.len() > self.max_line_length {
+            let error = io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "line exceeds maximum length of {} bytes",
+                    self.max_line_length
+                ),
+            );
+            self.line.clear();
+
+            return (consumed, self.fail(error));
+        }
+
+        self.line.extend_from_slice(chunk);
+
+        if chunk.len() < consumed {
+            (consumed, self.decode())
+        } else {
+            (consumed, None)
+        }
+    }
+
+    fn fail(&mut self, error: io::Error) -> Option<Result<T, JsonLineError>> {
+        self.done = true;
+        Some(Err(JsonLineError {
+            line: self.line_number,
+            error: serde_json::Error::io(error),
+        }))
+    }
+
+    /// Decode the current line, if it is not blank.
+    fn decode(&mut self) -> Option<Result<T, JsonLineError>> {
+        let line_number = self.line_number;
+        self.line_number += 1;
+
+        if self.line.iter().all(u8::is_ascii_whitespace) {
+            self.line.clear();
+            return None;
+        }
+
+        let result = serde_json::from_slice(&self.line).map_err(|error| JsonLineError {
+            line: line_number,
+            error,
+        });
+
+        self.line.clear();
+
+        Some(result)
+    }
+}
+
+/// An error decoding a line of a newline-delimited JSON response body.
+///
+/// Each line is decoded as a separate JSON document, so a [`serde_json::Error`]
+/// on its own would always report line 1 and could not say which line of the
+/// body was at fault. This type wraps the original error together with the
+/// line number within the body, and still offers the same ways to inspect
+/// it. The original error is available using
+/// [`into_inner`](Self::into_inner), or as the [source](StdError::source) of
+/// this error.
+///
+/// A line longer than 16 MiB also produces an I/O error, since lines have to
+/// be held in memory in their entirety to be decoded.
+pub struct JsonLineError {
+    /// Number of the offending line within the body, starting at 1.
+    line: u64,
+    error: serde_json::Error,
+}
+
+impl JsonLineError {
+    /// Get the number of the line within the body that caused this error,
+    /// starting at 1.
+    ///
+    /// For I/O errors, this is the line that was being read when the error
+    /// occurred.
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+
+    /// Get the column within the line at which this error occurred, starting
+    /// at 1, or 0 for I/O errors.
+    pub fn column(&self) -> usize {
+        self.error.column()
+    }
+
+    /// Get the category of this error.
+    pub fn classify(&self) -> serde_json::error::Category {
+        self.error.classify()
+    }
+
+    /// Returns true if this error was caused by failing to read the body.
+    pub fn is_io(&self) -> bool {
+        self.error.is_io()
+    }
+
+    /// Returns true if this error was caused by a line that is not valid JSON.
+    pub fn is_syntax(&self) -> bool {
+        self.error.is_syntax()
+    }
+
+    /// Returns true if this error was caused by a line containing valid JSON
+    /// that does not match the expected type.
+    pub fn is_data(&self) -> bool {
+        self.error.is_data()
+    }
+
+    /// Returns true if this error was caused by a line ending in the middle of
+    /// a JSON value.
+    pub fn is_eof(&self) -> bool {
+        self.error.is_eof()
+    }
+
+    /// Get the underlying [`serde_json::Error`]. Note that any position it
+    /// reports is relative to the start of the offending line.
+    pub fn into_inner(self) -> serde_json::Error {
+        self.error
+    }
+}
+
+impl fmt::Debug for JsonLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLineError")
+            .field("line", &self.line)
+            .field("error", &self.error)
+            .finish()
+    }
+}
+
+impl fmt::Display for JsonLineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.error.is_io() {
+            return write!(f, "{} while reading line {}", self.error, self.line);
+        }
+
+        // Replace the position reported by serde, which is always on line 1.
+        let message = self.error.to_string();
+        let position = format!(" at line 1 column {}", self.error.column());
+        let message = message.strip_suffix(&position).unwrap_or(&message);
+
+        write!(
+            f,
+            "{} at line {} column {}",
+            message,
+            self.line,
+            self.error.column()
+        )
+    }
+}
+
+impl StdError for JsonLineError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// An iterator over JSON values in a newline-delimited response body.
+pub struct JsonLines<R, T> {
+    reader: BufReader<R>,
+    decoder: Decoder<T>,
+}
+
+impl<R: Read, T: DeserializeOwned> JsonLines<R, T> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            decoder: Decoder::new(),
+        }
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for JsonLines<R, T> {
+    type Item = Result<T, JsonLineError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.decoder.done {
+            let buf = match self.reader.fill_buf() {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return self.decoder.fail(e),
+            };
+
+            let (consumed, item) = self.decoder.push(buf);
+            self.reader.consume(consumed);
+
+            if item.is_some() {
+                return item;
+            }
+        }
+
+        None
+    }
+}
+
+impl<R, T> fmt::Debug for JsonLines<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLines").finish()
+    }
+}
+
+/// A stream of JSON values in a newline-delimited response body.
+pub struct JsonLinesStream<R, T> {
+    reader: AsyncBufReader<R>,
+    decoder: Decoder<T>,
+}
+
+impl<R: AsyncRead + Unpin, T: DeserializeOwned> JsonLinesStream<R, T> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader: AsyncBufReader::new(reader),
+            decoder: Decoder::new(),
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin, T: DeserializeOwned> Stream for JsonLinesStream<R, T> {
+    type Item = Result<T, JsonLineError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        while !this.decoder.done {
+            let buf = match futures_lite::ready!(Pin::new(&mut this.reader).poll_fill_buf(cx)) {
+                Ok(buf) => buf,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Poll::Ready(this.decoder.fail(e)),
+            };
+
+            let (consumed, item) = this.decoder.push(buf);
+            Pin::new(&mut this.reader).consume(consumed);
+
+            if item.is_some() {
+                return Poll::Ready(item);
+            }
+        }
+
+        Poll::Ready(None)
+    }
+}
+
+impl<R, T> fmt::Debug for JsonLinesStream<R, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLinesStream").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::{StreamExt, future::block_on};
+    use serde_json::Value;
+
+    #[test]
+    fn values_are_decoded_from_each_line() {
+        let input = b"{\"a\":1}\r\n\n  \n[2]\n3";
+        let values = JsonLines::<_, Value>::new(&input[..])
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(
+            values,
+            vec![
+                serde_json::json!({"a": 1}),
+                serde_json::json!([2]),
+                3.into()
+            ]
+        );
+    }
+
+    #[test]
+    fn lines_longer_than_buffer_are_decoded() {
+        let long = "x".repeat(20_000);
+        let input = format!("\"{}\"\n\"short\"\n", long);
+        let values = JsonLines::<_, String>::new(input.as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(values, vec![long, String::from("short")]);
+    }
+
+    #[test]
+    fn errors_report_line_number_and_decoding_continues() {
+        let input = b"1\n\n{oops}\n4\n";
+        let mut lines = JsonLines::<_, Value>::new(&input[..]);
+
+        assert_eq!(lines.next().unwrap().unwrap(), 1);
+
+        let error = lines.next().unwrap().unwrap_err();
+        assert!(error.is_syntax());
+        assert_eq!(error.line(), 3);
+        assert_eq!(error.column(), 2);
+        assert_eq!(error.to_string(), "key must be a string at line 3 column 2");
+
+        assert_eq!(lines.next().unwrap().unwrap(), 4);
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn lines_longer_than_maximum_are_rejected() {
+        let input = b"1\n\"too long\"\n3\n";
+        let mut lines = JsonLines::<_, Value>::new(&input[..]);
+        lines.decoder.max_line_length = 8;
+
+        assert_eq!(lines.next().unwrap().unwrap(), 1);
+
+        let error = lines.next().unwrap().unwrap_err();
+        assert!(error.is_io());
+        assert_eq!(error.line(), 2);
+
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn unterminated_body_is_not_buffered_beyond_maximum() {
+        let input = vec![b' '; 1024];
+        let mut lines = JsonLines::<_, Value>::new(&input[..]);
+        lines.decoder.max_line_length = 64;
+
+        assert!(lines.next().unwrap().unwrap_err().is_io());
+        assert!(lines.decoder.line.capacity() <= 64);
+        assert!(lines.next().is_none());
+    }
+
+    #[test]
+    fn stream_decodes_values() {
+        let input = b"\"a\"\n\"b\"\n";
+        let values = block_on(
+            JsonLinesStream::<_, String>::new(&input[..])
+                .map(Result::unwrap)
+                .collect::<Vec<_>>(),
+        );
+
+        assert_eq!(values, vec!["a", "b"]);
+    }
+}
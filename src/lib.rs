@@ -153,6 +153,11 @@
 //!
 //! Enable persistent HTTP cookie support. Disabled by default.
 //!
+//! ## `form`
+//!
+//! Additional serialization of URL-encoded form bodies and query strings via
+//! [serde](https://serde.rs). Disabled by default.
+//!
 //! ## `http2`
 //!
 //! Enable compile-time support for HTTP/2 in libcurl via libnghttp2. This does
@@ -265,24 +270,36 @@ mod macros;
 
 mod agent;
 mod body;
+mod calendar;
+mod circuit_breaker;
 mod client;
+mod conditional;
 mod default_headers;
 mod handler;
 mod headers;
+mod hedge;
 mod info;
+mod json_lines;
 mod metrics;
 mod parsing;
+mod rate_limit;
 mod redirect;
 mod request;
 mod response;
+mod retry;
 mod task;
+mod temp;
 mod text;
 mod trailer;
 
 pub mod auth;
+pub mod cache;
 pub mod config;
 pub mod error;
+pub mod hsts;
+pub mod multipart;
 pub mod net;
+pub mod sse;
 
 #[cfg(feature = "cookies")]
 pub mod cookies;
@@ -299,6 +316,7 @@ pub(crate) mod interceptor;
 pub use crate::{
     body::{AsyncBody, Body},
     client::{HttpClient, HttpClientBuilder, ResponseFuture},
+    conditional::Validators,
     error::Error,
     http::{request::Request, response::Response},
     info::{is_http_version_supported, version},
@@ -308,6 +326,9 @@ pub use crate::{
     trailer::Trailer,
 };
 
+#[cfg(feature = "form")]
+pub use crate::request::RequestBuilderExt;
+
 /// Re-export of HTTP types.
 pub use http;
 
@@ -327,6 +348,10 @@ pub mod prelude {
     pub use crate::{
         AsyncReadResponseExt, ReadResponseExt, RequestExt, ResponseExt, config::Configurable,
     };
+
+    #[cfg(feature = "form")]
+    #[doc(no_inline)]
+    pub use crate::RequestBuilderExt;
 }
 
 /// Send a GET request to the given URI.
@@ -1,7 +1,7 @@
 //! Request and response metrics tracking.
 
 use crossbeam_utils::atomic::AtomicCell;
-use std::{fmt, sync::Arc, time::Duration};
+use std::{fmt, os::raw::c_long, sync::Arc, time::Duration};
 
 /// An object that holds status updates and progress statistics on a particular
 /// request. A [`Metrics`] can be shared between threads, which allows an agent
@@ -45,6 +45,9 @@ pub(crate) struct Inner {
     pub(crate) starttransfer_time: AtomicCell<f64>,
     pub(crate) total_time: AtomicCell<f64>,
     pub(crate) redirect_time: AtomicCell<f64>,
+
+    /// The HTTP version used for the last transfer, as reported by curl.
+    pub(crate) http_version: AtomicCell<c_long>,
 }
 
 impl Metrics {
@@ -149,6 +152,21 @@ impl Metrics {
     pub fn redirect_time(&self) -> Duration {
         Duration::from_secs_f64(self.inner.redirect_time.load())
     }
+
+    /// Get the HTTP version used for the request, once a connection has been
+    /// established.
+    ///
+    /// When a redirect is followed, this is the version used for the last
+    /// request.
+    pub fn http_version(&self) -> Option<http::Version> {
+        match self.inner.http_version.load() {
+            1 => Some(http::Version::HTTP_10),
+            2 => Some(http::Version::HTTP_11),
+            3 => Some(http::Version::HTTP_2),
+            30 => Some(http::Version::HTTP_3),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Debug for Metrics {
@@ -165,6 +183,7 @@ impl fmt::Debug for Metrics {
             .field("transfer_time", &self.transfer_time())
             .field("total_time", &self.total_time())
             .field("redirect_time", &self.redirect_time())
+            .field("http_version", &self.http_version())
             .finish()
     }
 }
@@ -0,0 +1,466 @@
+//! Types for building `multipart/form-data` request bodies.
+//!
+//! A [`Form`] is a list of named parts, each of which may be a simple text
+//! value, an in-memory file, or a stream of bytes read asynchronously. A form
+//! can be converted into either an [`AsyncBody`] or a [`Body`] and used as the
+//! body of a request, in which case the `Content-Type` header of the request
+//! is set automatically to include the boundary separating the parts, unless
+//! the request already sets its own `Content-Type`.
+//!
+//! If every part has a known length, then the body will also have a known
+//! length, allowing the request to be sent with a `Content-Length` header
+//! instead of using chunked encoding. If every part is stored in memory, then
+//! the body can also be rewound and sent again when following a redirect.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use isahc::{multipart::{Form, Part}, prelude::*, Request};
+//!
+//! let form = Form::new()
+//!     .text("title", "Quarterly report")
+//!     .part(
+//!         "attachment",
+//!         Part::bytes(std::fs::read("report.pdf")?)
+//!             .file_name("report.pdf")
+//!             .content_type("application/pdf"),
+//!     );
+//!
+//! let response = Request::post("https://example.org/upload")
+//!     .body(form)?
+//!     .send()?;
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::body::{AsyncBody, Body};
+use futures_lite::io::AsyncRead;
+use http::HeaderValue;
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt,
+    io::{self, Cursor, Read},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// A `multipart/form-data` body builder.
+///
+/// See the [module documentation](self) for more details.
+#[must_use = "builders have no effect if unused"]
+pub struct Form {
+    boundary: String,
+    parts: Vec<(String, Part)>,
+}
+
+impl Form {
+    /// Create a new empty form with a randomly generated boundary.
+    pub fn new() -> Self {
+        let boundary = std::iter::repeat_with(fastrand::alphanumeric)
+            .take(24)
+            .collect::<String>();
+
+        Self {
+            boundary: format!("isahc-boundary-{}", boundary),
+            parts: Vec::new(),
+        }
+    }
+
+    /// Get the boundary used to separate the parts of this form.
+    pub fn boundary(&self) -> &str {
+        &self.boundary
+    }
+
+    /// Get the value of the `Content-Type` header to use when sending this
+    /// form.
+    pub fn content_type(&self) -> String {
+        format!("multipart/form-data; boundary={}", self.boundary)
+    }
+
+    /// Add a text field to the form.
+    pub fn text(self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.part(name, Part::text(value))
+    }
+
+    /// Add a part to the form with the given field name.
+    pub fn part(mut self, name: impl Into<String>, part: Part) -> Self {
+        self.parts.push((name.into(), part));
+        self
+    }
+
+    /// Get the exact length of the encoded form, if every part has a known
+    /// length.
+    fn len(&self) -> Option<u64> {
+        let mut len = self.closing_delimiter().len() as u64;
+
+        for (name, part) in &self.parts {
+            len += self.part_header(name, part).len() as u64;
+            len += part.len()?;
+            len += 2;
+        }
+
+        Some(len)
+    }
+
+    fn part_header(&self, name: &str, part: &Part) -> String {
+        let mut header = format!(
+            "--{}\r\ncontent-disposition: form-data; name=\"{}\"",
+            self.boundary,
+            escape_quoted(name)
+        );
+
+        if let Some(file_name) = part.file_name.as_ref() {
+            header.push_str("; filename=\"");
+            header.push_str(&escape_quoted(file_name));
+            header.push('"');
+        }
+
+        header.push_str("\r\n");
+
+        if let Some(content_type) = part.content_type.as_ref() {
+            header.push_str("content-type: ");
+            header.push_str(content_type);
+            header.push_str("\r\n");
+        }
+
+        header.push_str("\r\n");
+        header
+    }
+
+    fn closing_delimiter(&self) -> String {
+        format!("--{}--\r\n", self.boundary)
+    }
+}
+
+impl Default for Form {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Form {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Form")
+            .field("boundary", &self.boundary)
+            .field("parts", &self.parts)
+            .finish()
+    }
+}
+
+impl From<Form> for AsyncBody {
+    fn from(form: Form) -> Self {
+        let content_type = HeaderValue::from_str(&form.content_type())
+            .expect("boundary is always a valid header value");
+        let len = form.len();
+
+        // If everything is in memory, encode the form into a single buffer so
+        // that the body can be rewound.
+        if form.parts.iter().all(|(_, part)| part.is_buffered()) {
+            let mut bytes = Vec::with_capacity(len.unwrap_or_default() as usize);
+
+            for (name, part) in &form.parts {
+                bytes.extend_from_slice(form.part_header(name, part).as_bytes());
+
+                if let PartBody::Bytes(data) = &part.body {
+                    bytes.extend_from_slice(data);
+                }
+
+                bytes.extend_from_slice(b"\r\n");
+            }
+
+            bytes.extend_from_slice(form.closing_delimiter().as_bytes());
+
+            return AsyncBody::from(bytes).with_content_type(content_type);
+        }
+
+        let headers = form
+            .parts
+            .iter()
+            .map(|(name, part)| form.part_header(name, part))
+            .collect::<Vec<_>>();
+        let closing_delimiter = form.closing_delimiter();
+        let mut segments = VecDeque::with_capacity(form.parts.len() * 3 + 1);
+
+        for (header, (_, part)) in headers.into_iter().zip(form.parts) {
+            segments.push_back(Segment::Bytes(Cursor::new(Cow::Owned(header.into_bytes()))));
+            segments.push_back(match part.body {
+                PartBody::Bytes(data) => Segment::Bytes(Cursor::new(data)),
+                PartBody::Reader(body) => Segment::Reader(body),
+            });
+            segments.push_back(Segment::Bytes(Cursor::new(Cow::Borrowed(b"\r\n"))));
+        }
+
+        segments.push_back(Segment::Bytes(Cursor::new(Cow::Owned(
+            closing_delimiter.into_bytes(),
+        ))));
+
+        let reader = FormReader { segments };
+        let body = match len {
+            Some(len) => AsyncBody::from_reader_sized(reader, len),
+            None => AsyncBody::from_reader(reader),
+        };
+
+        body.with_content_type(content_type)
+    }
+}
+
+impl From<Form> for Body {
+    fn from(form: Form) -> Self {
+        AsyncBody::from(form).into_sync()
+    }
+}
+
+/// A single part of a multipart form.
+pub struct Part {
+    body: PartBody,
+    file_name: Option<String>,
+    content_type: Option<String>,
+}
+
+enum PartBody {
+    Bytes(Cow<'static, [u8]>),
+    Reader(AsyncBody),
+}
+
+impl Part {
+    /// Create a part containing text.
+    pub fn text(value: impl Into<String>) -> Self {
+        Self::new(PartBody::Bytes(Cow::Owned(value.into().into_bytes())))
+    }
+
+    /// Create a part containing the given bytes.
+    ///
+    /// This is typically combined with [`Part::file_name`] to upload the
+    /// contents of a file.
+    pub fn bytes(bytes: impl Into<Cow<'static, [u8]>>) -> Self {
+        Self::new(PartBody::Bytes(bytes.into()))
+    }
+
+    /// Create a part whose contents are streamed from the given reader.
+    ///
+    /// The part will have an unknown length, which means the form will be
+    /// sent using chunked encoding. Use [`Part::reader_sized`] instead if the
+    /// length is known in advance.
+    pub fn reader<R>(reader: R) -> Self
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        Self::new(PartBody::Reader(AsyncBody::from_reader(reader)))
+    }
+
+    /// Create a part whose contents are streamed from the given reader, with a
+    /// known length.
+    ///
+    /// Giving a value for `length` that doesn't actually match how much data
+    /// the reader will produce may result in errors when sending the form.
+    pub fn reader_sized<R>(reader: R, length: u64) -> Self
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        Self::new(PartBody::Reader(AsyncBody::from_reader_sized(
+            reader, length,
+        )))
+    }
+
+    fn new(body: PartBody) -> Self {
+        Self {
+            body,
+            file_name: None,
+            content_type: None,
+        }
+    }
+
+    /// Set the file name of this part, marking it as a file upload.
+    #[must_use = "builders have no effect if unused"]
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    /// Set the content type of this part.
+    ///
+    /// Line breaks are not permitted in the content type and are removed.
+    #[must_use = "builders have no effect if unused"]
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        let mut content_type = content_type.into();
+        content_type.retain(|c| c != '\r' && c != '\n');
+        self.content_type = Some(content_type);
+        self
+    }
+
+    fn is_buffered(&self) -> bool {
+        matches!(self.body, PartBody::Bytes(_))
+    }
+
+    fn len(&self) -> Option<u64> {
+        match &self.body {
+            PartBody::Bytes(bytes) => Some(bytes.len() as u64),
+            PartBody::Reader(body) => body.len(),
+        }
+    }
+}
+
+impl fmt::Debug for Part {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Part")
+            .field("file_name", &self.file_name)
+            .field("content_type", &self.content_type)
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Escape a value for use inside a quoted parameter of the
+/// `Content-Disposition` header, following the rules used by browsers.
+fn escape_quoted(value: &str) -> Cow<'_, str> {
+    if value.contains(['"', '\r', '\n']) {
+        Cow::Owned(
+            value
+                .replace('"', "%22")
+                .replace('\r', "%0D")
+                .replace('\n', "%0A"),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+enum Segment {
+    Bytes(Cursor<Cow<'static, [u8]>>),
+    Reader(AsyncBody),
+}
+
+/// Reader that produces the encoded form by reading each segment in turn.
+struct FormReader {
+    segments: VecDeque<Segment>,
+}
+
+impl AsyncRead for FormReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        while let Some(segment) = self.segments.front_mut() {
+            let result = match segment {
+                Segment::Bytes(cursor) => cursor.read(buf),
+                Segment::Reader(body) => futures_lite::ready!(Pin::new(body).poll_read(cx, buf)),
+            };
+
+            match result {
+                Ok(0) => {
+                    self.segments.pop_front();
+                }
+                result => return Poll::Ready(result),
+            }
+        }
+
+        Poll::Ready(Ok(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::{future::block_on, io::AsyncReadExt};
+
+    static_assertions::assert_impl_all!(Form: Send, Sync);
+
+    fn read(mut body: AsyncBody) -> String {
+        let mut buf = String::new();
+        block_on(body.read_to_string(&mut buf)).unwrap();
+        buf
+    }
+
+    fn expected(form: &Form) -> String {
+        format!(
+            "--{b}\r\n\
+            content-disposition: form-data; name=\"title\"\r\n\
+            \r\n\
+            hello\r\n\
+            --{b}\r\n\
+            content-disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+            content-type: text/plain\r\n\
+            \r\n\
+            file contents\r\n\
+            --{b}--\r\n",
+            b = form.boundary()
+        )
+    }
+
+    #[test]
+    fn buffered_form_is_resettable_with_exact_length() {
+        let form = Form::new().text("title", "hello").part(
+            "file",
+            Part::bytes(&b"file contents"[..])
+                .file_name("a.txt")
+                .content_type("text/plain"),
+        );
+        let expected = expected(&form);
+        let content_type = form.content_type();
+
+        let mut body = AsyncBody::from(form);
+
+        assert_eq!(body.len(), Some(expected.len() as u64));
+        assert_eq!(body.content_type().unwrap(), content_type.as_str());
+        assert!(body.reset());
+
+        let mut buf = String::new();
+        block_on(body.read_to_string(&mut buf)).unwrap();
+        assert_eq!(buf, expected);
+
+        assert!(body.reset());
+        assert_eq!(read(body), expected);
+    }
+
+    #[test]
+    fn streaming_form_with_known_length() {
+        let form = Form::new().text("title", "hello").part(
+            "file",
+            Part::reader_sized(&b"file contents"[..], 13)
+                .file_name("a.txt")
+                .content_type("text/plain"),
+        );
+        let expected = expected(&form);
+
+        let body = AsyncBody::from(form);
+
+        assert_eq!(body.len(), Some(expected.len() as u64));
+        assert!(body.content_type().is_some());
+        assert_eq!(read(body), expected);
+    }
+
+    #[test]
+    fn streaming_form_with_unknown_length() {
+        let form = Form::new().part("data", Part::reader(&b"abc"[..]));
+        let boundary = form.boundary().to_owned();
+        let mut body = AsyncBody::from(form);
+
+        assert_eq!(body.len(), None);
+        assert!(!body.reset());
+        assert_eq!(
+            read(body),
+            format!(
+                "--{b}\r\ncontent-disposition: form-data; name=\"data\"\r\n\r\nabc\r\n--{b}--\r\n",
+                b = boundary
+            )
+        );
+    }
+
+    #[test]
+    fn quoted_values_are_escaped() {
+        let form = Form::new().part("a\"b", Part::text("").file_name("c\r\nd.txt"));
+        let body = read(AsyncBody::from(form));
+
+        assert!(body.contains("name=\"a%22b\"; filename=\"c%0D%0Ad.txt\""));
+    }
+
+    #[test]
+    fn boundaries_are_unique() {
+        assert_ne!(Form::new().boundary(), Form::new().boundary());
+    }
+}
@@ -0,0 +1,490 @@
+//! Name resolution using DNS over HTTPS.
+
+use super::{Resolve, ResolveFuture};
+use crate::{
+    AsyncReadResponseExt, HttpClient,
+    config::{Configurable, RedirectPolicy},
+    error::Error,
+};
+use http::{Request, Uri};
+use std::{
+    collections::HashMap,
+    fmt, io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+const MEDIA_TYPE: &str = "application/dns-message";
+
+/// How long a single lookup may take before giving up.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of host names to cache addresses for.
+const MAX_CACHE_ENTRIES: usize = 1024;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+const CLASS_IN: u16 = 1;
+const RCODE_NXDOMAIN: u16 = 3;
+
+/// Configuration for resolving host names using DNS over HTTPS (DoH), as
+/// described in [RFC 8484](https://www.rfc-editor.org/rfc/rfc8484).
+///
+/// Lookups are sent to the given DoH endpoint, such as
+/// `https://cloudflare-dns.com/dns-query`, instead of using the name resolver
+/// of the operating system. Requests to the endpoint are sent using a
+/// separate connection pool, and can use their own
+/// [`TlsConfig`](crate::tls::TlsConfig). The host of the endpoint itself is
+/// resolved using the operating system, unless it is an IP address.
+///
+/// Addresses are cached for as long as the DoH server allows, for up to 1024
+/// host names. Cloning a configuration returns a new reference to the same
+/// cache and connection pool.
+///
+/// Use DNS over HTTPS for requests using
+/// [`Configurable::dns_over_https`]. Since this type implements [`Resolve`],
+/// it can also be combined with other resolvers and used with
+/// [`HttpClientBuilder::resolver`](crate::HttpClientBuilder::resolver).
+///
+/// # Examples
+///
+/// ```
+/// use isahc::{net::DnsOverHttps, prelude::*, HttpClient};
+///
+/// let client = HttpClient::builder()
+///     .dns_over_https(DnsOverHttps::new(
+///         "https://cloudflare-dns.com/dns-query".parse()?,
+///     ))
+///     .build()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct DnsOverHttps {
+    uri: Uri,
+
+    #[cfg(feature = "tls")]
+    tls_config: Option<crate::tls::TlsConfig>,
+
+    state: Arc<State>,
+}
+
+#[derive(Default)]
+struct State {
+    client: OnceLock<Result<HttpClient, Error>>,
+    cache: Mutex<Cache>,
+}
+
+/// Cache of resolved addresses, keyed by host name.
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<String, CacheEntry>,
+}
+
+struct CacheEntry {
+    addrs: Vec<IpAddr>,
+    expires: Instant,
+}
+
+impl Cache {
+    fn get(&self, name: &str, now: Instant) -> Option<&[IpAddr]> {
+        self.entries
+            .get(name)
+            .filter(|entry| entry.expires > now)
+            .map(|entry| entry.addrs.as_slice())
+    }
+
+    fn insert(&mut self, name: String, entry: CacheEntry, now: Instant) {
+        if self.entries.len() >= MAX_CACHE_ENTRIES && !self.entries.contains_key(&name) {
+            self.entries.retain(|_, entry| entry.expires > now);
+
+            // If nothing has expired yet, make room by evicting the entry
+            // that would have expired first.
+            if self.entries.len() >= MAX_CACHE_ENTRIES {
+                let oldest = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires)
+                    .map(|(name, _)| name.clone());
+
+                if let Some(oldest) = oldest {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+
+        self.entries.insert(name, entry);
+    }
+}
+
+impl DnsOverHttps {
+    /// Resolve names using the DoH endpoint at the given URI.
+    pub fn new(uri: Uri) -> Self {
+        Self {
+            uri,
+            #[cfg(feature = "tls")]
+            tls_config: None,
+            state: Default::default(),
+        }
+    }
+
+    /// Get the URI of the DoH endpoint.
+    pub fn uri(&self) -> &Uri {
+        &self.uri
+    }
+
+    /// Set the TLS configuration to use when connecting to the DoH endpoint.
+    ///
+    /// By default, the default [`TlsConfig`](crate::tls::TlsConfig) is used,
+    /// regardless of the TLS configuration of the requests being resolved.
+    ///
+    /// # Availability
+    ///
+    /// This method is only available when the [`tls`](../index.html#tls)
+    /// feature is enabled.
+    #[cfg(feature = "tls")]
+    #[must_use = "builders have no effect if unused"]
+    pub fn tls_config(mut self, config: crate::tls::TlsConfig) -> Self {
+        self.tls_config = Some(config);
+        self.state = Default::default();
+        self
+    }
+
+    fn client(&self) -> io::Result<&HttpClient> {
+        self.state
+            .client
+            .get_or_init(|| {
+                let builder = HttpClient::builder()
+                    .timeout(TIMEOUT)
+                    .redirect_policy(RedirectPolicy::None);
+
+                #[cfg(feature = "tls")]
+                let builder = match self.tls_config.clone() {
+                    Some(config) => builder.tls_config(config),
+                    None => builder,
+                };
+
+                builder.build()
+            })
+            .as_ref()
+            .map_err(|e| io::Error::from(e.clone()))
+    }
+
+    async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        let name = host.trim_end_matches('.').to_ascii_lowercase();
+
+        if let Some(addrs) = self.state.cache.lock().unwrap().get(&name, Instant::now()) {
+            return Ok(addrs.to_vec());
+        }
+
+        let (v4, v6) =
+            futures_lite::future::zip(self.query(&name, TYPE_A), self.query(&name, TYPE_AAAA))
+                .await;
+
+        let answers = match (v4, v6) {
+            (Ok(v4), Ok(v6)) => vec![v4, v6],
+            (Ok(answer), Err(e)) | (Err(e), Ok(answer)) => {
+                tracing::debug!(host, "DoH query failed: {}", e);
+                vec![answer]
+            }
+            (Err(e), Err(_)) => return Err(e),
+        };
+
+        // Records may only be cached for as long as the shortest TTL of any
+        // record returned.
+        let ttl = answers
+            .iter()
+            .filter(|answer| !answer.addrs.is_empty())
+            .map(|answer| answer.ttl)
+            .min()
+            .unwrap_or_default();
+
+        let addrs = answers
+            .into_iter()
+            .flat_map(|answer| answer.addrs)
+            .collect::<Vec<_>>();
+
+        if addrs.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no addresses found for {}", name),
+            ));
+        }
+
+        let now = Instant::now();
+
+        self.state.cache.lock().unwrap().insert(
+            name,
+            CacheEntry {
+                addrs: addrs.clone(),
+                expires: now + ttl,
+            },
+            now,
+        );
+
+        Ok(addrs)
+    }
+
+    async fn query(&self, name: &str, record_type: u16) -> io::Result<Answer> {
+        let request = Request::post(self.uri.clone())
+            .header(http::header::CONTENT_TYPE, MEDIA_TYPE)
+            .header(http::header::ACCEPT, MEDIA_TYPE)
+            .body(encode_query(name, record_type)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let mut response = self.client()?.send_async(request).await?;
+
+        if !response.status().is_success() {
+            return Err(io::Error::other(format!(
+                "DoH server returned status {}",
+                response.status()
+            )));
+        }
+
+        decode_answer(&response.bytes().await?)
+    }
+}
+
+impl Resolve for DnsOverHttps {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            Ok(self
+                .lookup(host)
+                .await?
+                .into_iter()
+                .map(|ip| SocketAddr::new(ip, port))
+                .collect())
+        })
+    }
+}
+
+impl fmt::Debug for DnsOverHttps {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DnsOverHttps")
+            .field("uri", &self.uri)
+            .finish()
+    }
+}
+
+/// Addresses found in a DNS response.
+#[derive(Debug, Default, PartialEq)]
+struct Answer {
+    addrs: Vec<IpAddr>,
+
+    /// How long the addresses may be cached for.
+    ttl: Duration,
+}
+
+/// Encode a DNS query message for the given name and record type.
+fn encode_query(name: &str, record_type: u16) -> io::Result<Vec<u8>> {
+    // ID zero as recommended for caching, recursion desired, one question.
+    let mut message = vec![0, 0, 1, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+
+    for label in name.split('.') {
+        if label.is_empty() || label.len() > 63 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid host name: {}", name),
+            ));
+        }
+
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+
+    if message.len() - 12 > 254 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("host name too long: {}", name),
+        ));
+    }
+
+    message.push(0);
+    message.extend_from_slice(&record_type.to_be_bytes());
+    message.extend_from_slice(&CLASS_IN.to_be_bytes());
+
+    Ok(message)
+}
+
+/// Decode the addresses from a DNS response message.
+fn decode_answer(message: &[u8]) -> io::Result<Answer> {
+    let mut reader = Reader { message, pos: 0 };
+
+    reader.skip(2)?;
+    let flags = reader.u16()?;
+    let questions = reader.u16()?;
+    let answers = reader.u16()?;
+    reader.skip(4)?;
+
+    if flags & 0x8000 == 0 {
+        return Err(invalid_data("DNS message is not a response"));
+    }
+
+    match flags & 0xf {
+        0 => {}
+        RCODE_NXDOMAIN => return Err(io::Error::new(io::ErrorKind::NotFound, "no such host")),
+        rcode => return Err(io::Error::other(format!("DNS error code {}", rcode))),
+    }
+
+    for _ in 0..questions {
+        reader.skip_name()?;
+        reader.skip(4)?;
+    }
+
+    let mut answer = Answer::default();
+    let mut ttl = u32::MAX;
+
+    for _ in 0..answers {
+        reader.skip_name()?;
+        let record_type = reader.u16()?;
+        let class = reader.u16()?;
+        let record_ttl = reader.u32()?;
+        let len = reader.u16()? as usize;
+        let data = reader.take(len)?;
+
+        let addr = match (record_type, class) {
+            (TYPE_A, CLASS_IN) => <[u8; 4]>::try_from(data)
+                .ok()
+                .map(Ipv4Addr::from)
+                .map(IpAddr::from),
+            (TYPE_AAAA, CLASS_IN) => <[u8; 16]>::try_from(data)
+                .ok()
+                .map(Ipv6Addr::from)
+                .map(IpAddr::from),
+            _ => None,
+        };
+
+        if let Some(addr) = addr {
+            answer.addrs.push(addr);
+            ttl = ttl.min(record_ttl);
+        }
+    }
+
+    if !answer.addrs.is_empty() {
+        answer.ttl = Duration::from_secs(ttl.into());
+    }
+
+    Ok(answer)
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+struct Reader<'a> {
+    message: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let bytes = self
+            .message
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| invalid_data("truncated DNS message"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> io::Result<()> {
+        self.take(len).map(drop)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        self.take(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        self.take(4)
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// Skip over a possibly compressed domain name.
+    fn skip_name(&mut self) -> io::Result<()> {
+        loop {
+            match self.take(1)?[0] {
+                0 => return Ok(()),
+                len if len & 0xc0 == 0xc0 => return self.skip(1),
+                len => self.skip(len.into())?,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_query_message() {
+        assert_eq!(
+            encode_query("example.org", TYPE_AAAA).unwrap(),
+            b"\0\0\x01\0\0\x01\0\0\0\0\0\0\x07example\x03org\0\0\x1c\0\x01"
+        );
+
+        assert!(encode_query("example..org", TYPE_A).is_err());
+        assert!(encode_query(&"a".repeat(64), TYPE_A).is_err());
+    }
+
+    #[test]
+    fn decode_answer_with_compressed_names() {
+        let mut message = b"\0\0\x81\x80\0\x01\0\x03\0\0\0\0".to_vec();
+        message.extend_from_slice(b"\x07example\x03org\0\0\x01\0\x01");
+        // A CNAME record, followed by two A records for the canonical name.
+        message.extend_from_slice(b"\xc0\x0c\0\x05\0\x01\0\0\x0e\x10\0\x06\x03www\xc0\x0c");
+        message.extend_from_slice(b"\xc0\x29\0\x01\0\x01\0\0\0\x3c\0\x04\x0a\0\0\x01");
+        message.extend_from_slice(b"\xc0\x29\0\x01\0\x01\0\0\x01\x2c\0\x04\x0a\0\0\x02");
+
+        assert_eq!(
+            decode_answer(&message).unwrap(),
+            Answer {
+                addrs: vec![[10, 0, 0, 1].into(), [10, 0, 0, 2].into()],
+                ttl: Duration::from_secs(60),
+            }
+        );
+    }
+
+    #[test]
+    fn cache_is_bounded() {
+        let now = Instant::now();
+        let mut cache = Cache::default();
+        let entry = |secs| CacheEntry {
+            addrs: vec![Ipv4Addr::LOCALHOST.into()],
+            expires: now + Duration::from_secs(secs),
+        };
+
+        cache.insert("expired.test".into(), entry(0), now);
+        for i in 1..MAX_CACHE_ENTRIES {
+            cache.insert(format!("{}.test", i), entry(60 + i as u64), now);
+        }
+        assert_eq!(cache.entries.len(), MAX_CACHE_ENTRIES);
+        assert_eq!(cache.get("expired.test", now), None);
+
+        // Expired entries are pruned first.
+        cache.insert("new.test".into(), entry(60), now);
+        assert_eq!(cache.entries.len(), MAX_CACHE_ENTRIES);
+        assert!(!cache.entries.contains_key("expired.test"));
+
+        // Otherwise the entry expiring soonest is evicted.
+        cache.insert("newer.test".into(), entry(3600), now);
+        assert_eq!(cache.entries.len(), MAX_CACHE_ENTRIES);
+        assert_eq!(cache.get("new.test", now), None);
+        assert!(cache.get("1.test", now).is_some());
+        assert!(cache.get("newer.test", now).is_some());
+    }
+
+    #[test]
+    fn decode_error_responses() {
+        let nxdomain = b"\0\0\x81\x83\0\0\0\0\0\0\0\0";
+        assert_eq!(
+            decode_answer(nxdomain).unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        let truncated = b"\0\0\x81\x80\0\0\0\x01\0\0\0\0\xc0\x0c\0\x01";
+        assert_eq!(
+            decode_answer(truncated).unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
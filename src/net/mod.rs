@@ -1,3 +1,13 @@
 //! Types for configuring how HTTP connections interact with the network.
 
 pub mod interface;
+
+mod doh;
+mod resolve;
+mod socket;
+
+pub use self::doh::DnsOverHttps;
+pub use self::resolve::{Fallback, Resolve, ResolveFuture, StaticResolver, SystemResolver};
+pub(crate) use self::resolve::{Resolved, SharedResolver, resolve_uri};
+pub(crate) use self::socket::Connected;
+pub use self::socket::Socket;
@@ -0,0 +1,382 @@
+//! Pluggable resolution of host names into socket addresses.
+
+use crate::{
+    config::{
+        ResolveMap,
+        setopt::{EasyHandle, SetOpt, SetOptError},
+    },
+    task::spawn_blocking,
+};
+use curl::easy::List;
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    io,
+    net::{IpAddr, SocketAddr, ToSocketAddrs},
+    pin::Pin,
+    sync::Arc,
+};
+
+/// The type of future returned by a [`Resolve`] implementation.
+pub type ResolveFuture<'a> = Pin<Box<dyn Future<Output = io::Result<Vec<SocketAddr>>> + Send + 'a>>;
+
+/// A name resolver that looks up the socket addresses to connect to for a
+/// given host and port.
+///
+/// A resolver can be installed for a client using
+/// [`HttpClientBuilder::resolver`](crate::HttpClientBuilder::resolver), in
+/// which case it is consulted before each request is sent, and the addresses
+/// it returns are used in place of curl's own name resolution. This makes it
+/// possible to integrate with service discovery systems, or to resolve names
+/// using a custom DNS implementation.
+///
+/// Since clients may be used to send requests concurrently, resolvers must be
+/// able to handle multiple lookups in parallel.
+///
+/// # Examples
+///
+/// ```
+/// use isahc::net::{Resolve, ResolveFuture};
+/// use std::net::{Ipv4Addr, SocketAddr};
+///
+/// struct Discovery;
+///
+/// impl Resolve for Discovery {
+///     fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+///         Box::pin(async move {
+///             // Look up the service in a registry here.
+///             Ok(vec![SocketAddr::from((Ipv4Addr::LOCALHOST, port))])
+///         })
+///     }
+/// }
+/// ```
+pub trait Resolve: Send + Sync {
+    /// Resolve the given host name and port into a list of socket addresses
+    /// to connect to, in order of preference.
+    ///
+    /// The returned future is allowed to borrow the resolver for the duration
+    /// of its execution.
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a>;
+
+    /// Create a resolver that uses this resolver, and uses the given resolver
+    /// whenever this resolver fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::net::{Resolve, StaticResolver, SystemResolver};
+    /// use std::net::Ipv4Addr;
+    ///
+    /// let resolver = StaticResolver::new()
+    ///     .add("api.example.org", 443, Ipv4Addr::new(10, 0, 0, 1))
+    ///     .or(SystemResolver);
+    /// ```
+    fn or<R>(self, fallback: R) -> Fallback<Self, R>
+    where
+        Self: Sized,
+        R: Resolve,
+    {
+        Fallback {
+            primary: self,
+            fallback,
+        }
+    }
+}
+
+impl<R: Resolve + ?Sized> Resolve for Arc<R> {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+        (**self).resolve(host, port)
+    }
+}
+
+impl<R: Resolve + ?Sized> Resolve for Box<R> {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+        (**self).resolve(host, port)
+    }
+}
+
+/// A resolver that uses the name resolution facilities of the operating
+/// system, such as `getaddrinfo`.
+///
+/// Lookups are blocking operations, so they are performed on a small shared
+/// pool of background threads.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemResolver;
+
+impl Resolve for SystemResolver {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+        let host = host.to_owned();
+        let lookup = spawn_blocking(move || {
+            (host.as_str(), port)
+                .to_socket_addrs()
+                .map(Iterator::collect)
+        });
+
+        Box::pin(async move { lookup.await? })
+    }
+}
+
+/// A resolver that returns fixed addresses for specific host and port pairs.
+///
+/// Hosts are matched case-insensitively. Looking up a host and port pair that
+/// has no addresses fails with an error of kind [`io::ErrorKind::NotFound`],
+/// which makes this resolver suitable as the first in a chain of resolvers
+/// created using [`Resolve::or`].
+///
+/// This is similar to a [`ResolveMap`], except that it can be combined with
+/// other resolvers.
+#[derive(Clone, Debug, Default)]
+pub struct StaticResolver(HashMap<(String, u16), Vec<SocketAddr>>);
+
+impl StaticResolver {
+    /// Create a new resolver without any addresses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an address for a given host and port pair. If multiple addresses
+    /// are added for the same pair, they are returned in the order they were
+    /// added.
+    #[must_use = "builders have no effect if unused"]
+    pub fn add<H, A>(mut self, host: H, port: u16, addr: A) -> Self
+    where
+        H: AsRef<str>,
+        A: Into<IpAddr>,
+    {
+        self.0
+            .entry((host.as_ref().to_ascii_lowercase(), port))
+            .or_default()
+            .push(SocketAddr::new(addr.into(), port));
+        self
+    }
+}
+
+impl Resolve for StaticResolver {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+        let result = self
+            .0
+            .get(&(host.to_ascii_lowercase(), port))
+            .cloned()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("no static addresses for {}:{}", host, port),
+                )
+            });
+
+        Box::pin(async move { result })
+    }
+}
+
+/// A resolver that falls back to a second resolver when the first one fails.
+/// See [`Resolve::or`] for details.
+#[derive(Clone, Debug)]
+pub struct Fallback<A, B> {
+    primary: A,
+    fallback: B,
+}
+
+impl<A: Resolve, B: Resolve> Resolve for Fallback<A, B> {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+        Box::pin(async move {
+            match self.primary.resolve(host, port).await {
+                Ok(addrs) if !addrs.is_empty() => Ok(addrs),
+                Ok(_) => self.fallback.resolve(host, port).await,
+                Err(e) => {
+                    tracing::debug!(host, port, "resolver failed, trying fallback: {}", e);
+                    self.fallback.resolve(host, port).await
+                }
+            }
+        })
+    }
+}
+
+/// A resolver shared by all requests sent using a client.
+#[derive(Clone)]
+pub(crate) struct SharedResolver(Arc<dyn Resolve>);
+
+impl SharedResolver {
+    pub(crate) fn new(resolver: impl Resolve + 'static) -> Self {
+        Self(Arc::new(resolver))
+    }
+}
+
+impl Resolve for SharedResolver {
+    fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+        self.0.resolve(host, port)
+    }
+}
+
+impl fmt::Debug for SharedResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SharedResolver")
+    }
+}
+
+/// Resolve the host of a request URI using the given resolver, returning
+/// `None` if the URI does not need to be resolved.
+pub(crate) async fn resolve_uri(
+    resolver: &dyn Resolve,
+    uri: &http::Uri,
+    static_map: Option<&ResolveMap>,
+) -> Result<Option<Resolved>, crate::Error> {
+    let Some(host) = uri.host() else {
+        return Ok(None);
+    };
+
+    // IP addresses do not need to be resolved.
+    if host.starts_with('[') || host.parse::<IpAddr>().is_ok() {
+        return Ok(None);
+    }
+
+    let port = uri.port_u16().unwrap_or_else(|| {
+        if uri.scheme() == Some(&http::uri::Scheme::HTTPS) {
+            443
+        } else {
+            80
+        }
+    });
+
+    let addrs = resolver.resolve(host, port).await.map_err(|e| {
+        crate::Error::with_context(
+            crate::error::ErrorKind::NameResolution,
+            Some(format!("failed to resolve {}:{}", host, port)),
+            e,
+        )
+    })?;
+
+    Resolved::new(host, port, &addrs, static_map).map(Some)
+}
+
+/// Addresses found by a resolver for a single request, in the form expected
+/// by curl.
+#[derive(Clone, Debug)]
+pub(crate) struct Resolved {
+    resolve: ResolveMap,
+    connect_to: Option<String>,
+}
+
+impl Resolved {
+    fn new(
+        host: &str,
+        port: u16,
+        addrs: &[SocketAddr],
+        static_map: Option<&ResolveMap>,
+    ) -> Result<Self, crate::Error> {
+        let Some(first) = addrs.first() else {
+            return Err(crate::Error::with_context(
+                crate::error::ErrorKind::NameResolution,
+                Some(format!("no addresses found for {}:{}", host, port)),
+                io::Error::from(io::ErrorKind::NotFound),
+            ));
+        };
+
+        // Curl only knows about addresses for a single port per host and port
+        // pair, so connect to the port of the first address using any other
+        // addresses for the same port.
+        let target_port = first.port();
+        let ips = addrs
+            .iter()
+            .filter(|addr| addr.port() == target_port)
+            .map(SocketAddr::ip);
+
+        Ok(Self {
+            resolve: static_map
+                .cloned()
+                .unwrap_or_default()
+                .add_all(host, target_port, ips),
+            connect_to: (target_port != port)
+                .then(|| format!("{}:{}:{}:{}", host, port, host, target_port)),
+        })
+    }
+}
+
+impl SetOpt for Resolved {
+    fn set_opt(&self, easy: &mut EasyHandle) -> Result<(), SetOptError> {
+        self.resolve.set_opt(easy)?;
+
+        if let Some(connect_to) = self.connect_to.as_ref() {
+            let mut list = List::new();
+            list.append(connect_to)?;
+            easy.connect_to(list)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn static_resolver_matches_host_and_port() {
+        let resolver = StaticResolver::new()
+            .add("Example.org", 80, Ipv4Addr::new(10, 0, 0, 1))
+            .add("example.org", 80, Ipv4Addr::new(10, 0, 0, 2));
+
+        let addrs = futures_lite::future::block_on(resolver.resolve("EXAMPLE.org", 80)).unwrap();
+        assert_eq!(
+            addrs,
+            [
+                "10.0.0.1:80".parse().unwrap(),
+                "10.0.0.2:80".parse().unwrap()
+            ] as [SocketAddr; 2]
+        );
+
+        let error =
+            futures_lite::future::block_on(resolver.resolve("example.org", 443)).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn fallback_is_used_when_primary_fails() {
+        let resolver = StaticResolver::new()
+            .add("a.test", 80, Ipv4Addr::new(10, 0, 0, 1))
+            .or(StaticResolver::new().add("b.test", 80, Ipv4Addr::new(10, 0, 0, 2)));
+
+        let resolve = |host| futures_lite::future::block_on(resolver.resolve(host, 80));
+
+        assert_eq!(
+            resolve("a.test").unwrap()[0].ip(),
+            Ipv4Addr::new(10, 0, 0, 1)
+        );
+        assert_eq!(
+            resolve("b.test").unwrap()[0].ip(),
+            Ipv4Addr::new(10, 0, 0, 2)
+        );
+        assert!(resolve("c.test").is_err());
+    }
+
+    #[test]
+    fn system_resolver_resolves_ip_literals() {
+        let addrs =
+            futures_lite::future::block_on(SystemResolver.resolve("127.0.0.1", 8080)).unwrap();
+
+        assert_eq!(
+            addrs,
+            ["127.0.0.1:8080".parse().unwrap()] as [SocketAddr; 1]
+        );
+    }
+
+    #[test]
+    fn resolved_addresses_on_other_port_use_connect_to() {
+        let addrs = [
+            "10.0.0.1:8080".parse().unwrap(),
+            "10.0.0.2:9090".parse().unwrap(),
+        ];
+
+        let same_port = Resolved::new("a.test", 8080, &addrs, None).unwrap();
+        assert_eq!(same_port.connect_to, None);
+
+        let other_port = Resolved::new("a.test", 80, &addrs, None).unwrap();
+        assert_eq!(
+            other_port.connect_to.as_deref(),
+            Some("a.test:80:a.test:8080")
+        );
+
+        assert!(Resolved::new("a.test", 80, &[], None).is_err());
+    }
+}
@@ -0,0 +1,167 @@
+//! Sockets created by the user and handed to curl for a single connection.
+
+use crate::config::setopt::{EasyHandle, SetOpt, SetOptError};
+use curl::easy::List;
+use curl_sys::{CURLOPTTYPE_FUNCTIONPOINT, CURLoption, curl_socket_t};
+use std::{
+    fmt,
+    net::{SocketAddr, TcpStream},
+    os::raw::{c_int, c_void},
+};
+
+#[cfg(unix)]
+use std::os::{
+    fd::{AsFd, BorrowedFd, IntoRawFd, OwnedFd},
+    unix::net::UnixStream,
+};
+
+#[cfg(windows)]
+use std::os::windows::io::{AsSocket, BorrowedSocket, IntoRawSocket, OwnedSocket};
+
+// Not yet exposed by curl-sys.
+const CURLOPT_SOCKOPTFUNCTION: CURLoption = CURLOPTTYPE_FUNCTIONPOINT + 148;
+const CURL_SOCKOPT_ALREADY_CONNECTED: c_int = 2;
+
+/// An already connected socket to send a request over, as returned by a
+/// custom [`Dialer`](crate::config::Dialer).
+///
+/// A socket can be created from any connected stream socket, such as a
+/// [`TcpStream`] or a [`UnixStream`], or from a raw socket handle owned by the
+/// caller. Isahc takes ownership of the socket and closes it once the
+/// connection is closed.
+pub struct Socket {
+    #[cfg(unix)]
+    inner: OwnedFd,
+
+    #[cfg(windows)]
+    inner: OwnedSocket,
+
+    /// Address of the remote peer, if known.
+    peer_addr: Option<SocketAddr>,
+}
+
+impl Socket {
+    /// Hand ownership of the socket over to curl.
+    pub(crate) fn into_raw(self) -> curl_socket_t {
+        #[cfg(unix)]
+        return self.inner.into_raw_fd();
+
+        #[cfg(windows)]
+        return self.inner.into_raw_socket() as curl_socket_t;
+    }
+}
+
+impl fmt::Debug for Socket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Socket")
+            .field("inner", &self.inner)
+            .field("peer_addr", &self.peer_addr)
+            .finish()
+    }
+}
+
+impl From<TcpStream> for Socket {
+    fn from(stream: TcpStream) -> Self {
+        let peer_addr = stream.peer_addr().ok();
+
+        Self {
+            inner: stream.into(),
+            peer_addr,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl From<UnixStream> for Socket {
+    fn from(stream: UnixStream) -> Self {
+        Self {
+            inner: stream.into(),
+            peer_addr: None,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl From<OwnedFd> for Socket {
+    fn from(fd: OwnedFd) -> Self {
+        Self {
+            inner: fd,
+            peer_addr: None,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl AsFd for Socket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.inner.as_fd()
+    }
+}
+
+#[cfg(windows)]
+impl From<OwnedSocket> for Socket {
+    fn from(socket: OwnedSocket) -> Self {
+        Self {
+            inner: socket,
+            peer_addr: None,
+        }
+    }
+}
+
+#[cfg(windows)]
+impl AsSocket for Socket {
+    fn as_socket(&self) -> BorrowedSocket<'_> {
+        self.inner.as_socket()
+    }
+}
+
+/// Configures an easy handle to connect using a socket that has already been
+/// connected, which is then handed to curl by the request handler.
+pub(crate) struct Connected {
+    /// Address curl should believe it is connecting to.
+    connect_to: String,
+}
+
+impl Connected {
+    pub(crate) fn new(socket: &Socket) -> Self {
+        // Curl still goes through the motions of connecting to an address
+        // before it asks for a socket, so use the address of the peer if it is
+        // known to keep reported addresses accurate, or a placeholder that
+        // avoids any name resolution otherwise.
+        let connect_to = match socket.peer_addr {
+            Some(addr) => format!("::{}", addr),
+            None => String::from("::127.0.0.1:"),
+        };
+
+        Self { connect_to }
+    }
+}
+
+impl SetOpt for Connected {
+    #[allow(unsafe_code)]
+    fn set_opt(&self, easy: &mut EasyHandle) -> Result<(), SetOptError> {
+        let mut connect_to = List::new();
+        connect_to.append(&self.connect_to)?;
+        easy.connect_to(connect_to)?;
+
+        // The socket is only good for a single connection, which must neither
+        // be shared with other requests nor be replaced with a pooled one.
+        easy.fresh_connect(true)?;
+        easy.forbid_reuse(true)?;
+
+        let callback: extern "C" fn(*mut c_void, curl_socket_t, c_int) -> c_int = sockopt_cb;
+
+        unsafe {
+            match curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_SOCKOPTFUNCTION, callback) {
+                curl_sys::CURLE_OK => Ok(()),
+                code => Err(curl::Error::new(code).into()),
+            }
+        }
+    }
+}
+
+/// Tells curl that the socket it just opened is already connected, so that it
+/// skips connecting it.
+extern "C" fn sockopt_cb(_: *mut c_void, _: curl_socket_t, _: c_int) -> c_int {
+    CURL_SOCKOPT_ALREADY_CONNECTED
+}
@@ -0,0 +1,263 @@
+use crate::{
+    body::AsyncBody,
+    config::RateLimit,
+    error::Error,
+    interceptor::{Context, Interceptor, InterceptorFuture},
+    task::delay,
+};
+use event_listener::Event;
+use futures_lite::io::AsyncRead;
+use http::Request;
+use std::{
+    collections::HashMap,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context as TaskContext, Poll},
+    time::{Duration, Instant},
+};
+
+/// Number of keys to track before idle buckets are pruned.
+const PRUNE_THRESHOLD: usize = 256;
+
+/// Interceptor that delays requests until they are permitted by a rate limit.
+pub(crate) struct RateLimitInterceptor {
+    limit: RateLimit,
+    buckets: Mutex<HashMap<String, Arc<Bucket>>>,
+}
+
+/// Limiter state for a single key.
+struct Bucket {
+    state: Mutex<BucketState>,
+
+    /// Notified whenever an in-flight request completes.
+    released: Event,
+}
+
+struct BucketState {
+    /// Number of tokens available. May become negative, in which case
+    /// requests have reserved tokens that have yet to be replenished.
+    tokens: f64,
+
+    /// When tokens were last replenished.
+    updated: Instant,
+
+    /// Number of requests currently in flight.
+    in_flight: usize,
+}
+
+impl RateLimitInterceptor {
+    pub(crate) fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn bucket(&self, key: String) -> Arc<Bucket> {
+        let mut buckets = self.buckets.lock().unwrap();
+
+        if buckets.len() >= PRUNE_THRESHOLD {
+            let token_bucket = self.limit.token_bucket();
+            buckets
+                .retain(|_, bucket| Arc::strong_count(bucket) > 1 || !bucket.is_idle(token_bucket));
+        }
+
+        buckets
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(Bucket {
+                    state: Mutex::new(BucketState {
+                        tokens: self
+                            .limit
+                            .token_bucket()
+                            .map_or(0.0, |(_, capacity)| capacity),
+                        updated: Instant::now(),
+                        in_flight: 0,
+                    }),
+                    released: Event::new(),
+                })
+            })
+            .clone()
+    }
+}
+
+impl Interceptor for RateLimitInterceptor {
+    type Err = Error;
+
+    fn intercept<'a>(
+        &'a self,
+        request: Request<AsyncBody>,
+        ctx: Context<'a>,
+    ) -> InterceptorFuture<'a, Self::Err> {
+        Box::pin(async move {
+            let key = match self.limit.key_for(&request) {
+                Some(key) => key,
+                None => return ctx.send(request).await,
+            };
+
+            let bucket = self.bucket(key);
+
+            // Wait for a free slot first so that a request waiting for a slot
+            // does not hold on to a token it is not using yet.
+            let permit = bucket.acquire(self.limit.in_flight_limit()).await;
+
+            if let Some((rate, capacity)) = self.limit.token_bucket() {
+                let wait = bucket.reserve(rate, capacity);
+
+                if !wait.is_zero() {
+                    tracing::debug!(?wait, "rate limit exceeded, delaying request");
+                    delay(wait).await;
+                }
+            }
+
+            let response = ctx.send(request).await?;
+
+            // Keep the request in flight until the response body is consumed.
+            Ok(response.map(|body| {
+                let len = body.len();
+                let body = PermitBody {
+                    body,
+                    permit: Some(permit),
+                };
+
+                match len {
+                    Some(len) => AsyncBody::from_reader_sized(body, len),
+                    None => AsyncBody::from_reader(body),
+                }
+            }))
+        })
+    }
+}
+
+impl Bucket {
+    /// Wait until a request may be put in flight.
+    async fn acquire(self: &Arc<Self>, limit: Option<usize>) -> Permit {
+        loop {
+            let listener = {
+                let mut state = self.state.lock().unwrap();
+
+                if limit.is_none_or(|limit| state.in_flight < limit) {
+                    state.in_flight += 1;
+
+                    return Permit(self.clone());
+                }
+
+                // Listen while still holding the lock so that a release cannot
+                // be missed between checking and waiting.
+                self.released.listen()
+            };
+
+            listener.await;
+        }
+    }
+
+    /// Take a token from the bucket, returning how long to wait until the
+    /// token is available.
+    fn reserve(&self, rate: f64, capacity: f64) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.updated).as_secs_f64();
+
+        state.tokens = (state.tokens + elapsed * rate).min(capacity) - 1.0;
+        state.updated = now;
+
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / rate)
+        }
+    }
+
+    /// Check whether this bucket is in its initial state, and so can be
+    /// discarded without any effect on future requests.
+    fn is_idle(&self, token_bucket: Option<(f64, f64)>) -> bool {
+        let state = self.state.lock().unwrap();
+
+        state.in_flight == 0
+            && token_bucket.is_none_or(|(rate, capacity)| {
+                let elapsed = state.updated.elapsed().as_secs_f64();
+
+                state.tokens + elapsed * rate >= capacity
+            })
+    }
+}
+
+/// Marks a request as being in flight until dropped.
+struct Permit(Arc<Bucket>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.state.lock().unwrap().in_flight -= 1;
+        self.0.released.notify(1);
+    }
+}
+
+/// Response body wrapper that holds a permit until the end of the body is
+/// reached.
+struct PermitBody {
+    body: AsyncBody,
+    permit: Option<Permit>,
+}
+
+impl AsyncRead for PermitBody {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = &mut *self;
+        let result = futures_lite::ready!(Pin::new(&mut this.body).poll_read(cx, buf));
+
+        if matches!(result, Ok(0) | Err(_)) {
+            this.permit = None;
+        }
+
+        Poll::Ready(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(tokens: f64) -> Arc<Bucket> {
+        Arc::new(Bucket {
+            state: Mutex::new(BucketState {
+                tokens,
+                updated: Instant::now(),
+                in_flight: 0,
+            }),
+            released: Event::new(),
+        })
+    }
+
+    #[test]
+    fn tokens_are_reserved_in_order() {
+        let bucket = bucket(2.0);
+
+        assert_eq!(bucket.reserve(10.0, 2.0), Duration::ZERO);
+        assert_eq!(bucket.reserve(10.0, 2.0), Duration::ZERO);
+
+        let first = bucket.reserve(10.0, 2.0);
+        let second = bucket.reserve(10.0, 2.0);
+
+        assert!(first > Duration::from_millis(90) && first <= Duration::from_millis(100));
+        assert!(second > Duration::from_millis(190) && second <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn permits_are_limited_and_released_on_drop() {
+        let bucket = bucket(0.0);
+        let first = futures_lite::future::block_on(bucket.acquire(Some(1)));
+
+        let mut second = Box::pin(bucket.acquire(Some(1)));
+        assert!(
+            futures_lite::future::block_on(futures_lite::future::poll_once(&mut second)).is_none()
+        );
+
+        drop(first);
+        let _second = futures_lite::future::block_on(second);
+        assert!(!bucket.is_idle(None));
+    }
+}
@@ -47,9 +47,12 @@ impl Interceptor for RedirectInterceptor {
             // No redirect handling, just proceed normally.
             if policy == RedirectPolicy::None {
                 let mut response = ctx.send(request).await?;
-                response
-                    .extensions_mut()
-                    .insert(EffectiveUri(effective_uri));
+
+                if response.extensions().get::<EffectiveUri>().is_none() {
+                    response
+                        .extensions_mut()
+                        .insert(EffectiveUri(effective_uri));
+                }
 
                 return Ok(response);
             }
@@ -75,6 +78,12 @@ impl Interceptor for RedirectInterceptor {
                 // Send the request to get the ball rolling.
                 let mut response = ctx.send(request).await?;
 
+                // An inner interceptor may have sent the request to a
+                // different URI, such as when upgrading to HTTPS.
+                if let Some(EffectiveUri(uri)) = response.extensions().get() {
+                    effective_uri = uri.clone();
+                }
+
                 // Check for a redirect.
                 if let Some(redirect_location) = get_redirect_location(&effective_uri, &response) {
                     // If we've reached the limit, return an error as requested.
@@ -87,6 +87,68 @@ impl<T> RequestExt<T> for Request<T> {
     }
 }
 
+/// Extension methods on an HTTP request builder.
+///
+/// # Availability
+///
+/// This trait is only available when the [`form`](index.html#form) feature is
+/// enabled.
+#[cfg(feature = "form")]
+pub trait RequestBuilderExt: Sized {
+    /// Serialize the given value as URL-encoded query parameters and append
+    /// them to the query string of the request URI.
+    ///
+    /// Any parameters already present in the URI are kept, with the new
+    /// parameters added after them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::{prelude::*, Request};
+    ///
+    /// let request = Request::get("https://example.org/search?page=2")
+    ///     .query(&[("q", "rust & curl")])?
+    ///     .body(())?;
+    ///
+    /// assert_eq!(request.uri(), "https://example.org/search?page=2&q=rust+%26+curl");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn query<T>(self, query: &T) -> Result<Self, serde_urlencoded::ser::Error>
+    where
+        T: serde::Serialize + ?Sized;
+}
+
+#[cfg(feature = "form")]
+impl RequestBuilderExt for http::request::Builder {
+    fn query<T>(self, query: &T) -> Result<Self, serde_urlencoded::ser::Error>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        let serialized = serde_urlencoded::to_string(query)?;
+
+        // Nothing to add, or the builder already has an error that will be
+        // reported when it is built.
+        let uri = match self.uri_ref() {
+            Some(uri) if !serialized.is_empty() => uri,
+            _ => return Ok(self),
+        };
+
+        let path_and_query = match uri.query() {
+            Some(existing) if !existing.is_empty() => {
+                format!("{}?{}&{}", uri.path(), existing, serialized)
+            }
+            _ => format!("{}?{}", uri.path(), serialized),
+        };
+
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = path_and_query.try_into().ok();
+
+        // If the new URI is somehow invalid, the builder will hold on to the
+        // error and return it when the request is built.
+        Ok(self.uri(parts))
+    }
+}
+
 impl Configurable for http::request::Builder {
     #[cfg(feature = "cookies")]
     fn cookie_jar(self, cookie_jar: crate::cookies::CookieJar) -> Self {
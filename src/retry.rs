@@ -0,0 +1,179 @@
+use crate::{
+    body::AsyncBody,
+    config::{RetryPolicy, request::RequestConfig},
+    error::{Error, ErrorKind},
+    handler::RequestBody,
+    interceptor::{Context, Interceptor, InterceptorFuture},
+    request::RequestExt,
+    task::delay,
+};
+use http::{HeaderValue, Method, Request, Response};
+use std::time::{Duration, SystemTime};
+
+/// Interceptor that implements automatic retrying of failed requests according
+/// to the request's retry policy.
+pub(crate) struct RetryInterceptor;
+
+impl Interceptor for RetryInterceptor {
+    type Err = Error;
+
+    fn intercept<'a>(
+        &'a self,
+        mut request: Request<AsyncBody>,
+        ctx: Context<'a>,
+    ) -> InterceptorFuture<'a, Self::Err> {
+        Box::pin(async move {
+            // Get the retry policy for this request.
+            let policy = request
+                .extensions()
+                .get::<RequestConfig>()
+                .and_then(|config| config.retry_policy.as_ref())
+                .cloned()
+                .unwrap_or_default();
+
+            // No retry handling, just proceed normally.
+            if policy.max_retries() == 0 || !is_idempotent(request.method()) {
+                return ctx.send(request).await;
+            }
+
+            let mut attempt: u32 = 0;
+
+            loop {
+                // Preserve a clone of the request before sending it.
+                let request_builder = request.to_builder();
+
+                // If the request fails we never get the body back from the
+                // handler, so keep a copy around in case we need to send it
+                // again. This is only possible for bodies stored in memory.
+                let body_copy = request.body().try_clone();
+
+                let (request_body, wait) = match ctx.send(request).await {
+                    Ok(mut response) => {
+                        if attempt >= policy.max_retries()
+                            || !policy.should_retry_status(response.status())
+                        {
+                            return Ok(response);
+                        }
+
+                        let wait = match get_retry_after(&policy, &response) {
+                            // Server asked us to wait longer than we are
+                            // willing to, give up and return the response.
+                            Some(wait) if wait > policy.max_backoff() => return Ok(response),
+                            Some(wait) => wait,
+                            None => policy.backoff_for(attempt),
+                        };
+
+                        // Grab the request body back from the internal
+                        // handler and rewind it so that it can be sent again.
+                        let mut request_body = response
+                            .extensions_mut()
+                            .remove::<RequestBody>()
+                            .map(|v| v.0)
+                            .unwrap_or_default();
+
+                        if !request_body.reset() {
+                            return Err(Error::with_response(
+                                ErrorKind::RequestBodyNotRewindable,
+                                &response,
+                            ));
+                        }
+
+                        (request_body, wait)
+                    }
+                    Err(e) => {
+                        if attempt >= policy.max_retries() || !policy.should_retry_error(e.kind()) {
+                            return Err(e);
+                        }
+
+                        let request_body = body_copy
+                            .ok_or_else(|| Error::new(ErrorKind::RequestBodyNotRewindable, e))?;
+
+                        (request_body, policy.backoff_for(attempt))
+                    }
+                };
+
+                attempt += 1;
+
+                tracing::debug!(attempt, ?wait, "retrying request");
+                delay(wait).await;
+
+                request = request_builder
+                    .body(request_body)
+                    .map_err(|e| Error::new(ErrorKind::InvalidRequest, e))?;
+            }
+        })
+    }
+}
+
+/// Check whether a request method is idempotent as defined by [RFC 9110,
+/// Section 9.2.2](https://www.rfc-editor.org/rfc/rfc9110#section-9.2.2).
+pub(crate) fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS | Method::TRACE
+    )
+}
+
+/// Get the delay requested by the server via the `Retry-After` header, if
+/// present and the policy allows it.
+fn get_retry_after<T>(policy: &RetryPolicy, response: &Response<T>) -> Option<Duration> {
+    if !policy.respects_retry_after() {
+        return None;
+    }
+
+    response
+        .headers()
+        .get(http::header::RETRY_AFTER)
+        .and_then(parse_retry_after)
+}
+
+/// Parse a `Retry-After` header value, which is either a number of seconds or
+/// an HTTP date.
+fn parse_retry_after(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+
+    // A date in the past means we can retry right away.
+    Some(date.duration_since(SystemTime::now()).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    #[test_case("120", Some(Duration::from_secs(120)))]
+    #[test_case(" 5 ", Some(Duration::from_secs(5)))]
+    #[test_case("Wed, 21 Oct 2015 07:28:00 GMT", Some(Duration::ZERO))]
+    #[test_case("-1", None)]
+    #[test_case("soon", None)]
+    fn parse_retry_after_values(value: &str, expected: Option<Duration>) {
+        assert_eq!(
+            parse_retry_after(&HeaderValue::from_str(value).unwrap()),
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_retry_after_future_date() {
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(60));
+        let parsed = parse_retry_after(&HeaderValue::from_str(&date).unwrap()).unwrap();
+
+        assert!(parsed > Duration::from_secs(55));
+        assert!(parsed <= Duration::from_secs(60));
+    }
+
+    #[test_case(Method::GET, true)]
+    #[test_case(Method::PUT, true)]
+    #[test_case(Method::DELETE, true)]
+    #[test_case(Method::POST, false)]
+    #[test_case(Method::PATCH, false)]
+    fn idempotent_methods(method: Method, expected: bool) {
+        assert_eq!(is_idempotent(&method), expected);
+    }
+}
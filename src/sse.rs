@@ -0,0 +1,703 @@
+//! Types for consuming [server-sent
+//! events](https://html.spec.whatwg.org/multipage/server-sent-events.html).
+//!
+//! A response with a `text/event-stream` body can be read as a sequence of
+//! [`Event`]s using [`ReadResponseExt::sse`](crate::ReadResponseExt::sse) or
+//! [`AsyncReadResponseExt::sse`](crate::AsyncReadResponseExt::sse). Events are
+//! parsed incrementally as the body arrives, so the stream can be consumed for
+//! as long as the server keeps the connection open.
+//!
+//! To automatically reconnect when the connection is closed, use an
+//! [`EventSource`] instead, which re-sends the request using the same client
+//! and resumes the stream from the last event received.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use isahc::prelude::*;
+//!
+//! let mut response = isahc::get("https://example.org/events")?;
+//!
+//! for event in response.sse() {
+//!     let event = event?;
+//!     println!("{}: {}", event.event(), event.data());
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+use crate::{
+    body::AsyncBody,
+    client::HttpClient,
+    error::{Error, ErrorKind},
+    request::RequestExt,
+    task::{Delay, delay},
+};
+use futures_lite::{Stream, StreamExt, future::block_on, io::AsyncRead};
+use http::{HeaderValue, Request, Response, StatusCode};
+use std::{
+    collections::VecDeque,
+    fmt,
+    future::Future,
+    io::{self, Read},
+    mem,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+/// Name of the header used to tell the server the last event received.
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// Reconnection time used until the server specifies one.
+const DEFAULT_RECONNECTION_TIME: Duration = Duration::from_secs(3);
+
+/// A single event received from an event stream.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Event {
+    id: Option<String>,
+    event: String,
+    data: String,
+    retry: Option<Duration>,
+}
+
+impl Event {
+    /// Get the last event ID set by the server at the time this event was
+    /// received, if any.
+    ///
+    /// As with browsers, the ID persists for subsequent events until the
+    /// server sets a new one.
+    pub fn id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Get the type of this event. Defaults to `message` if the server did not
+    /// specify one.
+    pub fn event(&self) -> &str {
+        &self.event
+    }
+
+    /// Get the data of this event. Multiple `data` lines are joined using a
+    /// line feed.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// Get the reconnection time requested by the server along with this
+    /// event, if any.
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+}
+
+/// Incremental parser for the event stream format.
+#[derive(Debug, Default)]
+struct Parser {
+    /// Bytes of the line currently being received.
+    line: Vec<u8>,
+
+    /// Whether the last byte received was a carriage return, in which case a
+    /// following line feed is part of the same line ending.
+    after_cr: bool,
+
+    /// Whether the start of the stream has been processed, used to strip a
+    /// leading byte order mark.
+    started: bool,
+
+    event: String,
+    data: String,
+    retry: Option<Duration>,
+    last_event_id: Option<String>,
+    reconnection_time: Option<Duration>,
+    events: VecDeque<Event>,
+}
+
+impl Parser {
+    fn feed(&mut self, bytes: &[u8]) {
+        if self.started {
+            return self.feed_lines(bytes);
+        }
+
+        // Hold on to the start of the stream until we know whether it begins
+        // with a byte order mark.
+        self.line.extend_from_slice(bytes);
+
+        if self.line.len() < 3 && b"\xEF\xBB\xBF".starts_with(&self.line) {
+            return;
+        }
+
+        self.started = true;
+
+        let buffered = mem::take(&mut self.line);
+        let buffered = buffered.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&buffered);
+
+        self.feed_lines(buffered);
+    }
+
+    fn feed_lines(&mut self, mut bytes: &[u8]) {
+        if self.after_cr && bytes.first() == Some(&b'\n') {
+            bytes = &bytes[1..];
+        }
+
+        self.after_cr = false;
+
+        while let Some(end) = bytes.iter().position(|&b| b == b'\r' || b == b'\n') {
+            self.line.extend_from_slice(&bytes[..end]);
+
+            let line = mem::take(&mut self.line);
+            self.process_line(&line);
+
+            if bytes[end] == b'\r' {
+                match bytes.get(end + 1) {
+                    Some(b'\n') => bytes = &bytes[end + 2..],
+                    Some(_) => bytes = &bytes[end + 1..],
+                    None => {
+                        self.after_cr = true;
+                        bytes = &[];
+                    }
+                }
+            } else {
+                bytes = &bytes[end + 1..];
+            }
+        }
+
+        self.line.extend_from_slice(bytes);
+    }
+
+    fn process_line(&mut self, line: &[u8]) {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        // Comment line.
+        if line[0] == b':' {
+            return;
+        }
+
+        let line = String::from_utf8_lossy(line);
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (&*line, ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_owned(),
+            "data" => {
+                self.data.push_str(value);
+                self.data.push('\n');
+            }
+            "id" if !value.contains('\0') => {
+                self.last_event_id = Some(value.to_owned()).filter(|id| !id.is_empty());
+            }
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(millis) = value.parse() {
+                    let retry = Duration::from_millis(millis);
+                    self.retry = Some(retry);
+                    self.reconnection_time = Some(retry);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn dispatch(&mut self) {
+        let event = mem::take(&mut self.event);
+        let mut data = mem::take(&mut self.data);
+        let retry = self.retry.take();
+
+        // Blocks without any data do not produce an event.
+        if data.is_empty() {
+            return;
+        }
+
+        data.pop();
+
+        self.events.push_back(Event {
+            id: self.last_event_id.clone(),
+            event: if event.is_empty() {
+                String::from("message")
+            } else {
+                event
+            },
+            data,
+            retry,
+        });
+    }
+}
+
+/// An iterator over the events of a response body.
+///
+/// Created by [`ReadResponseExt::sse`](crate::ReadResponseExt::sse).
+pub struct Events<R> {
+    reader: R,
+    parser: Parser,
+    buf: Box<[u8]>,
+    done: bool,
+}
+
+impl<R> Events<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: Parser::default(),
+            buf: vec![0; 8192].into_boxed_slice(),
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Iterator for Events<R> {
+    type Item = io::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.parser.events.pop_front() {
+                return Some(Ok(event));
+            }
+
+            if self.done {
+                return None;
+            }
+
+            match self.reader.read(&mut self.buf) {
+                // Any incomplete event at the end of the stream is discarded.
+                Ok(0) => self.done = true,
+                Ok(n) => self.parser.feed(&self.buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+impl<R> fmt::Debug for Events<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Events").finish()
+    }
+}
+
+/// A stream of the events of a response body.
+///
+/// Created by [`AsyncReadResponseExt::sse`](crate::AsyncReadResponseExt::sse).
+pub struct EventStream<R> {
+    reader: R,
+    parser: Parser,
+    buf: Box<[u8]>,
+    done: bool,
+}
+
+impl<R> EventStream<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            reader,
+            parser: Parser::default(),
+            buf: vec![0; 8192].into_boxed_slice(),
+            done: false,
+        }
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for EventStream<R> {
+    type Item = io::Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            if let Some(event) = this.parser.events.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            match futures_lite::ready!(Pin::new(&mut this.reader).poll_read(cx, &mut this.buf)) {
+                Ok(0) => this.done = true,
+                Ok(n) => this.parser.feed(&this.buf[..n]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+        }
+    }
+}
+
+impl<R> fmt::Debug for EventStream<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventStream").finish()
+    }
+}
+
+/// An event stream that automatically reconnects.
+///
+/// When the connection is closed or fails, the request is sent again using
+/// the same client after waiting for the reconnection time, which the server
+/// may change using the `retry` field. If an event ID was received, it is sent
+/// to the server in the `Last-Event-ID` header so that the stream can resume
+/// where it left off.
+///
+/// Errors sending the request are yielded from the stream before reconnecting,
+/// and do not end the stream. The stream ends if the server responds with `204
+/// No Content`, and ends with an error if the server responds with any other
+/// status than `200 OK` or with a content type other than
+/// `text/event-stream`.
+///
+/// An event source is an asynchronous [`Stream`], and can also be turned into
+/// a blocking [`Iterator`] using [`IntoIterator`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use isahc::{sse::EventSource, HttpClient, Request};
+///
+/// let client = HttpClient::new()?;
+/// let request = Request::get("https://example.org/events").body(())?;
+///
+/// for event in EventSource::new(&client, request) {
+///     match event {
+///         Ok(event) => println!("{}", event.data()),
+///         Err(e) => eprintln!("connection error: {}", e),
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct EventSource {
+    client: HttpClient,
+    request: Request<()>,
+    last_event_id: Option<String>,
+    reconnection_time: Duration,
+    state: State,
+}
+
+enum State {
+    Idle,
+    Connecting(Pin<Box<dyn Future<Output = Result<Response<AsyncBody>, Error>> + Send>>),
+    Streaming(EventStream<AsyncBody>),
+    Waiting(Delay),
+    Closed,
+}
+
+impl EventSource {
+    /// Create a new event source that sends the given request using the given
+    /// client.
+    ///
+    /// The request is not sent until the first event is requested. If the
+    /// request already contains a `Last-Event-ID` header, it is used for the
+    /// first connection.
+    pub fn new(client: &HttpClient, request: Request<()>) -> Self {
+        let last_event_id = request
+            .headers()
+            .get(LAST_EVENT_ID)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        Self {
+            client: client.clone(),
+            request,
+            last_event_id,
+            reconnection_time: DEFAULT_RECONNECTION_TIME,
+            state: State::Idle,
+        }
+    }
+
+    /// Set the time to wait before reconnecting, until the server specifies
+    /// one. The default is 3 seconds.
+    #[must_use = "builders have no effect if unused"]
+    pub fn reconnection_time(mut self, time: Duration) -> Self {
+        self.reconnection_time = time;
+        self
+    }
+
+    /// Get the ID of the last event received, if any.
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    fn connect(&self) -> Result<State, Error> {
+        let mut request = self
+            .request
+            .to_builder()
+            .body(())
+            .map_err(|e| Error::new(ErrorKind::InvalidRequest, e))?;
+        let headers = request.headers_mut();
+
+        headers
+            .entry(http::header::ACCEPT)
+            .or_insert(HeaderValue::from_static("text/event-stream"));
+        headers
+            .entry(http::header::CACHE_CONTROL)
+            .or_insert(HeaderValue::from_static("no-cache"));
+
+        if let Some(id) = self.last_event_id.as_ref() {
+            let value =
+                HeaderValue::from_str(id).map_err(|e| Error::new(ErrorKind::InvalidRequest, e))?;
+            headers.insert(LAST_EVENT_ID, value);
+        }
+
+        let client = self.client.clone();
+
+        Ok(State::Connecting(Box::pin(async move {
+            client.send_async(request).await
+        })))
+    }
+
+    /// Remember the stream position from the connection that just ended.
+    fn disconnected(&mut self, stream: &EventStream<AsyncBody>) {
+        // The ID is carried over when connecting, so if it is unset now the
+        // server reset it with an empty `id` field.
+        self.last_event_id = stream.parser.last_event_id.clone();
+
+        if let Some(time) = stream.parser.reconnection_time {
+            self.reconnection_time = time;
+        }
+
+        tracing::debug!(
+            delay = ?self.reconnection_time,
+            "event stream disconnected, reconnecting"
+        );
+    }
+}
+
+impl Stream for EventSource {
+    type Item = Result<Event, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        loop {
+            match &mut this.state {
+                State::Idle => match this.connect() {
+                    Ok(state) => this.state = state,
+                    Err(e) => {
+                        this.state = State::Closed;
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                },
+                State::Connecting(future) => match futures_lite::ready!(future.as_mut().poll(cx)) {
+                    Ok(response) => match check_response(&response) {
+                        Ok(true) => {
+                            let mut stream = EventStream::new(response.into_body());
+                            stream.parser.last_event_id = this.last_event_id.clone();
+                            this.state = State::Streaming(stream);
+                        }
+                        Ok(false) => this.state = State::Closed,
+                        Err(e) => {
+                            this.state = State::Closed;
+                            return Poll::Ready(Some(Err(e)));
+                        }
+                    },
+                    Err(e) => {
+                        this.state = State::Waiting(delay(this.reconnection_time));
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                },
+                State::Streaming(stream) => match futures_lite::ready!(stream.poll_next(cx)) {
+                    Some(Ok(event)) => {
+                        this.last_event_id = event.id().map(str::to_owned);
+
+                        return Poll::Ready(Some(Ok(event)));
+                    }
+                    Some(Err(_)) | None => {
+                        if let State::Streaming(stream) =
+                            mem::replace(&mut this.state, State::Closed)
+                        {
+                            this.disconnected(&stream);
+                        }
+
+                        this.state = State::Waiting(delay(this.reconnection_time));
+                    }
+                },
+                State::Waiting(delay) => {
+                    futures_lite::ready!(Pin::new(delay).poll(cx));
+                    this.state = State::Idle;
+                }
+                State::Closed => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+impl IntoIterator for EventSource {
+    type Item = Result<Event, Error>;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+/// A blocking iterator over the events of an [`EventSource`].
+#[derive(Debug)]
+pub struct IntoIter(EventSource);
+
+impl Iterator for IntoIter {
+    type Item = Result<Event, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        block_on(self.0.next())
+    }
+}
+
+impl fmt::Debug for EventSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventSource")
+            .field("uri", self.request.uri())
+            .field("last_event_id", &self.last_event_id)
+            .field("reconnection_time", &self.reconnection_time)
+            .finish()
+    }
+}
+
+/// Check whether a response can be read as an event stream. Returns
+/// `Ok(false)` if the server asked the client to stop reconnecting.
+fn check_response<T>(response: &Response<T>) -> Result<bool, Error> {
+    if response.status() == StatusCode::NO_CONTENT {
+        return Ok(false);
+    }
+
+    let is_event_stream = response
+        .headers()
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case("text/event-stream"));
+
+    if response.status() != StatusCode::OK || !is_event_stream {
+        return Err(Error::with_context(
+            ErrorKind::ProtocolViolation,
+            Some(String::from("server did not respond with an event stream")),
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unexpected response status {}", response.status()),
+            ),
+        ));
+    }
+
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    static_assertions::assert_impl_all!(EventSource: Send, Unpin);
+
+    fn parse_chunks(chunks: &[&[u8]]) -> Vec<Event> {
+        let mut parser = Parser::default();
+
+        for chunk in chunks {
+            parser.feed(chunk);
+        }
+
+        parser.events.into_iter().collect()
+    }
+
+    fn parse(input: &str) -> Vec<Event> {
+        parse_chunks(&[input.as_bytes()])
+    }
+
+    fn event(event: &str, data: &str, id: Option<&str>) -> Event {
+        Event {
+            id: id.map(String::from),
+            event: event.to_owned(),
+            data: data.to_owned(),
+            retry: None,
+        }
+    }
+
+    #[test]
+    fn multi_line_data_is_joined() {
+        assert_eq!(
+            parse("data: YHOO\ndata: +2\ndata: 10\n\n"),
+            vec![event("message", "YHOO\n+2\n10", None)]
+        );
+    }
+
+    #[test]
+    fn comments_and_unknown_fields_are_ignored() {
+        assert_eq!(
+            parse(
+                ": test stream\nfoo: bar\ndata: first event\nid: 1\n\ndata:second event\nid\n\ndata:  third event\n\n"
+            ),
+            vec![
+                event("message", "first event", Some("1")),
+                event("message", "second event", None),
+                event("message", " third event", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_data_lines_produce_events() {
+        assert_eq!(
+            parse("data\n\ndata\ndata\n\ndata:\n"),
+            vec![event("message", "", None), event("message", "\n", None)]
+        );
+    }
+
+    #[test]
+    fn event_type_and_id_persist_correctly() {
+        assert_eq!(
+            parse("event: add\nid: 7\ndata: a\n\ndata: b\n\n"),
+            vec![
+                event("add", "a", Some("7")),
+                event("message", "b", Some("7"))
+            ]
+        );
+    }
+
+    #[test]
+    fn block_without_data_is_not_dispatched() {
+        assert_eq!(parse("event: ping\nid: 3\n\n"), vec![]);
+    }
+
+    #[test_case(&[b"data: a\r\n\r\n"] ; "crlf")]
+    #[test_case(&[b"data: a\r\r"] ; "cr")]
+    #[test_case(&[b"data: a\r", b"\n\r\n"] ; "crlf split across chunks")]
+    #[test_case(&[b"\xEF\xBB", b"\xBFdata: a\n\n"] ; "bom split across chunks")]
+    #[test_case(&[b"da", b"ta", b": a\n", b"\n"] ; "field split across chunks")]
+    fn line_endings_and_chunking(chunks: &[&[u8]]) {
+        assert_eq!(parse_chunks(chunks), vec![event("message", "a", None)]);
+    }
+
+    #[test]
+    fn retry_is_reported() {
+        let mut parser = Parser::default();
+        parser.feed(b"retry: 1500\ndata: a\n\nretry: soon\ndata: b\n\n");
+
+        assert_eq!(parser.events[0].retry(), Some(Duration::from_millis(1500)));
+        assert_eq!(parser.events[1].retry(), None);
+        assert_eq!(parser.reconnection_time, Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn id_with_null_is_ignored() {
+        assert_eq!(
+            parse("id: 1\n\nid: a\0b\ndata: x\n\n"),
+            vec![event("message", "x", Some("1"))]
+        );
+    }
+
+    #[test]
+    fn incomplete_event_is_discarded() {
+        let events = Events::new(&b"data: a\n\ndata: b\n"[..])
+            .collect::<io::Result<Vec<_>>>()
+            .unwrap();
+
+        assert_eq!(events, vec![event("message", "a", None)]);
+    }
+
+    #[test]
+    fn stream_yields_events() {
+        let events = block_on(EventStream::new(&b"data: a\n\ndata: b\n\n"[..]).collect::<Vec<_>>());
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].as_ref().unwrap().data(), "b");
+    }
+}
@@ -1,6 +1,23 @@
 //! Helpers for working with tasks and futures.
 
-use std::task::Waker;
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, VecDeque},
+    future::Future,
+    io,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, LazyLock, Mutex},
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Maximum number of threads used to run blocking functions.
+const MAX_BLOCKING_THREADS: usize = 16;
+
+/// How long a blocking thread waits for more work before exiting.
+const BLOCKING_KEEP_ALIVE: Duration = Duration::from_secs(10);
 
 /// Helper methods for working with wakers.
 pub(crate) trait WakerExt {
@@ -15,3 +32,305 @@ impl WakerExt for Waker {
         waker_fn::waker_fn(move || (f)(&inner))
     }
 }
+
+/// Create a future that completes once the given duration has elapsed.
+pub(crate) fn delay(duration: Duration) -> Delay {
+    Delay {
+        deadline: Instant::now() + duration,
+        state: None,
+    }
+}
+
+/// A runtime-agnostic timer future.
+///
+/// Since we do not depend on any particular async runtime, pending timers are
+/// serviced by a single background thread that is spawned the first time a
+/// timer is needed and shared by all clients.
+#[derive(Debug)]
+pub(crate) struct Delay {
+    deadline: Instant,
+    state: Option<Arc<Mutex<DelayState>>>,
+}
+
+#[derive(Debug, Default)]
+struct DelayState {
+    fired: bool,
+    waker: Option<Waker>,
+}
+
+impl Future for Delay {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(state) = self.state.as_ref() {
+            let mut state = state.lock().unwrap();
+
+            if state.fired {
+                return Poll::Ready(());
+            }
+
+            state.waker = Some(cx.waker().clone());
+
+            return Poll::Pending;
+        }
+
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+
+        let state = Arc::new(Mutex::new(DelayState {
+            fired: false,
+            waker: Some(cx.waker().clone()),
+        }));
+
+        TIMER_THREAD.register(self.deadline, state.clone());
+        self.state = Some(state);
+
+        Poll::Pending
+    }
+}
+
+static TIMER_THREAD: LazyLock<TimerThread> = LazyLock::new(TimerThread::spawn);
+
+/// Shared state of the background timer thread.
+struct TimerThread {
+    queue: Arc<(Mutex<BinaryHeap<TimerEntry>>, Condvar)>,
+}
+
+impl TimerThread {
+    fn spawn() -> Self {
+        let queue = Arc::new((Mutex::new(BinaryHeap::<TimerEntry>::new()), Condvar::new()));
+
+        thread::Builder::new()
+            .name(String::from("isahc-timer"))
+            .spawn({
+                let queue = queue.clone();
+
+                move || {
+                    let (entries, condvar) = &*queue;
+                    let mut entries = entries.lock().unwrap();
+
+                    loop {
+                        let now = Instant::now();
+
+                        while entries.peek().is_some_and(|entry| entry.deadline <= now) {
+                            let entry = entries.pop().unwrap();
+                            let mut state = entry.state.lock().unwrap();
+
+                            state.fired = true;
+
+                            if let Some(waker) = state.waker.take() {
+                                waker.wake();
+                            }
+                        }
+
+                        entries = match entries.peek() {
+                            Some(entry) => {
+                                let timeout = entry.deadline.saturating_duration_since(now);
+                                condvar.wait_timeout(entries, timeout).unwrap().0
+                            }
+                            None => condvar.wait(entries).unwrap(),
+                        };
+                    }
+                }
+            })
+            .expect("failed to spawn timer thread");
+
+        Self { queue }
+    }
+
+    fn register(&self, deadline: Instant, state: Arc<Mutex<DelayState>>) {
+        let (entries, condvar) = &*self.queue;
+
+        entries.lock().unwrap().push(TimerEntry { deadline, state });
+        condvar.notify_one();
+    }
+}
+
+/// Run a blocking function in the background, returning a future that
+/// resolves to its result.
+///
+/// Functions are run on a shared pool of at most [`MAX_BLOCKING_THREADS`]
+/// threads, which are spawned as needed and exit once they have been idle for
+/// a while. If every thread is busy, the function waits in a queue until one
+/// becomes available.
+pub(crate) fn spawn_blocking<T, F>(f: F) -> impl Future<Output = io::Result<T>> + Send + 'static
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let (sender, receiver) = async_channel::bounded(1);
+
+    let spawned = BLOCKING_POOL.execute(Box::new(move || {
+        let _ = sender.try_send(f());
+    }));
+
+    async move {
+        spawned?;
+
+        receiver
+            .recv()
+            .await
+            .map_err(|_| io::ErrorKind::Interrupted.into())
+    }
+}
+
+static BLOCKING_POOL: LazyLock<BlockingPool> = LazyLock::new(BlockingPool::default);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Shared state of the pool of threads running blocking functions.
+#[derive(Default)]
+struct BlockingPool {
+    state: Mutex<BlockingPoolState>,
+    condvar: Condvar,
+}
+
+#[derive(Default)]
+struct BlockingPoolState {
+    jobs: VecDeque<Job>,
+    threads: usize,
+    idle: usize,
+}
+
+impl BlockingPool {
+    fn execute(&'static self, job: Job) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+
+        state.jobs.push_back(job);
+
+        // Every idle thread will pick up one job, so only spawn a new thread
+        // if there are more jobs waiting than that.
+        if state.jobs.len() > state.idle && state.threads < MAX_BLOCKING_THREADS {
+            let spawned = thread::Builder::new()
+                .name(String::from("isahc-blocking"))
+                .spawn(move || self.run());
+
+            match spawned {
+                Ok(_) => state.threads += 1,
+
+                // The job will still be run by an existing thread, if any.
+                Err(e) if state.threads == 0 => {
+                    state.jobs.pop_back();
+                    return Err(e);
+                }
+                Err(e) => tracing::debug!("failed to spawn blocking thread: {}", e),
+            }
+        }
+
+        self.condvar.notify_one();
+
+        Ok(())
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                drop(state);
+
+                // A panicking job drops its result sender, which its future
+                // reports as an error, so the thread can carry on.
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+
+                state = self.state.lock().unwrap();
+                continue;
+            }
+
+            state.idle += 1;
+            let (guard, result) = self
+                .condvar
+                .wait_timeout(state, BLOCKING_KEEP_ALIVE)
+                .unwrap();
+            state = guard;
+            state.idle -= 1;
+
+            if result.timed_out() && state.jobs.is_empty() {
+                state.threads -= 1;
+                return;
+            }
+        }
+    }
+}
+
+/// A pending timer. Entries are ordered so that the earliest deadline is at
+/// the top of the heap.
+struct TimerEntry {
+    deadline: Instant,
+    state: Arc<Mutex<DelayState>>,
+}
+
+impl PartialEq for TimerEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+
+impl Eq for TimerEntry {}
+
+impl PartialOrd for TimerEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for TimerEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.deadline.cmp(&self.deadline)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_lite::future::block_on;
+
+    #[test]
+    fn delay_waits_at_least_duration() {
+        let start = Instant::now();
+
+        block_on(delay(Duration::from_millis(50)));
+
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[test]
+    fn zero_delay_completes_immediately() {
+        block_on(delay(Duration::ZERO));
+    }
+
+    #[test]
+    fn blocking_functions_run_on_bounded_threads() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static RUNNING: AtomicUsize = AtomicUsize::new(0);
+        static MAX_RUNNING: AtomicUsize = AtomicUsize::new(0);
+
+        let futures = (0..MAX_BLOCKING_THREADS * 2)
+            .map(|i| {
+                spawn_blocking(move || {
+                    let running = RUNNING.fetch_add(1, Ordering::SeqCst) + 1;
+                    MAX_RUNNING.fetch_max(running, Ordering::SeqCst);
+                    thread::sleep(Duration::from_millis(10));
+                    RUNNING.fetch_sub(1, Ordering::SeqCst);
+                    i
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for (i, future) in futures.into_iter().enumerate() {
+            assert_eq!(block_on(future).unwrap(), i);
+        }
+
+        assert!(MAX_RUNNING.load(Ordering::SeqCst) <= MAX_BLOCKING_THREADS);
+    }
+
+    #[test]
+    fn panicking_blocking_function_is_an_error() {
+        let error = block_on(spawn_blocking(|| panic!("oops"))).unwrap_err();
+
+        assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+        assert_eq!(block_on(spawn_blocking(|| 1)).unwrap(), 1);
+    }
+}
@@ -0,0 +1,129 @@
+//! Private temporary files, for passing data to curl options that only accept
+//! a file.
+
+use std::{
+    env,
+    fs::DirBuilder,
+    io,
+    path::{Path, PathBuf},
+};
+
+/// Number of random names to try before giving up.
+const ATTEMPTS: usize = 16;
+
+/// Create a new directory inside the system temporary directory that can only
+/// be accessed by the current user, with an unpredictable name starting with
+/// the given prefix.
+pub(crate) fn create_private_dir(prefix: &str) -> io::Result<PathBuf> {
+    let mut builder = DirBuilder::new();
+
+    #[cfg(unix)]
+    std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+    create_unique(prefix, "", |path| builder.create(path)).map(|(path, ())| path)
+}
+
+/// Create a new file inside the system temporary directory containing the
+/// given bytes, which can only be accessed by the current user, with an
+/// unpredictable name starting with the given prefix and ending with the given
+/// suffix.
+#[cfg(feature = "tls")]
+pub(crate) fn create_private_file(
+    prefix: &str,
+    suffix: &str,
+    contents: &[u8],
+) -> io::Result<PathBuf> {
+    use std::{
+        fs::{self, OpenOptions},
+        io::Write,
+    };
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    let (path, mut file) = create_unique(prefix, suffix, |path| options.open(path))?;
+
+    if let Err(e) = file.write_all(contents) {
+        drop(file);
+        let _ = fs::remove_file(&path);
+        return Err(e);
+    }
+
+    Ok(path)
+}
+
+/// Create a file system entry using a random name, retrying with a different
+/// name if one already exists.
+fn create_unique<T>(
+    prefix: &str,
+    suffix: &str,
+    create: impl Fn(&Path) -> io::Result<T>,
+) -> io::Result<(PathBuf, T)> {
+    let dir = env::temp_dir();
+
+    for _ in 0..ATTEMPTS {
+        let name = (0..16)
+            .map(|_| fastrand::alphanumeric())
+            .collect::<String>();
+        let path = dir.join(format!("{}-{}{}", prefix, name, suffix));
+
+        match create(&path) {
+            Ok(value) => return Ok((path, value)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        "too many temporary files exist",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    #[cfg(feature = "tls")]
+    fn private_file_is_created_with_contents() {
+        let path = create_private_file("isahc-test", ".txt", b"hello").unwrap();
+        let other = create_private_file("isahc-test", ".txt", b"").unwrap();
+
+        assert_ne!(path, other);
+        assert!(path.to_str().unwrap().ends_with(".txt"));
+        assert_eq!(fs::read(&path).unwrap(), b"hello");
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_file(path).unwrap();
+        fs::remove_file(other).unwrap();
+    }
+
+    #[test]
+    fn private_dir_is_created() {
+        let path = create_private_dir("isahc-test").unwrap();
+
+        assert!(path.is_dir());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        fs::remove_dir(path).unwrap();
+    }
+}
@@ -1,8 +1,32 @@
+use super::der::{SubjectAltName, TbsCertificate};
+use sha2::{Digest, Sha256};
+use std::{sync::OnceLock, time::SystemTime};
+
 /// An X.509 digital certificate.
+///
+/// # Inspecting certificates
+///
+/// A handful of read-only accessors are provided for fields that are useful
+/// for diagnostics, such as [`subject`](Certificate::subject) and
+/// [`not_after`](Certificate::not_after). The certificate is parsed the first
+/// time one of these is called, and each returns `None` if the certificate is
+/// malformed. If this object contains more than one certificate, these
+/// accessors describe the first one; use
+/// [`from_pem_bundle`](Certificate::from_pem_bundle) to get each certificate
+/// individually.
 #[derive(Clone, Debug)]
 pub struct Certificate {
     /// Curl prefers to work in the PEM format, so internally we do as well.
     pem: String,
+
+    /// Parsed fields of the first certificate, populated on demand.
+    parsed: OnceLock<Option<Parsed>>,
+}
+
+#[derive(Clone, Debug)]
+struct Parsed {
+    tbs: TbsCertificate,
+    sha256_fingerprint: [u8; 32],
 }
 
 impl Certificate {
@@ -31,10 +55,169 @@ impl Certificate {
     pub fn from_pem<B: AsRef<[u8]>>(pem: B) -> Self {
         Self {
             pem: String::from_utf8(pem.as_ref().to_vec()).unwrap(),
+            parsed: OnceLock::new(),
         }
     }
 
+    /// Split a bundle of PEM-encoded certificates into individual
+    /// certificates.
+    ///
+    /// Each `CERTIFICATE` block in the bundle is returned in the order it
+    /// appears. Any text between blocks, such as comments, is ignored. The
+    /// certificates are not validated here.
+    pub fn from_pem_bundle<B: AsRef<[u8]>>(pem: B) -> Vec<Self> {
+        let pem = String::from_utf8_lossy(pem.as_ref());
+
+        pem_blocks(&pem).map(Self::from_pem).collect()
+    }
+
+    /// Get the certificate in PEM format.
+    pub fn as_pem(&self) -> &str {
+        &self.pem
+    }
+
+    /// Get the certificate in DER format.
+    ///
+    /// Returns `None` if the certificate is malformed, or if it contains more
+    /// than one certificate.
+    pub fn to_der(&self) -> Option<Vec<u8>> {
+        match pem_rfc7468::decode_vec(self.pem.as_bytes()) {
+            Ok(("CERTIFICATE", der)) => Some(der),
+            _ => None,
+        }
+    }
+
+    /// Get the distinguished name of the subject of the certificate, such as
+    /// `C=US, O=Example, CN=example.org`.
+    pub fn subject(&self) -> Option<&str> {
+        self.parsed().map(|parsed| parsed.tbs.subject.as_str())
+    }
+
+    /// Get the distinguished name of the issuer of the certificate, in the
+    /// same format as [`subject`](Certificate::subject).
+    pub fn issuer(&self) -> Option<&str> {
+        self.parsed().map(|parsed| parsed.tbs.issuer.as_str())
+    }
+
+    /// Get the big-endian bytes of the serial number of the certificate.
+    pub fn serial_number(&self) -> Option<&[u8]> {
+        self.parsed()
+            .map(|parsed| parsed.tbs.serial_number.as_slice())
+    }
+
+    /// Get the time from which the certificate is valid.
+    pub fn not_before(&self) -> Option<SystemTime> {
+        self.parsed().map(|parsed| parsed.tbs.not_before)
+    }
+
+    /// Get the time after which the certificate is no longer valid.
+    pub fn not_after(&self) -> Option<SystemTime> {
+        self.parsed().map(|parsed| parsed.tbs.not_after)
+    }
+
+    /// Get the subject alternative names of the certificate, which are the
+    /// host names and addresses that the certificate is valid for.
+    ///
+    /// Returns an empty slice if the certificate has no subject alternative
+    /// name extension.
+    pub fn subject_alt_names(&self) -> Option<&[SubjectAltName]> {
+        self.parsed()
+            .map(|parsed| parsed.tbs.subject_alt_names.as_slice())
+    }
+
+    /// Get the SHA-256 fingerprint of the certificate, which is the hash of
+    /// its DER encoding.
+    pub fn sha256_fingerprint(&self) -> Option<[u8; 32]> {
+        self.parsed().map(|parsed| parsed.sha256_fingerprint)
+    }
+
+    fn parsed(&self) -> Option<&Parsed> {
+        self.parsed
+            .get_or_init(|| {
+                let block = pem_blocks(&self.pem).next()?;
+                let der = match pem_rfc7468::decode_vec(block.as_bytes()) {
+                    Ok(("CERTIFICATE", der)) => der,
+                    _ => return None,
+                };
+
+                Some(Parsed {
+                    tbs: TbsCertificate::parse(&der)?,
+                    sha256_fingerprint: Sha256::digest(&der).into(),
+                })
+            })
+            .as_ref()
+    }
+
     pub(crate) fn as_pem_bytes(&self) -> &[u8] {
         self.pem.as_bytes()
     }
 }
+
+/// Iterate over each PEM-encoded certificate in the given string.
+fn pem_blocks(pem: &str) -> impl Iterator<Item = &str> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+
+    let mut rest = pem;
+
+    std::iter::from_fn(move || {
+        let start = rest.find(BEGIN)?;
+        let end = start + rest[start..].find(END)? + END.len();
+        let block = &rest[start..end];
+
+        rest = &rest[end..];
+
+        Some(block)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn der_round_trip() {
+        let der = include_bytes!("../../tests/certs/isrgrootx1.der");
+        let cert = Certificate::from_der(der);
+
+        assert!(cert.as_pem().starts_with("-----BEGIN CERTIFICATE-----"));
+        assert_eq!(cert.to_der().unwrap(), der);
+    }
+
+    #[test]
+    fn inspect_isrg_root() {
+        let cert = Certificate::from_pem(include_str!("../../tests/certs/isrgrootx1.pem"));
+
+        assert_eq!(
+            cert.subject(),
+            Some("C=US, O=Internet Security Research Group, CN=ISRG Root X1")
+        );
+        assert_eq!(cert.issuer(), cert.subject());
+        assert_eq!(cert.serial_number().unwrap().len(), 16);
+        assert!(cert.not_before() < cert.not_after());
+        assert_eq!(cert.subject_alt_names(), Some(&[][..]));
+        assert_eq!(
+            cert.sha256_fingerprint().unwrap()[..4],
+            [0x96, 0xbc, 0xec, 0x06]
+        );
+    }
+
+    #[test]
+    fn malformed_certificate_has_no_fields() {
+        let cert = Certificate::from_pem("not a certificate");
+
+        assert_eq!(cert.subject(), None);
+        assert_eq!(cert.sha256_fingerprint(), None);
+    }
+
+    #[test]
+    fn split_bundle() {
+        let pem = include_str!("../../tests/certs/isrgrootx1.pem");
+        let bundle = format!("# First\n{pem}\n# Second\n{pem}");
+        let certs = Certificate::from_pem_bundle(bundle);
+
+        assert_eq!(certs.len(), 2);
+        assert_eq!(certs[0].to_der(), certs[1].to_der());
+        assert!(certs[1].to_der().is_some());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{
+    config::setopt::EasyHandle,
+    error::{Error, ErrorKind},
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+/// A source of certificate revocation lists (CRLs).
+#[derive(Clone, Debug)]
+pub(crate) enum Crl {
+    File(PathBuf),
+    Pem(Arc<PemCrl>),
+}
+
+/// CRLs held in memory.
+///
+/// Curl only accepts CRLs as a file, so the first time these are used they are
+/// written to a private temporary file, which is removed again once no longer
+/// in use.
+#[derive(Debug)]
+pub(crate) struct PemCrl {
+    pem: Vec<u8>,
+    file: OnceLock<Result<PathBuf, Arc<io::Error>>>,
+}
+
+impl Crl {
+    pub(crate) fn from_pem(pem: Vec<u8>) -> Self {
+        Self::Pem(Arc::new(PemCrl {
+            pem,
+            file: OnceLock::new(),
+        }))
+    }
+
+    /// Keep the temporary file used for in-memory CRLs alive for as long as
+    /// the given handle.
+    pub(crate) fn retain(&self, easy: &mut EasyHandle) {
+        if let Self::Pem(crl) = self {
+            easy.get_mut().retained.push(crl.clone());
+        }
+    }
+
+    /// Get the path of a file containing the CRLs.
+    pub(crate) fn path(&self) -> Result<&Path, Error> {
+        match self {
+            Self::File(path) => Ok(path),
+            Self::Pem(crl) => crl
+                .file
+                .get_or_init(|| crl.write().map_err(Arc::new))
+                .as_deref()
+                .map_err(|e| Error::new(ErrorKind::InvalidTlsConfiguration, Arc::clone(e))),
+        }
+    }
+}
+
+impl PemCrl {
+    fn write(&self) -> io::Result<PathBuf> {
+        crate::temp::create_private_file("isahc-crl", ".pem", &self.pem)
+    }
+}
+
+impl Drop for PemCrl {
+    fn drop(&mut self) {
+        if let Some(Ok(path)) = self.file.get() {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pem_is_written_to_temporary_file() {
+        let crl = Crl::from_pem(b"-----BEGIN X509 CRL-----".to_vec());
+        let path = crl.path().unwrap().to_path_buf();
+
+        assert_eq!(fs::read(&path).unwrap(), b"-----BEGIN X509 CRL-----");
+        let clone = crl.clone();
+        assert_eq!(clone.path().unwrap(), path);
+
+        drop(crl);
+        assert!(path.exists());
+
+        drop(clone);
+
+        assert!(!path.exists());
+    }
+}
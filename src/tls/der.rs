@@ -0,0 +1,447 @@
+//! A minimal DER parser, sufficient for reading the fields of an X.509
+//! certificate that are useful for diagnostics.
+
+use crate::calendar::days_from_civil;
+use std::{
+    fmt::Write,
+    net::IpAddr,
+    time::{Duration, SystemTime},
+};
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTF8_STRING: u8 = 0x0c;
+const TAG_PRINTABLE_STRING: u8 = 0x13;
+const TAG_T61_STRING: u8 = 0x14;
+const TAG_IA5_STRING: u8 = 0x16;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_UNIVERSAL_STRING: u8 = 0x1c;
+const TAG_BMP_STRING: u8 = 0x1e;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_SET: u8 = 0x31;
+
+/// Object identifier of the subject alternative name extension (2.5.29.17).
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+
+/// A name that a certificate is valid for, in addition to its subject.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SubjectAltName {
+    /// A DNS host name, which may contain a wildcard.
+    Dns(String),
+
+    /// An IP address.
+    Ip(IpAddr),
+
+    /// An email address.
+    Email(String),
+
+    /// A URI.
+    Uri(String),
+}
+
+/// The fields of a parsed X.509 certificate.
+#[derive(Clone, Debug)]
+pub(crate) struct TbsCertificate {
+    pub(crate) serial_number: Vec<u8>,
+    pub(crate) issuer: String,
+    pub(crate) not_before: SystemTime,
+    pub(crate) not_after: SystemTime,
+    pub(crate) subject: String,
+    pub(crate) subject_alt_names: Vec<SubjectAltName>,
+}
+
+impl TbsCertificate {
+    /// Parse a DER-encoded X.509 certificate, returning `None` if it is
+    /// malformed.
+    pub(crate) fn parse(der: &[u8]) -> Option<Self> {
+        let mut certificate = Reader::new(der).read(TAG_SEQUENCE)?;
+        let mut tbs = certificate.read(TAG_SEQUENCE)?;
+
+        // Version is optional and we do not need it.
+        tbs.read_optional(0xa0)?;
+
+        let mut serial_number = tbs.read(TAG_INTEGER)?.rest();
+
+        // Strip the sign byte of positive serial numbers.
+        if serial_number.len() > 1 && serial_number[0] == 0 {
+            serial_number = &serial_number[1..];
+        }
+
+        // Signature algorithm.
+        tbs.read(TAG_SEQUENCE)?;
+
+        let issuer = parse_name(tbs.read(TAG_SEQUENCE)?)?;
+
+        let mut validity = tbs.read(TAG_SEQUENCE)?;
+        let not_before = parse_time(&mut validity)?;
+        let not_after = parse_time(&mut validity)?;
+
+        let subject = parse_name(tbs.read(TAG_SEQUENCE)?)?;
+
+        // Subject public key info, followed by the optional issuer and subject
+        // unique IDs.
+        tbs.read(TAG_SEQUENCE)?;
+        tbs.read_optional(0x81)?;
+        tbs.read_optional(0x82)?;
+
+        let mut subject_alt_names = Vec::new();
+
+        if let Some(mut extensions) = tbs.read_optional(0xa3)? {
+            let mut extensions = extensions.read(TAG_SEQUENCE)?;
+
+            while !extensions.is_empty() {
+                let mut extension = extensions.read(TAG_SEQUENCE)?;
+                let id = extension.read(TAG_OID)?.rest();
+                extension.read_optional(TAG_BOOLEAN)?;
+                let value = extension.read(TAG_OCTET_STRING)?.rest();
+
+                if id == OID_SUBJECT_ALT_NAME {
+                    subject_alt_names = parse_general_names(value)?;
+                }
+            }
+        }
+
+        Some(Self {
+            serial_number: serial_number.to_vec(),
+            issuer,
+            not_before,
+            not_after,
+            subject,
+            subject_alt_names,
+        })
+    }
+}
+
+/// Reads DER-encoded values from a buffer.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self(data)
+    }
+
+    fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Get all the remaining bytes.
+    fn rest(self) -> &'a [u8] {
+        self.0
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.0.first().copied()
+    }
+
+    /// Read the next value of any type, returning its tag and contents.
+    fn read_any(&mut self) -> Option<(u8, Reader<'a>)> {
+        let (&tag, rest) = self.0.split_first()?;
+        let (&first, mut rest) = rest.split_first()?;
+
+        let len = if first < 0x80 {
+            usize::from(first)
+        } else {
+            // Long form, where the low bits are the number of length bytes.
+            let count = usize::from(first & 0x7f);
+
+            if count == 0 || count > 4 || rest.len() < count {
+                return None;
+            }
+
+            let (bytes, tail) = rest.split_at(count);
+            rest = tail;
+            bytes
+                .iter()
+                .fold(0, |len, &byte| (len << 8) | usize::from(byte))
+        };
+
+        if rest.len() < len {
+            return None;
+        }
+
+        let (contents, rest) = rest.split_at(len);
+        self.0 = rest;
+
+        Some((tag, Reader(contents)))
+    }
+
+    /// Read the next value, which must have the given tag.
+    fn read(&mut self, tag: u8) -> Option<Reader<'a>> {
+        match self.read_any()? {
+            (actual, contents) if actual == tag => Some(contents),
+            _ => None,
+        }
+    }
+
+    /// Read the next value if it has the given tag.
+    fn read_optional(&mut self, tag: u8) -> Option<Option<Reader<'a>>> {
+        if self.peek_tag() == Some(tag) {
+            self.read(tag).map(Some)
+        } else {
+            Some(None)
+        }
+    }
+}
+
+/// Format a distinguished name as a comma-separated list of attributes, in the
+/// order they appear in the certificate.
+fn parse_name(mut name: Reader<'_>) -> Option<String> {
+    let mut formatted = String::new();
+
+    while !name.is_empty() {
+        let mut rdn = name.read(TAG_SET)?;
+
+        while !rdn.is_empty() {
+            let mut attribute = rdn.read(TAG_SEQUENCE)?;
+            let oid = attribute.read(TAG_OID)?.rest();
+            let (tag, value) = attribute.read_any()?;
+
+            if !formatted.is_empty() {
+                formatted.push_str(", ");
+            }
+
+            match attribute_name(oid) {
+                Some(name) => formatted.push_str(name),
+                None => formatted.push_str(&format_oid(oid)?),
+            }
+
+            formatted.push('=');
+            formatted.push_str(&parse_string(tag, value.rest())?);
+        }
+    }
+
+    Some(formatted)
+}
+
+fn attribute_name(oid: &[u8]) -> Option<&'static str> {
+    Some(match oid {
+        [0x55, 0x04, 0x03] => "CN",
+        [0x55, 0x04, 0x05] => "serialNumber",
+        [0x55, 0x04, 0x06] => "C",
+        [0x55, 0x04, 0x07] => "L",
+        [0x55, 0x04, 0x08] => "ST",
+        [0x55, 0x04, 0x09] => "street",
+        [0x55, 0x04, 0x0a] => "O",
+        [0x55, 0x04, 0x0b] => "OU",
+        [0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01] => "emailAddress",
+        [0x09, 0x92, 0x26, 0x89, 0x93, 0xf2, 0x2c, 0x64, 0x01, 0x19] => "DC",
+        _ => return None,
+    })
+}
+
+/// Format an object identifier in dotted decimal notation.
+fn format_oid(oid: &[u8]) -> Option<String> {
+    let mut arcs = Vec::new();
+    let mut arc = 0u64;
+
+    for &byte in oid {
+        arc = arc.checked_mul(128)? | u64::from(byte & 0x7f);
+
+        if byte & 0x80 == 0 {
+            arcs.push(arc);
+            arc = 0;
+        }
+    }
+
+    let (&first, rest) = arcs.split_first()?;
+    let mut formatted = match first {
+        0..40 => format!("0.{first}"),
+        40..80 => format!("1.{}", first - 40),
+        _ => format!("2.{}", first - 80),
+    };
+
+    for arc in rest {
+        write!(formatted, ".{arc}").ok()?;
+    }
+
+    Some(formatted)
+}
+
+fn parse_string(tag: u8, value: &[u8]) -> Option<String> {
+    match tag {
+        TAG_UTF8_STRING | TAG_PRINTABLE_STRING | TAG_IA5_STRING => {
+            String::from_utf8(value.to_vec()).ok()
+        }
+        // Treated as Latin-1, which is how it is used in practice.
+        TAG_T61_STRING => Some(value.iter().map(|&byte| char::from(byte)).collect()),
+        TAG_BMP_STRING => {
+            let units = value
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]));
+
+            char::decode_utf16(units).collect::<Result<_, _>>().ok()
+        }
+        TAG_UNIVERSAL_STRING => value
+            .chunks_exact(4)
+            .map(|quad| char::from_u32(u32::from_be_bytes([quad[0], quad[1], quad[2], quad[3]])))
+            .collect(),
+        _ => None,
+    }
+}
+
+fn parse_general_names(value: &[u8]) -> Option<Vec<SubjectAltName>> {
+    let mut names = Reader::new(value).read(TAG_SEQUENCE)?;
+    let mut parsed = Vec::new();
+
+    while !names.is_empty() {
+        let (tag, value) = names.read_any()?;
+        let value = value.rest();
+
+        parsed.push(match tag {
+            0x81 => SubjectAltName::Email(String::from_utf8(value.to_vec()).ok()?),
+            0x82 => SubjectAltName::Dns(String::from_utf8(value.to_vec()).ok()?),
+            0x86 => SubjectAltName::Uri(String::from_utf8(value.to_vec()).ok()?),
+            0x87 => SubjectAltName::Ip(match value.len() {
+                4 => IpAddr::from(<[u8; 4]>::try_from(value).ok()?),
+                16 => IpAddr::from(<[u8; 16]>::try_from(value).ok()?),
+                _ => return None,
+            }),
+            // Other kinds of names are rarely used by servers.
+            _ => continue,
+        });
+    }
+
+    Some(parsed)
+}
+
+fn parse_time(reader: &mut Reader<'_>) -> Option<SystemTime> {
+    let (tag, value) = reader.read_any()?;
+    let value = std::str::from_utf8(value.rest()).ok()?;
+
+    let year_len = match tag {
+        TAG_UTC_TIME => 2,
+        TAG_GENERALIZED_TIME => 4,
+        _ => return None,
+    };
+
+    // DER requires times to be in UTC with seconds included.
+    if value.len() != year_len + 11
+        || !value.ends_with('Z')
+        || !value[..year_len + 10].bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let (year, rest) = value.split_at(year_len);
+    let year = match year.parse::<i64>().ok()? {
+        year if tag == TAG_GENERALIZED_TIME => year,
+        year if year < 50 => 2000 + year,
+        year => 1900 + year,
+    };
+
+    let field = |i: usize| rest[i..i + 2].parse::<u32>().ok();
+    let (month, day) = (field(0)?, field(2)?);
+    let (hour, minute, second) = (field(4)?, field(6)?, field(8)?);
+
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let seconds =
+        days_from_civil(year, month, day)? * 86400 + i64::from(hour * 3600 + minute * 60 + second);
+    let offset = Duration::from_secs(seconds.unsigned_abs());
+
+    // Times before the epoch are valid, if unusual.
+    if seconds < 0 {
+        SystemTime::UNIX_EPOCH.checked_sub(offset)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_add(offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_isrg_root() {
+        let cert =
+            TbsCertificate::parse(include_bytes!("../../tests/certs/isrgrootx1.der")).unwrap();
+
+        assert_eq!(
+            cert.subject,
+            "C=US, O=Internet Security Research Group, CN=ISRG Root X1"
+        );
+        assert_eq!(cert.issuer, cert.subject);
+        assert_eq!(
+            cert.serial_number,
+            [
+                0x82, 0x10, 0xcf, 0xb0, 0xd2, 0x40, 0xe3, 0x59, 0x44, 0x63, 0xe0, 0xbb, 0x63, 0x82,
+                0x8b, 0x00
+            ]
+        );
+        assert_eq!(
+            httpdate::fmt_http_date(cert.not_before),
+            "Thu, 04 Jun 2015 11:04:38 GMT"
+        );
+        assert_eq!(
+            httpdate::fmt_http_date(cert.not_after),
+            "Mon, 04 Jun 2035 11:04:38 GMT"
+        );
+        assert!(cert.subject_alt_names.is_empty());
+    }
+
+    #[test]
+    fn parse_general_names_of_each_kind() {
+        let value = [
+            0x30, 0x18, // SEQUENCE
+            0x82, 0x0b, b'e', b'x', b'a', b'm', b'p', b'l', b'e', b'.', b'o', b'r',
+            b'g', // DNS
+            0x87, 0x04, 127, 0, 0, 1, // IP
+            0x81, 0x03, b'a', b'@', b'b', // email
+        ];
+
+        assert_eq!(
+            parse_general_names(&value).unwrap(),
+            vec![
+                SubjectAltName::Dns("example.org".into()),
+                SubjectAltName::Ip([127, 0, 0, 1].into()),
+                SubjectAltName::Email("a@b".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_oids_are_formatted_as_dotted_decimal() {
+        assert_eq!(format_oid(&[0x55, 0x1d, 0x11]).unwrap(), "2.5.29.17");
+        assert_eq!(
+            format_oid(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x01]).unwrap(),
+            "1.2.840.113549.1.9.1"
+        );
+    }
+
+    #[test]
+    fn times_before_the_epoch_are_parsed() {
+        fn generalized_time(value: &str) -> Option<SystemTime> {
+            let mut der = vec![TAG_GENERALIZED_TIME, value.len() as u8];
+            der.extend_from_slice(value.as_bytes());
+
+            parse_time(&mut Reader::new(&der))
+        }
+
+        assert_eq!(
+            generalized_time("19691231235959Z"),
+            Some(SystemTime::UNIX_EPOCH - Duration::from_secs(1))
+        );
+        assert_eq!(
+            generalized_time("00000101000000Z"),
+            Some(SystemTime::UNIX_EPOCH - Duration::from_secs(62167219200))
+        );
+        assert_eq!(
+            generalized_time("20000229120000Z"),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(951825600))
+        );
+        assert_eq!(generalized_time("+0000101000000Z"), None);
+    }
+
+    #[test]
+    fn truncated_input_is_rejected() {
+        let der = include_bytes!("../../tests/certs/isrgrootx1.der");
+
+        assert!(TbsCertificate::parse(&der[..der.len() / 2]).is_none());
+    }
+}
@@ -6,7 +6,11 @@ use curl_sys::{
     CURLOPT_PROXY_SSLCERT_BLOB, CURLOPT_PROXY_SSLKEY_BLOB, CURLOPT_SSLCERT_BLOB,
     CURLOPT_SSLKEY_BLOB,
 };
-use std::{path::PathBuf, sync::Arc};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 /// A cryptographic identity used to authenticate the client with a server.
 ///
@@ -148,6 +152,30 @@ impl Identity {
             password,
         }
     }
+
+    /// Get the paths of any files that this identity and its private key are
+    /// loaded from.
+    pub(crate) fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.data
+            .path()
+            .into_iter()
+            .chain(self.private_key.as_ref().and_then(|key| key.data.path()))
+    }
+
+    /// Read any files that this identity and its private key are loaded from
+    /// into memory.
+    pub(crate) fn load(&self) -> io::Result<Self> {
+        Ok(Self {
+            format: self.format,
+            data: self.data.load()?,
+            private_key: self
+                .private_key
+                .as_ref()
+                .map(PrivateKey::load)
+                .transpose()?,
+            password: self.password.clone(),
+        })
+    }
 }
 
 impl SetOpt for Identity {
@@ -196,6 +224,32 @@ impl SetOptProxy for Identity {
     }
 }
 
+/// A function that picks the identity to authenticate a request with, shared
+/// by all requests sent using a client.
+#[derive(Clone)]
+pub(crate) struct IdentityResolver(Arc<ResolveFn>);
+
+type ResolveFn = dyn Fn(&http::Uri) -> Option<Identity> + Send + Sync;
+
+impl IdentityResolver {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn(&http::Uri) -> Option<Identity> + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn resolve(&self, uri: &http::Uri) -> Option<Identity> {
+        (self.0)(uri)
+    }
+}
+
+impl fmt::Debug for IdentityResolver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("IdentityResolver")
+    }
+}
+
 /// A private key file.
 #[derive(Clone, Debug)]
 pub struct PrivateKey {
@@ -277,6 +331,14 @@ impl PrivateKey {
             password: password.into(),
         }
     }
+
+    fn load(&self) -> io::Result<Self> {
+        Ok(Self {
+            format: self.format,
+            data: self.data.load()?,
+            password: self.password.clone(),
+        })
+    }
 }
 
 impl SetOpt for PrivateKey {
@@ -325,6 +387,23 @@ enum PathOrBlob {
     Blob(Arc<[u8]>),
 }
 
+impl PathOrBlob {
+    fn path(&self) -> Option<&Path> {
+        match self {
+            Self::Path(path) => Some(path),
+            Self::Blob(_) => None,
+        }
+    }
+
+    /// Read the file into memory, if this is a path.
+    fn load(&self) -> io::Result<Self> {
+        match self {
+            Self::Path(path) => Ok(Self::Blob(fs::read(path)?.into())),
+            Self::Blob(bytes) => Ok(Self::Blob(bytes.clone())),
+        }
+    }
+}
+
 /// Possible formats for certificates supported by curl.
 #[derive(Clone, Copy, Debug)]
 enum CertFormat {
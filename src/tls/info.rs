@@ -0,0 +1,322 @@
+#![allow(unsafe_code)]
+
+use super::{Certificate, ProtocolVersion};
+use curl_sys::{CURL, CURLE_OK, CURLINFO, CURLINFO_CERTINFO, CURLINFO_SLIST, curl_certinfo};
+use std::{
+    ffi::CStr,
+    os::raw::{c_int, c_void},
+    ptr,
+};
+
+// Not yet exposed by curl-sys.
+const CURLINFO_TLS_SSL_PTR: CURLINFO = CURLINFO_SLIST + 45;
+#[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+const CURLSSLBACKEND_OPENSSL: c_int = 1;
+#[cfg(feature = "rustls-tls")]
+const CURLSSLBACKEND_RUSTLS: c_int = 14;
+
+#[repr(C)]
+struct curl_tlssessioninfo {
+    backend: c_int,
+    internals: *mut c_void,
+}
+
+/// Information about the secure connection a response was received over.
+///
+/// Capturing this information is disabled by default. To enable it, use
+/// [`Configurable::tls_info`](crate::config::Configurable::tls_info), and then
+/// it can be accessed using
+/// [`ResponseExt::tls_info`](crate::ResponseExt::tls_info).
+///
+/// # Backend support
+///
+/// The negotiated protocol version, cipher suite, and ALPN protocol are only
+/// available when using the Rustls or OpenSSL TLS backends. With other
+/// backends, only the certificate chain is available, and only for responses
+/// received over a newly established connection.
+#[derive(Clone, Debug, Default)]
+pub struct TlsInfo {
+    peer_certificates: Vec<Certificate>,
+    protocol_version: Option<ProtocolVersion>,
+    cipher_suite: Option<String>,
+    alpn_protocol: Option<Vec<u8>>,
+}
+
+impl TlsInfo {
+    /// Get the certificate chain presented by the server, starting with the
+    /// server's own certificate.
+    pub fn peer_certificates(&self) -> &[Certificate] {
+        &self.peer_certificates
+    }
+
+    /// Get the negotiated TLS protocol version, if known.
+    pub fn protocol_version(&self) -> Option<ProtocolVersion> {
+        self.protocol_version
+    }
+
+    /// Get the name of the negotiated cipher suite, if known.
+    ///
+    /// The naming convention used depends on the TLS backend.
+    pub fn cipher_suite(&self) -> Option<&str> {
+        self.cipher_suite.as_deref()
+    }
+
+    /// Get the protocol negotiated using Application-Layer Protocol Negotiation
+    /// (ALPN), such as `h2` or `http/1.1`, if any.
+    pub fn alpn_protocol(&self) -> Option<&[u8]> {
+        self.alpn_protocol.as_deref()
+    }
+
+    /// Capture information about the secure connection currently used by the
+    /// given curl handle, if any.
+    pub(crate) fn capture(handle: *mut CURL) -> Option<Self> {
+        if handle.is_null() {
+            return None;
+        }
+
+        let mut session = ptr::null::<curl_tlssessioninfo>();
+
+        unsafe {
+            if curl_sys::curl_easy_getinfo(handle, CURLINFO_TLS_SSL_PTR, &mut session) != CURLE_OK {
+                return None;
+            }
+        }
+
+        let session = unsafe { session.as_ref() }?;
+
+        // Plain-text connections do not have a session.
+        if session.internals.is_null() {
+            return None;
+        }
+
+        let info = match session.backend {
+            #[cfg(feature = "rustls-tls")]
+            CURLSSLBACKEND_RUSTLS => unsafe { rustls::capture(session.internals) },
+            #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+            CURLSSLBACKEND_OPENSSL => unsafe { openssl::capture(session.internals) },
+            _ => Self {
+                peer_certificates: unsafe { certinfo_chain(handle) },
+                ..Default::default()
+            },
+        };
+
+        Some(info)
+    }
+}
+
+/// Read the certificate chain collected by curl when the `CURLOPT_CERTINFO`
+/// option is enabled.
+unsafe fn certinfo_chain(handle: *mut CURL) -> Vec<Certificate> {
+    let mut certinfo = ptr::null::<curl_certinfo>();
+    let mut chain = Vec::new();
+
+    unsafe {
+        if curl_sys::curl_easy_getinfo(handle, CURLINFO_CERTINFO, &mut certinfo) != CURLE_OK {
+            return chain;
+        }
+
+        let Some(certinfo) = certinfo.as_ref() else {
+            return chain;
+        };
+
+        for i in 0..certinfo.num_of_certs.max(0) as usize {
+            let mut item = *certinfo.certinfo.add(i);
+
+            // Each certificate is a list of `name:value` fields, of which we
+            // only care about the PEM-encoded certificate itself.
+            while let Some(slist) = item.as_ref() {
+                if let Some(pem) = CStr::from_ptr(slist.data)
+                    .to_str()
+                    .ok()
+                    .and_then(|field| field.strip_prefix("Cert:"))
+                {
+                    chain.push(Certificate::from_pem(pem));
+                    break;
+                }
+
+                item = slist.next;
+            }
+        }
+    }
+
+    chain
+}
+
+impl ProtocolVersion {
+    /// Get the protocol version for the given version number as it appears on
+    /// the wire.
+    fn from_wire(version: u16) -> Option<Self> {
+        match version {
+            0x0002 => Some(Self::Sslv2),
+            0x0300 => Some(Self::Sslv3),
+            0x0301 => Some(Self::Tlsv10),
+            0x0302 => Some(Self::Tlsv11),
+            0x0303 => Some(Self::Tlsv12),
+            0x0304 => Some(Self::Tlsv13),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "rustls-tls")]
+mod rustls {
+    use super::*;
+    use std::{os::raw::c_char, slice};
+
+    #[repr(C)]
+    struct rustls_str {
+        data: *const c_char,
+        len: usize,
+    }
+
+    const RUSTLS_RESULT_OK: u32 = 7000;
+
+    // Provided by rustls-ffi, which curl is linked with.
+    unsafe extern "C" {
+        fn rustls_connection_get_protocol_version(conn: *const c_void) -> u16;
+        fn rustls_connection_get_negotiated_ciphersuite_name(conn: *const c_void) -> rustls_str;
+        fn rustls_connection_get_alpn_protocol(
+            conn: *const c_void,
+            protocol_out: *mut *const u8,
+            protocol_out_len: *mut usize,
+        );
+        fn rustls_connection_get_peer_certificate(conn: *const c_void, i: usize) -> *const c_void;
+        fn rustls_certificate_get_der(
+            cert: *const c_void,
+            out_der_data: *mut *const u8,
+            out_der_len: *mut usize,
+        ) -> u32;
+    }
+
+    pub(super) unsafe fn capture(conn: *const c_void) -> TlsInfo {
+        unsafe {
+            let mut info = TlsInfo {
+                protocol_version: ProtocolVersion::from_wire(
+                    rustls_connection_get_protocol_version(conn),
+                ),
+                ..Default::default()
+            };
+
+            let name = rustls_connection_get_negotiated_ciphersuite_name(conn);
+            if !name.data.is_null() && name.len > 0 {
+                let name = slice::from_raw_parts(name.data.cast::<u8>(), name.len);
+                info.cipher_suite = Some(String::from_utf8_lossy(name).into_owned());
+            }
+
+            let mut protocol = ptr::null();
+            let mut protocol_len = 0;
+            rustls_connection_get_alpn_protocol(conn, &mut protocol, &mut protocol_len);
+            if !protocol.is_null() {
+                info.alpn_protocol = Some(slice::from_raw_parts(protocol, protocol_len).to_vec());
+            }
+
+            for i in 0.. {
+                let cert = rustls_connection_get_peer_certificate(conn, i);
+                if cert.is_null() {
+                    break;
+                }
+
+                let mut der = ptr::null();
+                let mut der_len = 0;
+                if rustls_certificate_get_der(cert, &mut der, &mut der_len) == RUSTLS_RESULT_OK {
+                    info.peer_certificates
+                        .push(Certificate::from_der(slice::from_raw_parts(der, der_len)));
+                }
+            }
+
+            info
+        }
+    }
+}
+
+#[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+mod openssl {
+    use super::*;
+    use std::{
+        os::raw::{c_char, c_uint},
+        slice,
+    };
+
+    // Provided by OpenSSL, which curl is linked with.
+    unsafe extern "C" {
+        fn SSL_version(ssl: *const c_void) -> c_int;
+        fn SSL_get_current_cipher(ssl: *const c_void) -> *const c_void;
+        fn SSL_CIPHER_get_name(cipher: *const c_void) -> *const c_char;
+        fn SSL_get0_alpn_selected(ssl: *const c_void, data: *mut *const u8, len: *mut c_uint);
+        fn SSL_get_peer_cert_chain(ssl: *const c_void) -> *mut c_void;
+        fn OPENSSL_sk_num(stack: *const c_void) -> c_int;
+        fn OPENSSL_sk_value(stack: *const c_void, i: c_int) -> *mut c_void;
+        fn i2d_X509(x509: *mut c_void, out: *mut *mut u8) -> c_int;
+        fn CRYPTO_free(ptr: *mut c_void, file: *const c_char, line: c_int);
+    }
+
+    pub(super) unsafe fn capture(ssl: *const c_void) -> TlsInfo {
+        unsafe {
+            let mut info = TlsInfo {
+                protocol_version: u16::try_from(SSL_version(ssl))
+                    .ok()
+                    .and_then(ProtocolVersion::from_wire),
+                ..Default::default()
+            };
+
+            let cipher = SSL_get_current_cipher(ssl);
+            if !cipher.is_null() {
+                let name = SSL_CIPHER_get_name(cipher);
+                if !name.is_null() {
+                    info.cipher_suite = Some(CStr::from_ptr(name).to_string_lossy().into_owned());
+                }
+            }
+
+            let mut protocol = ptr::null();
+            let mut protocol_len = 0;
+            SSL_get0_alpn_selected(ssl, &mut protocol, &mut protocol_len);
+            if !protocol.is_null() && protocol_len > 0 {
+                info.alpn_protocol =
+                    Some(slice::from_raw_parts(protocol, protocol_len as usize).to_vec());
+            }
+
+            // On the client side, the chain includes the server certificate.
+            let chain = SSL_get_peer_cert_chain(ssl);
+            if !chain.is_null() {
+                for i in 0..OPENSSL_sk_num(chain) {
+                    let mut der = ptr::null_mut();
+                    let der_len = i2d_X509(OPENSSL_sk_value(chain, i), &mut der);
+
+                    if der_len > 0 && !der.is_null() {
+                        info.peer_certificates
+                            .push(Certificate::from_der(slice::from_raw_parts(
+                                der,
+                                der_len as usize,
+                            )));
+                        CRYPTO_free(der.cast(), c"isahc".as_ptr(), 0);
+                    }
+                }
+            }
+
+            info
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wire_versions() {
+        assert_eq!(
+            ProtocolVersion::from_wire(0x0303),
+            Some(ProtocolVersion::Tlsv12)
+        );
+        assert_eq!(
+            ProtocolVersion::from_wire(0x0304),
+            Some(ProtocolVersion::Tlsv13)
+        );
+        assert_eq!(ProtocolVersion::from_wire(0x7f1c), None);
+    }
+
+    #[test]
+    fn no_info_without_handle() {
+        assert!(TlsInfo::capture(ptr::null_mut()).is_none());
+    }
+}
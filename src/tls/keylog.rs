@@ -0,0 +1,354 @@
+//! Logging of TLS session secrets, for debugging.
+//!
+//! Curl only supports writing session secrets to the file named by the
+//! `SSLKEYLOGFILE` environment variable, which it opens once per process. To
+//! support other destinations with OpenSSL, secrets are captured from the TLS
+//! backend once for the entire process, and then forwarded to every [`KeyLog`]
+//! currently in use.
+
+#![allow(unsafe_code)]
+
+use super::{CURLE_NOT_BUILT_IN, TlsEngine};
+use crate::{
+    config::setopt::EasyHandle,
+    error::{Error, ErrorKind},
+};
+use std::{
+    env, fmt,
+    fs::File,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, Weak},
+};
+
+/// The environment variable conventionally used to name a key log file.
+const ENV_VAR: &str = "SSLKEYLOGFILE";
+
+/// Key logs that are currently in use by at least one TLS configuration.
+static ACTIVE: Mutex<Vec<Weak<Sink>>> = Mutex::new(Vec::new());
+
+/// A destination for logging the secrets of TLS sessions.
+///
+/// **This is a debugging facility.** Anyone with access to the logged secrets
+/// can decrypt all traffic sent over the logged connections. Key logs are
+/// written in the [NSS key log
+/// format](https://developer.mozilla.org/en-US/docs/Mozilla/Projects/NSS/Key_Log_Format),
+/// which is understood by tools such as Wireshark.
+///
+/// Use a key log with
+/// [`TlsConfigBuilder::key_log`](super::TlsConfigBuilder::key_log).
+///
+/// # Scope
+///
+/// TLS backends only allow capturing secrets for the entire process. As a
+/// result, while any key log is in use, secrets for _every_ TLS connection
+/// made by the process are written to every key log in use, including
+/// connections made by other clients or by requests that did not enable key
+/// logging. Key logs stop receiving secrets once every configuration using
+/// them has been dropped.
+///
+/// # Backend support
+///
+/// All kinds of key logs are supported with the OpenSSL backend.
+///
+/// The rustls backend, which Isahc uses by default, only supports the
+/// `SSLKEYLOGFILE` environment variable. Curl reads the variable once when it
+/// is initialized, which happens before the first client or request is
+/// created, and writes secrets to the named file by itself. Only a key log
+/// created using [`KeyLog::from_env`], or a [file](KeyLog::file) with the same
+/// path, can be used with rustls, and only if the variable was set before curl
+/// was initialized, such as by setting it before starting the process. Other
+/// files and [callbacks](KeyLog::callback) are not supported with rustls.
+///
+/// Using an unsupported key log will cause requests to fail with a
+/// [`TlsEngine`](crate::error::ErrorKind::TlsEngine) error.
+///
+/// # Examples
+///
+/// Log secrets to the file named by `SSLKEYLOGFILE`, if set, which is
+/// supported by every backend:
+///
+/// ```
+/// use isahc::{prelude::*, HttpClient, tls::{KeyLog, TlsConfig}};
+///
+/// let client = HttpClient::builder()
+///     .tls_config(TlsConfig::builder()
+///         .key_log(KeyLog::from_env())
+///         .build())
+///     .build()?;
+/// # Ok::<(), isahc::Error>(())
+/// ```
+#[derive(Clone)]
+pub struct KeyLog(Arc<Sink>);
+
+enum Sink {
+    Disabled,
+    File {
+        path: PathBuf,
+        file: Mutex<Option<File>>,
+    },
+    Callback(Box<dyn Fn(&str) + Send + Sync>),
+}
+
+impl KeyLog {
+    /// Log secrets to the file named by the `SSLKEYLOGFILE` environment
+    /// variable, following the convention used by browsers and many other
+    /// TLS clients.
+    ///
+    /// The variable is read when this method is called. If it is not set,
+    /// nothing is logged. This is the only kind of key log supported by the
+    /// rustls backend.
+    pub fn from_env() -> Self {
+        match env::var_os(ENV_VAR) {
+            Some(path) if !path.is_empty() => Self::file(path),
+            _ => Self(Arc::new(Sink::Disabled)),
+        }
+    }
+
+    /// Log secrets to the given file.
+    ///
+    /// The file is created if it does not exist, and appended to otherwise. It
+    /// is opened the first time a secret is logged.
+    ///
+    /// With the rustls backend this is only supported if the path is the one
+    /// named by the `SSLKEYLOGFILE` environment variable, as described under
+    /// [Backend support](#backend-support).
+    pub fn file(path: impl Into<PathBuf>) -> Self {
+        Self(Arc::new(Sink::File {
+            path: path.into(),
+            file: Mutex::new(None),
+        }))
+    }
+
+    /// Pass secrets to the given callback, one line at a time, without a
+    /// trailing newline.
+    ///
+    /// The callback may be invoked from any thread, and should return quickly
+    /// to avoid delaying connections.
+    ///
+    /// This is only supported by the OpenSSL backend, and not by the rustls
+    /// backend that Isahc uses by default.
+    pub fn callback<F>(callback: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        Self(Arc::new(Sink::Callback(Box::new(callback))))
+    }
+
+    /// Start forwarding secrets to this key log, if not already, and configure
+    /// the given handle to capture them.
+    pub(crate) fn enable(&self, easy: &mut EasyHandle) -> Result<(), Error> {
+        if let Sink::Disabled = *self.0 {
+            return Ok(());
+        }
+
+        #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+        if TlsEngine::OpenSsl.is_available() {
+            self.activate();
+            easy.get_mut().log_tls_keys = true;
+            return Ok(());
+        }
+
+        // Curl writes secrets to the file named by the environment variable
+        // by itself, which is the only destination supported with rustls.
+        #[cfg(feature = "rustls-tls")]
+        if TlsEngine::Rustls.is_available() {
+            if self.is_env_file() {
+                return Ok(());
+            }
+
+            return Err(Error::with_context(
+                ErrorKind::TlsEngine,
+                Some(format!(
+                    "TLS secrets can only be logged to the file named by {} with rustls",
+                    ENV_VAR
+                )),
+                curl::Error::new(CURLE_NOT_BUILT_IN),
+            ));
+        }
+
+        let _ = easy;
+
+        Err(Error::with_context(
+            ErrorKind::TlsEngine,
+            Some(String::from(
+                "TLS key logging is not supported by the TLS engine",
+            )),
+            curl::Error::new(CURLE_NOT_BUILT_IN),
+        ))
+    }
+
+    /// Check whether this key log writes to the file named by the environment
+    /// variable, which curl writes to by itself.
+    #[allow(dead_code)]
+    fn is_env_file(&self) -> bool {
+        match (&*self.0, env::var_os(ENV_VAR)) {
+            (Sink::File { path, .. }, Some(env_path)) => path.as_path() == Path::new(&env_path),
+            _ => false,
+        }
+    }
+
+    /// Capture secrets for all connections using the given OpenSSL `SSL_CTX`.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be a valid `SSL_CTX` provided by curl.
+    #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+    pub(crate) unsafe fn attach(ssl_ctx: *mut std::os::raw::c_void) {
+        unsafe {
+            openssl::attach(ssl_ctx);
+        }
+    }
+
+    #[allow(dead_code)]
+    fn activate(&self) {
+        let mut active = ACTIVE.lock().unwrap();
+
+        active.retain(|sink| sink.strong_count() > 0);
+
+        if !active
+            .iter()
+            .any(|sink| sink.as_ptr() == Arc::as_ptr(&self.0))
+        {
+            active.push(Arc::downgrade(&self.0));
+        }
+    }
+}
+
+impl fmt::Debug for KeyLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &*self.0 {
+            Sink::Disabled => f.write_str("KeyLog::Disabled"),
+            Sink::File { path, .. } => f.debug_tuple("KeyLog::File").field(path).finish(),
+            Sink::Callback(_) => f.write_str("KeyLog::Callback"),
+        }
+    }
+}
+
+impl Sink {
+    fn log(&self, line: &str) {
+        match self {
+            Self::Disabled => {}
+            Self::File { path, file } => {
+                let mut file = file.lock().unwrap();
+
+                if file.is_none() {
+                    match File::options().create(true).append(true).open(path) {
+                        Ok(f) => *file = Some(f),
+                        Err(e) => {
+                            tracing::warn!(?path, "failed to open TLS key log: {}", e);
+                            return;
+                        }
+                    }
+                }
+
+                if let Some(f) = file.as_mut() {
+                    if let Err(e) = writeln!(f, "{}", line) {
+                        tracing::warn!(?path, "failed to write TLS key log: {}", e);
+                    }
+                }
+            }
+            Self::Callback(callback) => callback(line),
+        }
+    }
+}
+
+/// Forward a line of secrets to every active key log.
+#[allow(dead_code)]
+fn dispatch(line: &str) {
+    let line = line.trim_end();
+
+    if line.is_empty() {
+        return;
+    }
+
+    // Avoid holding the lock while writing.
+    let sinks = ACTIVE
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect::<Vec<_>>();
+
+    for sink in sinks {
+        sink.log(line);
+    }
+}
+
+/// Capturing secrets from OpenSSL, which allows registering a callback on each
+/// `SSL_CTX` created by curl.
+#[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+mod openssl {
+    use std::{
+        ffi::CStr,
+        os::raw::{c_char, c_void},
+    };
+
+    type KeylogCallback = extern "C" fn(ssl: *const c_void, line: *const c_char);
+
+    // Provided by OpenSSL, which curl is linked with.
+    unsafe extern "C" {
+        fn SSL_CTX_set_keylog_callback(ctx: *mut c_void, cb: KeylogCallback);
+    }
+
+    extern "C" fn callback(_ssl: *const c_void, line: *const c_char) {
+        if !line.is_null() {
+            super::dispatch(&unsafe { CStr::from_ptr(line) }.to_string_lossy());
+        }
+    }
+
+    /// Log secrets for all connections using the given `SSL_CTX`.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be a valid `SSL_CTX` provided by curl.
+    pub(super) unsafe fn attach(ctx: *mut c_void) {
+        unsafe {
+            SSL_CTX_set_keylog_callback(ctx, callback);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_callbacks_receive_lines() {
+        let lines = Arc::new(Mutex::new(Vec::new()));
+        let key_log = KeyLog::callback({
+            let lines = lines.clone();
+            move |line| lines.lock().unwrap().push(line.to_owned())
+        });
+
+        dispatch("CLIENT_RANDOM 00 11\n");
+        assert!(lines.lock().unwrap().is_empty());
+
+        key_log.activate();
+        key_log.clone().activate();
+        dispatch("CLIENT_RANDOM 00 11\n");
+        dispatch("\n");
+
+        assert_eq!(*lines.lock().unwrap(), ["CLIENT_RANDOM 00 11"]);
+
+        drop(key_log);
+        dispatch("CLIENT_RANDOM 22 33");
+
+        assert_eq!(lines.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn file_is_appended_to() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "existing\n").unwrap();
+
+        let key_log = KeyLog::file(file.path());
+        key_log.0.log("CLIENT_RANDOM 00 11");
+        key_log.0.log("CLIENT_RANDOM 22 33");
+
+        assert_eq!(
+            std::fs::read_to_string(file.path()).unwrap(),
+            "existing\nCLIENT_RANDOM 00 11\nCLIENT_RANDOM 22 33\n"
+        );
+    }
+}
@@ -30,6 +30,11 @@
 //! - `rustls-tls-webpki-roots`: Use [rustls] along with the [webpki-root-certs]
 //!   crate to provide a static set of root certificates from the Mozilla CA
 //!   bundle.
+//! - `trust-native-roots`: Provides integration with the
+//!   [rustls-native-certs] crate, which allows the operating system's root
+//!   certificates to be combined with other certificates in a trust store.
+//!   This feature by itself does not change the default trust provider, and
+//!   can be used with any TLS backend.
 //! - `trust-webpki-roots`: Provides integration with the [webpki-root-certs] as
 //!   a trust store which can be used at runtime. This feature by itself does
 //!   not change the default trust provider, and can be used with any TLS
@@ -78,30 +83,50 @@
 //! [Secure Transport]:
 //!     https://developer.apple.com/documentation/security/secure_transport
 
+use self::{crl::Crl, reload::Source};
 use crate::{
     config::setopt::{EasyHandle, SetOpt, SetOptError, SetOptProxy},
     error::{Error, ErrorKind},
     info::curl_info,
 };
 use curl::easy::{SslOpt, SslVersion};
-use std::fmt;
+use curl_sys::{CURLOPTTYPE_LONG, CURLOPTTYPE_OBJECTPOINT, CURLcode, CURLoption};
+use std::{ffi::CString, fmt, os::raw::c_long, path::PathBuf, sync::Arc};
 
 mod cert;
+mod crl;
+mod der;
 mod identity;
+mod info;
+mod keylog;
+mod pin;
+mod reload;
 mod trust;
 
 pub use self::{
     cert::Certificate,
+    der::SubjectAltName,
     identity::{Identity, PrivateKey},
-    trust::TrustStore,
+    info::TlsInfo,
+    keylog::KeyLog,
+    pin::PublicKeyPin,
+    reload::Reloadable,
+    trust::{TrustStore, TrustStoreBuilder},
 };
 
+pub(crate) use self::identity::IdentityResolver;
+
 #[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
 compile_error!("`tls` feature is enabled, but no TLS backend was selected");
 
 #[cfg(all(feature = "native-tls", feature = "rustls-tls"))]
 compile_error!("multiple TLS engines cannot be enabled at the same time");
 
+// Not yet exposed by curl-sys.
+const CURLE_NOT_BUILT_IN: CURLcode = 4;
+const CURLOPT_PROXY_PINNEDPUBLICKEY: CURLoption = CURLOPTTYPE_OBJECTPOINT + 263;
+const CURLOPT_SSL_VERIFYSTATUS: CURLoption = CURLOPTTYPE_LONG + 232;
+
 // SPNEGO is only supported with native TLS.
 #[cfg(all(not(feature = "native-tls"), feature = "spnego"))]
 compile_error!("spnego must be used with native-tls");
@@ -110,9 +135,13 @@ compile_error!("spnego must be used with native-tls");
 #[derive(Debug, Default)]
 #[must_use = "builders have no effect if unused"]
 pub struct TlsConfigBuilder {
-    trust_store: TrustStore,
+    trust_store: Source<TrustStore>,
     issuer_cert: Option<Certificate>,
-    identity: Option<Identity>,
+    identity: Option<Source<Identity>>,
+    pinned_public_keys: Vec<PublicKeyPin>,
+    crl: Option<Crl>,
+    require_ocsp_stapling: bool,
+    key_log: Option<KeyLog>,
     ciphers: Option<String>,
     min_version: Option<ProtocolVersion>,
     max_version: Option<ProtocolVersion>,
@@ -155,7 +184,19 @@ impl TlsConfigBuilder {
     ///     .build();
     /// ```
     pub fn trust_store(mut self, store: TrustStore) -> Self {
-        self.trust_store = store;
+        self.trust_store = Source::Fixed(store);
+        self
+    }
+
+    /// Set the certificate store containing trusted root certificates using a
+    /// handle that allows the store to be replaced later.
+    ///
+    /// This is the same as [`TlsConfigBuilder::trust_store`], except that
+    /// whenever the store in the handle is replaced, requests sent afterwards
+    /// use the new store, without having to rebuild the HTTP client. See
+    /// [`Reloadable`] for details.
+    pub fn trust_store_source(mut self, store: Reloadable<TrustStore>) -> Self {
+        self.trust_store = Source::Reloadable(store);
         self
     }
 
@@ -229,7 +270,197 @@ impl TlsConfigBuilder {
     ///     .build();
     /// ```
     pub fn identity(mut self, identity: Identity) -> Self {
-        self.identity = Some(identity);
+        self.identity = Some(Source::Fixed(identity));
+        self
+    }
+
+    /// Add a custom client certificate to use for client authentication using
+    /// a handle that allows the certificate to be replaced later.
+    ///
+    /// This is the same as [`TlsConfigBuilder::identity`], except that whenever
+    /// the identity in the handle is replaced, requests sent afterwards use the
+    /// new identity, without having to rebuild the HTTP client. This is useful
+    /// for client certificates that are rotated frequently. See [`Reloadable`]
+    /// for details.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::tls::{Identity, Reloadable, TlsConfig};
+    ///
+    /// let identity = Reloadable::new(Identity::from_pem("<<PEM DATA>>", None));
+    ///
+    /// let config = TlsConfig::builder()
+    ///     .identity_source(identity.clone())
+    ///     .build();
+    ///
+    /// // Later, once the certificate has been rotated...
+    /// identity.set(Identity::from_pem("<<NEW PEM DATA>>", None));
+    /// ```
+    pub fn identity_source(mut self, identity: Reloadable<Identity>) -> Self {
+        self.identity = Some(Source::Reloadable(identity));
+        self
+    }
+
+    /// Only trust servers presenting one of the given public keys, also known
+    /// as *certificate pinning*.
+    ///
+    /// When pins are set, the public key of the server's certificate must match
+    /// one of them in addition to the certificate being valid, otherwise the
+    /// connection is terminated with a
+    /// [`PinnedPublicKeyMismatch`](crate::error::ErrorKind::PinnedPublicKeyMismatch)
+    /// error. This protects against certificates wrongly issued for the server
+    /// by a trusted certificate authority.
+    ///
+    /// Pinning the key of a single certificate means that connections will fail
+    /// as soon as the server switches to a new key, so it is a good idea to
+    /// also include a pin for a backup key. Passing an empty list disables
+    /// pinning, which is the default.
+    ///
+    /// When used as a proxy TLS configuration, the pins apply to the proxy
+    /// instead.
+    ///
+    /// # Backend support
+    ///
+    /// Pinning is **not supported** when using the Rustls TLS backend, and
+    /// requests using pins will fail with a
+    /// [`TlsEngine`](crate::error::ErrorKind::TlsEngine) error instead of
+    /// connecting without checking them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::tls::{PublicKeyPin, TlsConfig};
+    ///
+    /// let config = TlsConfig::builder()
+    ///     .pinned_public_keys([
+    ///         // Current key
+    ///         PublicKeyPin::from_sha256_base64("sha256//YhKJKSzoTt2b5FP18fvpHo7fJYqQCjAa3HWY3tvRMwE="),
+    ///         // Backup key
+    ///         PublicKeyPin::from_public_key_pem(
+    ///             "-----BEGIN PUBLIC KEY-----\n\
+    ///             MCowBQYDK2VwAyEAGb9ECWmEzf6FQbrBZ9w7lshQhqowtrbLDFw4rXAxZuE=\n\
+    ///             -----END PUBLIC KEY-----\n",
+    ///         ),
+    ///     ])
+    ///     .build();
+    /// ```
+    pub fn pinned_public_keys<I>(mut self, pins: I) -> Self
+    where
+        I: IntoIterator<Item = PublicKeyPin>,
+    {
+        self.pinned_public_keys = pins.into_iter().collect();
+        self
+    }
+
+    /// Check server certificates against the certificate revocation lists
+    /// (CRLs) in the given PEM-encoded file.
+    ///
+    /// When set, the server certificate and any intermediate certificates in
+    /// its chain are each checked against the CRL of their issuer, which must
+    /// be present in the file. The root certificate of the chain, which is
+    /// trusted directly, is not checked. If any of the checked certificates
+    /// has been revoked then the connection is terminated with a
+    /// [`CertificateRevoked`](crate::error::ErrorKind::CertificateRevoked)
+    /// error, and if the file has no CRL for one of them then the connection
+    /// is terminated with a
+    /// [`BadServerCertificate`](crate::error::ErrorKind::BadServerCertificate)
+    /// error, since its revocation status cannot be determined. The file is
+    /// read again whenever a new connection is established.
+    ///
+    /// If the file does not exist or cannot be parsed, an error will be
+    /// returned when attempting to send a request using the offending file.
+    ///
+    /// By default, no CRLs are used.
+    ///
+    /// # Backend support
+    ///
+    /// CRLs are supported by the OpenSSL and Rustls TLS backends, which both
+    /// check the chain as described above. Other backends may ignore this
+    /// option, or fail with a
+    /// [`TlsEngine`](crate::error::ErrorKind::TlsEngine) error.
+    ///
+    /// With Rustls, CRLs can only be checked when the trust store provides
+    /// the trusted certificates itself, such as one loaded from a PEM file or
+    /// built using [`TrustStore::builder`]. When [`TrustStore::native`] uses
+    /// the platform verifier, which is the default unless the
+    /// `SSL_CERT_FILE` environment variable is set, sending a request fails
+    /// with an
+    /// [`InvalidTlsConfiguration`](crate::error::ErrorKind::InvalidTlsConfiguration)
+    /// error instead.
+    pub fn crl_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.crl = Some(Crl::File(path.into()));
+        self
+    }
+
+    /// Check server certificates against the given PEM-encoded certificate
+    /// revocation lists (CRLs) in memory.
+    ///
+    /// This is the same as [`TlsConfigBuilder::crl_file`], except that the
+    /// CRLs are stored in memory. Since TLS backends only accept CRLs from a
+    /// file, they are written to a private temporary file the first time they
+    /// are used, which is removed once the configuration is dropped.
+    pub fn crl_pem(mut self, pem: impl AsRef<[u8]>) -> Self {
+        self.crl = Some(Crl::from_pem(pem.as_ref().to_vec()));
+        self
+    }
+
+    /// Require the server to provide a valid stapled OCSP response for its
+    /// certificate, also known as *OCSP stapling*.
+    ///
+    /// When enabled, the server must include a response from the certificate
+    /// authority's Online Certificate Status Protocol (OCSP) responder during
+    /// the handshake, stating that its certificate has not been revoked. If
+    /// the certificate has been revoked, the connection is terminated with a
+    /// [`CertificateRevoked`](crate::error::ErrorKind::CertificateRevoked)
+    /// error. If the response is missing or invalid, the connection is
+    /// terminated with a
+    /// [`BadServerCertificate`](crate::error::ErrorKind::BadServerCertificate)
+    /// error.
+    ///
+    /// By default, this is disabled.
+    ///
+    /// # Backend support
+    ///
+    /// OCSP stapling is **not supported** when using the Rustls, Schannel, or
+    /// Secure Transport TLS backends, and requests requiring it will fail with
+    /// a [`TlsEngine`](crate::error::ErrorKind::TlsEngine) error instead of
+    /// connecting without checking it. It is also not supported for
+    /// connections to a proxy, and requests using a proxy TLS configuration
+    /// requiring it will fail with an
+    /// [`InvalidTlsConfiguration`](crate::error::ErrorKind::InvalidTlsConfiguration)
+    /// error.
+    pub fn require_ocsp_stapling(mut self, require: bool) -> Self {
+        self.require_ocsp_stapling = require;
+        self
+    }
+
+    /// Log the secrets of TLS sessions to the given [`KeyLog`], allowing tools
+    /// such as Wireshark to decrypt captured traffic.
+    ///
+    /// **This is a debugging facility and should never be enabled in
+    /// production.** Secrets are captured for every connection made by the
+    /// process while a key log is in use; see [`KeyLog`] for details.
+    ///
+    /// The rustls backend, which Isahc uses by default, only supports logging
+    /// to the file named by the `SSLKEYLOGFILE` environment variable using
+    /// [`KeyLog::from_env`], and only if the variable was set before the first
+    /// client or request was created. [Files](KeyLog::file) at other paths and
+    /// [callbacks](KeyLog::callback) require the OpenSSL backend, and fail
+    /// with a [`TlsEngine`](crate::error::ErrorKind::TlsEngine) error
+    /// otherwise.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::tls::{KeyLog, TlsConfig};
+    ///
+    /// let config = TlsConfig::builder()
+    ///     .key_log(KeyLog::from_env())
+    ///     .build();
+    /// ```
+    pub fn key_log(mut self, key_log: KeyLog) -> Self {
+        self.key_log = Some(key_log);
         self
     }
 
@@ -376,12 +607,15 @@ impl TlsConfigBuilder {
             curl_flags: {
                 let mut options = SslOpt::new();
                 options.no_revoke(self.danger_accept_revoked_certs);
-                self.trust_store.configure_ssl_options(&mut options);
                 options
             },
             trust_store: self.trust_store,
             issuer_cert: self.issuer_cert,
             identity: self.identity,
+            pinned_public_keys: self.pinned_public_keys.into(),
+            crl: self.crl,
+            require_ocsp_stapling: self.require_ocsp_stapling,
+            key_log: self.key_log,
             ciphers: self.ciphers,
             min_version: self
                 .min_version
@@ -400,9 +634,13 @@ impl TlsConfigBuilder {
 /// Configuration for making SSL/TLS connections.
 #[derive(Clone, Debug)]
 pub struct TlsConfig {
-    trust_store: TrustStore,
+    trust_store: Source<TrustStore>,
     issuer_cert: Option<Certificate>,
-    identity: Option<Identity>,
+    identity: Option<Source<Identity>>,
+    pinned_public_keys: Arc<[PublicKeyPin]>,
+    crl: Option<Crl>,
+    require_ocsp_stapling: bool,
+    key_log: Option<KeyLog>,
 
     /// List of ciphers to use, in a string format compatible with curl.
     ciphers: Option<String>,
@@ -442,6 +680,56 @@ impl TlsConfig {
     }
 }
 
+impl TlsConfig {
+    /// Check whether an identity to authenticate with the server is set.
+    pub(crate) fn has_identity(&self) -> bool {
+        self.identity.is_some()
+    }
+
+    /// Replace the identity used to authenticate with the server.
+    pub(crate) fn set_identity(&mut self, identity: Identity) {
+        self.identity = Some(Source::Fixed(identity));
+    }
+
+    /// Get the CRLs to check certificates against, if any, failing if they
+    /// cannot be used with the given trust store.
+    fn crl(&self, trust_store: &TrustStore) -> Result<Option<&Crl>, Error> {
+        match self.crl.as_ref() {
+            // The platform verifier used by rustls for the native store does
+            // not support CRLs, so curl would not check them.
+            Some(_) if TlsEngine::Rustls.is_available() && trust_store.is_native_ca() => {
+                Err(Error::with_context(
+                    ErrorKind::InvalidTlsConfiguration,
+                    Some(String::from(
+                        "CRLs cannot be used with the native trust store when using rustls, \
+                         use a trust store loaded from a PEM file or built from certificates",
+                    )),
+                    curl::Error::new(CURLE_NOT_BUILT_IN),
+                ))
+            }
+            crl => Ok(crl),
+        }
+    }
+
+    /// Get the curl SSL options to use with the given trust store.
+    fn ssl_options(&self, trust_store: &TrustStore) -> SslOpt {
+        let mut options = self.curl_flags.clone();
+        trust_store.configure_ssl_options(&mut options);
+        options
+    }
+
+    /// Get the pinned public keys in the format expected by curl, if any.
+    fn pinned_public_keys_string(&self) -> Result<Option<String>, Error> {
+        if self.pinned_public_keys.is_empty() {
+            return Ok(None);
+        }
+
+        PublicKeyPin::join(&self.pinned_public_keys)
+            .map(Some)
+            .map_err(|e| Error::new(ErrorKind::InvalidTlsConfiguration, e))
+    }
+}
+
 impl Default for TlsConfig {
     fn default() -> Self {
         Self::new()
@@ -454,7 +742,9 @@ impl SetOpt for TlsConfig {
             easy.ssl_cipher_list(ciphers)?;
         }
 
-        easy.ssl_options(&self.curl_flags)?;
+        let trust_store = self.trust_store.get();
+
+        easy.ssl_options(&self.ssl_options(&trust_store))?;
         easy.ssl_verify_peer(!self.danger_accept_invalid_certs)?;
         easy.ssl_verify_host(!self.danger_accept_invalid_hosts)?;
 
@@ -485,14 +775,39 @@ impl SetOpt for TlsConfig {
             self.max_version.unwrap_or(SslVersion::Default),
         )?;
 
-        self.trust_store.set_opt(easy)?;
+        trust_store.set_opt(easy)?;
 
         if let Some(cert) = self.issuer_cert.as_ref() {
             easy.issuer_cert_blob(cert.as_pem_bytes())?;
         }
 
         if let Some(identity) = self.identity.as_ref() {
-            identity.set_opt(easy)?;
+            identity.get().set_opt(easy)?;
+        }
+
+        if let Some(pins) = self.pinned_public_keys_string()? {
+            easy.pinned_public_key(&pins)
+                .map_err(|e| not_supported(e, "public key pinning"))?;
+        }
+
+        if let Some(crl) = self.crl(&trust_store)? {
+            easy.crlfile(crl.path()?)?;
+            crl.retain(easy);
+        }
+
+        if self.require_ocsp_stapling {
+            // The safe wrapper does not support this option.
+            let code = unsafe {
+                curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_SSL_VERIFYSTATUS, 1 as c_long)
+            };
+
+            if code != curl_sys::CURLE_OK {
+                return Err(not_supported(curl::Error::new(code), "OCSP stapling"));
+            }
+        }
+
+        if let Some(key_log) = self.key_log.as_ref() {
+            key_log.enable(easy)?;
         }
 
         Ok(())
@@ -505,7 +820,9 @@ impl SetOptProxy for TlsConfig {
             easy.proxy_ssl_cipher_list(ciphers)?;
         }
 
-        easy.proxy_ssl_options(&self.curl_flags)?;
+        let trust_store = self.trust_store.get();
+
+        easy.proxy_ssl_options(&self.ssl_options(&trust_store))?;
         easy.proxy_ssl_verify_peer(self.danger_accept_invalid_certs)?;
         easy.proxy_ssl_verify_host(self.danger_accept_invalid_hosts)?;
 
@@ -514,14 +831,47 @@ impl SetOptProxy for TlsConfig {
             self.max_version.unwrap_or(SslVersion::Default),
         )?;
 
-        self.trust_store.set_opt_proxy(easy)?;
+        trust_store.set_opt_proxy(easy)?;
 
         if let Some(cert) = self.issuer_cert.as_ref() {
             easy.proxy_issuer_cert_blob(cert.as_pem_bytes())?;
         }
 
         if let Some(identity) = self.identity.as_ref() {
-            identity.set_opt_proxy(easy)?;
+            identity.get().set_opt_proxy(easy)?;
+        }
+
+        if let Some(pins) = self.pinned_public_keys_string()? {
+            let pins = CString::new(pins).unwrap();
+
+            // The safe wrapper does not support this option.
+            let code = unsafe {
+                curl_sys::curl_easy_setopt(easy.raw(), CURLOPT_PROXY_PINNEDPUBLICKEY, pins.as_ptr())
+            };
+
+            if code != curl_sys::CURLE_OK {
+                return Err(not_supported(curl::Error::new(code), "public key pinning"));
+            }
+        }
+
+        if let Some(crl) = self.crl(&trust_store)? {
+            easy.proxy_crlfile(crl.path()?)?;
+            crl.retain(easy);
+        }
+
+        if self.require_ocsp_stapling {
+            return Err(Error::with_context(
+                ErrorKind::InvalidTlsConfiguration,
+                Some(String::from(
+                    "OCSP stapling is not supported for proxy connections",
+                )),
+                curl::Error::new(CURLE_NOT_BUILT_IN),
+            )
+            .into());
+        }
+
+        if let Some(key_log) = self.key_log.as_ref() {
+            key_log.enable(easy)?;
         }
 
         Ok(())
@@ -576,6 +926,8 @@ impl ProtocolVersion {
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 enum TlsEngine {
+    #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+    OpenSsl,
     Rustls,
     Schannel,
     SecureTransport,
@@ -585,6 +937,8 @@ impl TlsEngine {
     fn is_available(&self) -> bool {
         if let Some(version) = curl_info().ssl_version() {
             match self {
+                #[cfg(all(feature = "native-tls", unix, not(target_vendor = "apple")))]
+                TlsEngine::OpenSsl => version.contains("OpenSSL/"),
                 TlsEngine::Rustls => version.contains("rustls/"),
                 TlsEngine::Schannel => version.contains("Schannel"),
                 TlsEngine::SecureTransport => version.contains("SecureTransport"),
@@ -595,6 +949,21 @@ impl TlsEngine {
     }
 }
 
+/// Convert an error setting an option into a more helpful error if the TLS
+/// engine does not support the given feature.
+fn not_supported(error: curl::Error, feature: &str) -> SetOptError {
+    if error.code() == CURLE_NOT_BUILT_IN {
+        Error::with_context(
+            ErrorKind::TlsEngine,
+            Some(format!("{} is not supported by the TLS engine", feature)),
+            error,
+        )
+        .into()
+    } else {
+        error.into()
+    }
+}
+
 /// A highly specific error type that is returned when a user attempts to set a
 /// maximum TLS version that is known to not be supported by the configured TLS
 /// engine.
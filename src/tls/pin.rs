@@ -0,0 +1,185 @@
+use base64ct::{Base64, Encoding};
+use sha2::{Digest, Sha256};
+use std::fmt;
+
+/// A pinned public key, used to restrict which servers are trusted to a known
+/// set of keys.
+///
+/// A pin is the SHA-256 hash of a DER-encoded SubjectPublicKeyInfo (SPKI)
+/// structure, which is the same format used by HTTP Public Key Pinning and by
+/// curl's `--pinnedpubkey` option. It can be created from an existing hash, or
+/// computed from a public key.
+///
+/// Pins are not validated here. If a pin is malformed, an error will be
+/// returned when attempting to send a request using the offending pin.
+///
+/// See [`TlsConfigBuilder::pinned_public_keys`](super::TlsConfigBuilder::pinned_public_keys)
+/// for how pins are used.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PublicKeyPin(Result<[u8; 32], InvalidPinError>);
+
+impl PublicKeyPin {
+    /// Create a pin from the raw SHA-256 hash of a public key.
+    pub const fn from_sha256(hash: [u8; 32]) -> Self {
+        Self(Ok(hash))
+    }
+
+    /// Create a pin from the base64-encoded SHA-256 hash of a public key.
+    ///
+    /// The hash may optionally be prefixed with `sha256//`, in which case the
+    /// format is the same as expected by curl's `--pinnedpubkey` option.
+    ///
+    /// # Examples
+    ///
+    /// A hash in this format can be generated from a server's certificate
+    /// using OpenSSL:
+    ///
+    /// ```sh
+    /// openssl x509 -in cert.pem -pubkey -noout \
+    ///     | openssl pkey -pubin -outform der \
+    ///     | openssl dgst -sha256 -binary \
+    ///     | openssl enc -base64
+    /// ```
+    ///
+    /// ```
+    /// use isahc::tls::PublicKeyPin;
+    ///
+    /// let pin = PublicKeyPin::from_sha256_base64("sha256//YhKJKSzoTt2b5FP18fvpHo7fJYqQCjAa3HWY3tvRMwE=");
+    /// ```
+    pub fn from_sha256_base64(hash: impl AsRef<str>) -> Self {
+        let hash = hash.as_ref();
+        let hash = hash.strip_prefix("sha256//").unwrap_or(hash);
+        let mut buf = [0; 32];
+
+        Self(
+            match Base64::decode(hash, &mut buf).map(|decoded| decoded.len()) {
+                Ok(32) => Ok(buf),
+                _ => Err(InvalidPinError("invalid base64-encoded SHA-256 hash")),
+            },
+        )
+    }
+
+    /// Compute a pin for a DER-encoded public key in SubjectPublicKeyInfo
+    /// format.
+    pub fn from_public_key_der(der: impl AsRef<[u8]>) -> Self {
+        Self::from_sha256(Sha256::digest(der.as_ref()).into())
+    }
+
+    /// Compute a pin for a PEM-encoded public key, which must be labeled
+    /// `PUBLIC KEY`.
+    pub fn from_public_key_pem(pem: impl AsRef<[u8]>) -> Self {
+        match pem_rfc7468::decode_vec(pem.as_ref()) {
+            Ok(("PUBLIC KEY", der)) => Self::from_public_key_der(der),
+            Ok(_) => Self(Err(InvalidPinError("PEM data is not a public key"))),
+            Err(_) => Self(Err(InvalidPinError("invalid PEM-encoded public key"))),
+        }
+    }
+
+    /// Get the pin in the format expected by curl.
+    fn to_curl_string(&self) -> Result<String, InvalidPinError> {
+        self.0
+            .as_ref()
+            .map(|hash| format!("sha256//{}", Base64::encode_string(hash)))
+            .map_err(Clone::clone)
+    }
+
+    /// Join the given pins into a single string in the format expected by
+    /// curl.
+    pub(crate) fn join(pins: &[Self]) -> Result<String, InvalidPinError> {
+        let pins = pins
+            .iter()
+            .map(Self::to_curl_string)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(pins.join(";"))
+    }
+}
+
+impl From<[u8; 32]> for PublicKeyPin {
+    fn from(hash: [u8; 32]) -> Self {
+        Self::from_sha256(hash)
+    }
+}
+
+/// Error returned when attempting to use a malformed public key pin.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) struct InvalidPinError(&'static str);
+
+impl std::error::Error for InvalidPinError {}
+
+impl fmt::Display for InvalidPinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid public key pin: {}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An arbitrary Ed25519 public key.
+    const PUBLIC_KEY_PEM: &str = "-----BEGIN PUBLIC KEY-----
+MCowBQYDK2VwAyEAGb9ECWmEzf6FQbrBZ9w7lshQhqowtrbLDFw4rXAxZuE=
+-----END PUBLIC KEY-----
+";
+
+    #[test]
+    fn base64_pin_round_trips() {
+        let pin = PublicKeyPin::from_sha256_base64(
+            "sha256//YhKJKSzoTt2b5FP18fvpHo7fJYqQCjAa3HWY3tvRMwE=",
+        );
+
+        assert_eq!(
+            PublicKeyPin::join(&[pin]).unwrap(),
+            "sha256//YhKJKSzoTt2b5FP18fvpHo7fJYqQCjAa3HWY3tvRMwE="
+        );
+    }
+
+    #[test]
+    fn prefix_is_optional() {
+        assert_eq!(
+            PublicKeyPin::from_sha256_base64("YhKJKSzoTt2b5FP18fvpHo7fJYqQCjAa3HWY3tvRMwE="),
+            PublicKeyPin::from_sha256_base64(
+                "sha256//YhKJKSzoTt2b5FP18fvpHo7fJYqQCjAa3HWY3tvRMwE="
+            ),
+        );
+    }
+
+    #[test]
+    fn wrong_length_hash_is_invalid() {
+        let pin = PublicKeyPin::from_sha256_base64("sha256//AAAA");
+
+        assert!(PublicKeyPin::join(&[pin]).is_err());
+    }
+
+    #[test]
+    fn pem_and_der_keys_produce_same_pin() {
+        let (_, der) = pem_rfc7468::decode_vec(PUBLIC_KEY_PEM.as_bytes()).unwrap();
+
+        assert_eq!(
+            PublicKeyPin::from_public_key_pem(PUBLIC_KEY_PEM),
+            PublicKeyPin::from_public_key_der(der),
+        );
+    }
+
+    #[test]
+    fn pem_certificate_is_not_a_public_key() {
+        let pin =
+            PublicKeyPin::from_public_key_pem(include_str!("../../tests/certs/isrgrootx1.pem"));
+
+        assert!(PublicKeyPin::join(&[pin]).is_err());
+    }
+
+    #[test]
+    fn multiple_pins_are_joined() {
+        let pins = [
+            PublicKeyPin::from_sha256([0; 32]),
+            PublicKeyPin::from_sha256([0xff; 32]),
+        ];
+
+        assert_eq!(
+            PublicKeyPin::join(&pins).unwrap(),
+            "sha256//AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=;sha256////////////////////////////////////////////8="
+        );
+    }
+}
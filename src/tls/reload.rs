@@ -0,0 +1,308 @@
+use super::{Identity, TrustStore};
+use std::{
+    borrow::Cow,
+    fmt, fs, io,
+    path::PathBuf,
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// A handle to an [`Identity`] or [`TrustStore`] that can be replaced at
+/// runtime, without having to rebuild the HTTP client using it.
+///
+/// Pass a handle to
+/// [`TlsConfigBuilder::identity_source`](super::TlsConfigBuilder::identity_source)
+/// or
+/// [`TlsConfigBuilder::trust_store_source`](super::TlsConfigBuilder::trust_store_source)
+/// and keep a clone of it around. Whenever a new value is
+/// [`set`](Reloadable::set), every request sent afterwards will use it, while
+/// the client keeps its connection pool and agent thread.
+///
+/// Requests that are already in flight are not affected. Pooled connections
+/// established using a previous value are not reused for new requests, and are
+/// closed once they have been idle for long enough, so existing connections
+/// drain gracefully.
+///
+/// # Watching files
+///
+/// Values referring to files, such as those created using
+/// [`Identity::from_pem_file`] or [`TrustStore::from_file`], can instead be
+/// watched for changes using [`Reloadable::watch`]. The files are read into
+/// memory immediately, and then reloaded whenever their modification time
+/// changes.
+///
+/// Files are not watched in the background. Instead, whenever a request is
+/// sent using the handle and at least the given interval has passed since the
+/// last check, the modification times of the files are checked. If reloading
+/// fails, for example because a file is only partially written, then the
+/// previous value continues to be used, and reloading will be attempted again
+/// after the next interval.
+///
+/// # Examples
+///
+/// ```
+/// use isahc::{prelude::*, HttpClient, tls::{Identity, Reloadable, TlsConfig}};
+/// use std::time::Duration;
+///
+/// let identity = Reloadable::<Identity>::watch(
+///     Identity::from_pem_file("client.pem", None),
+///     Duration::from_secs(60),
+/// );
+///
+/// let client = HttpClient::builder()
+///     .tls_config(TlsConfig::builder().identity_source(identity.clone()).build())
+///     .build()?;
+/// # Ok::<(), isahc::Error>(())
+/// ```
+pub struct Reloadable<T>(Arc<Inner<T>>);
+
+struct Inner<T> {
+    current: RwLock<T>,
+    watcher: Option<Watcher<T>>,
+}
+
+struct Watcher<T> {
+    /// The original value referring to the watched files.
+    source: T,
+    paths: Vec<PathBuf>,
+    interval: Duration,
+    load: fn(&T) -> io::Result<T>,
+    state: Mutex<WatchState>,
+}
+
+struct WatchState {
+    last_checked: Instant,
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl<T: Clone> Reloadable<T> {
+    /// Create a new handle with the given initial value.
+    pub fn new(value: T) -> Self {
+        Self(Arc::new(Inner {
+            current: RwLock::new(value),
+            watcher: None,
+        }))
+    }
+
+    /// Get a copy of the current value.
+    ///
+    /// If files are being watched, they are reloaded first if they have
+    /// changed.
+    pub fn get(&self) -> T {
+        if let Some(watcher) = self.0.watcher.as_ref() {
+            self.poll(watcher);
+        }
+
+        self.0.current.read().unwrap().clone()
+    }
+
+    /// Replace the current value. Requests sent from now on will use the new
+    /// value.
+    ///
+    /// If files are being watched, they will continue to be watched, and will
+    /// replace this value once they change.
+    pub fn set(&self, value: T) {
+        *self.0.current.write().unwrap() = value;
+    }
+
+    fn watch_with(
+        value: T,
+        paths: Vec<PathBuf>,
+        interval: Duration,
+        load: fn(&T) -> io::Result<T>,
+    ) -> Self {
+        let modified = modified_times(&paths);
+
+        // If the files cannot be loaded yet, fall back to letting the TLS
+        // backend read them so that any errors are reported when sending a
+        // request.
+        let current = load(&value).unwrap_or_else(|e| {
+            tracing::warn!(?paths, "failed to load watched files: {}", e);
+            value.clone()
+        });
+
+        Self(Arc::new(Inner {
+            current: RwLock::new(current),
+            watcher: Some(Watcher {
+                source: value,
+                paths,
+                interval,
+                load,
+                state: Mutex::new(WatchState {
+                    last_checked: Instant::now(),
+                    modified,
+                }),
+            }),
+        }))
+    }
+
+    fn poll(&self, watcher: &Watcher<T>) {
+        // If another request is already checking, don't wait for it.
+        let Ok(mut state) = watcher.state.try_lock() else {
+            return;
+        };
+
+        if state.last_checked.elapsed() < watcher.interval {
+            return;
+        }
+
+        state.last_checked = Instant::now();
+
+        let modified = modified_times(&watcher.paths);
+
+        if modified == state.modified {
+            return;
+        }
+
+        match (watcher.load)(&watcher.source) {
+            Ok(value) => {
+                tracing::debug!(paths = ?watcher.paths, "reloaded watched files");
+                self.set(value);
+                state.modified = modified;
+            }
+            Err(e) => {
+                tracing::warn!(paths = ?watcher.paths, "failed to reload watched files: {}", e);
+            }
+        }
+    }
+}
+
+impl Reloadable<Identity> {
+    /// Watch the files referred to by the given identity, including those of
+    /// its private key, and reload them when they change.
+    ///
+    /// See the [type-level documentation](Reloadable#watching-files) for
+    /// details.
+    pub fn watch(identity: Identity, interval: Duration) -> Self {
+        let paths = identity.paths().map(Into::into).collect();
+
+        Self::watch_with(identity, paths, interval, Identity::load)
+    }
+}
+
+impl Reloadable<TrustStore> {
+    /// Watch the certificate bundle file referred to by the given trust store,
+    /// and reload it when it changes.
+    ///
+    /// See the [type-level documentation](Reloadable#watching-files) for
+    /// details.
+    pub fn watch(store: TrustStore, interval: Duration) -> Self {
+        let paths = store.path().map(Into::into).into_iter().collect();
+
+        Self::watch_with(store, paths, interval, TrustStore::load)
+    }
+}
+
+impl<T> Clone for Reloadable<T> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Reloadable<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Reloadable")
+            .field(&*self.0.current.read().unwrap())
+            .finish()
+    }
+}
+
+fn modified_times(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// A configuration value that is either fixed or reloadable.
+#[derive(Clone, Debug)]
+pub(crate) enum Source<T> {
+    Fixed(T),
+    Reloadable(Reloadable<T>),
+}
+
+impl<T: Clone> Source<T> {
+    pub(crate) fn get(&self) -> Cow<'_, T> {
+        match self {
+            Self::Fixed(value) => Cow::Borrowed(value),
+            Self::Reloadable(handle) => Cow::Owned(handle.get()),
+        }
+    }
+}
+
+impl<T: Default> Default for Source<T> {
+    fn default() -> Self {
+        Self::Fixed(T::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(dir: &tempfile::TempDir, contents: &str) -> PathBuf {
+        let path = dir.path().join("value");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn read(path: &PathBuf) -> io::Result<PathBuf> {
+        fs::read_to_string(path).map(PathBuf::from)
+    }
+
+    #[test]
+    fn set_replaces_value() {
+        let handle = Reloadable::new(1);
+        let clone = handle.clone();
+
+        clone.set(2);
+
+        assert_eq!(handle.get(), 2);
+    }
+
+    #[test]
+    fn watched_file_is_reloaded_when_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(&dir, "first");
+        let handle = Reloadable::watch_with(path.clone(), vec![path.clone()], Duration::ZERO, read);
+
+        assert_eq!(handle.get(), PathBuf::from("first"));
+
+        fs::write(&path, "second").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+
+        assert_eq!(handle.get(), PathBuf::from("second"));
+
+        fs::remove_file(&path).unwrap();
+
+        // A missing file keeps the previous value.
+        assert_eq!(handle.get(), PathBuf::from("second"));
+    }
+
+    #[test]
+    fn watched_file_is_not_checked_before_interval() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = temp_file(&dir, "first");
+        let handle = Reloadable::watch_with(
+            path.clone(),
+            vec![path.clone()],
+            Duration::from_secs(3600),
+            read,
+        );
+
+        fs::write(&path, "second").unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+
+        assert_eq!(handle.get(), PathBuf::from("first"));
+    }
+}
@@ -18,13 +18,15 @@ use curl_sys::{
     CURLOPT_PROXY_CAINFO_BLOB, CURLOPT_PROXY_CAPATH,
 };
 use std::{
-    env, fmt,
+    env, fmt, fs, io,
     os::raw::c_char,
-    path::PathBuf,
+    path::{Path, PathBuf},
     ptr,
-    sync::{Arc, LazyLock},
+    sync::{Arc, LazyLock, OnceLock},
 };
 
+#[cfg(feature = "trust-native-roots")]
+mod native_roots;
 #[cfg(feature = "trust-webpki-roots")]
 mod webpki_roots;
 
@@ -51,6 +53,27 @@ mod webpki_roots;
 /// use rustls as the TLS backend, with [`TrustStore::webpki_roots`] instead as
 /// the default trust store.
 ///
+/// # Combining stores
+///
+/// Most TLS backends only support a single source of trusted certificates at a
+/// time, and differ in how they combine the operating system's store with
+/// custom certificates. To trust the certificates of more than one store, such
+/// as both the operating system's roots and a private certificate authority,
+/// use [`TrustStoreBuilder::include`] and `TrustStoreBuilder::include_native`
+/// (which requires the `trust-native-roots` feature) to merge them into a
+/// single in-memory bundle. A merged store behaves the
+/// same way with every backend, since only the merged bundle is used for
+/// verification.
+///
+/// ```
+/// use isahc::tls::TrustStore;
+///
+/// let store = TrustStore::builder()
+///     .include(TrustStore::from_file("/etc/certs/cabundle.pem"))
+///     .certificate_from_pem(include_str!("../../../tests/certs/isrgrootx1.pem"))
+///     .build();
+/// ```
+///
 /// # Cloning
 ///
 /// A trust store can be expensive to create, but once created, it should be
@@ -101,8 +124,24 @@ enum Repr {
         // TODO: How to track when it is safe to free this?
         bytes: Arc<[u8]>,
     },
+
+    /// Use the certificates in a hashed directory, which are read into memory
+    /// the first time the store is used.
+    ///
+    /// Only some backends support directories natively, so reading them
+    /// ourselves keeps the behavior consistent.
+    Directory {
+        path: PathBuf,
+        bundle: Arc<OnceLock<LoadResult>>,
+    },
+
+    /// A store that could not be built, which fails when used.
+    Invalid(Arc<io::Error>),
 }
 
+/// The result of reading certificates into memory.
+type LoadResult = Result<Arc<[u8]>, Arc<io::Error>>;
+
 impl TrustStore {
     /// Use the operating system's native APIs for verifying certificate trust,
     /// if possible. This is normally the trust method used for most typical applications.
@@ -196,6 +235,35 @@ impl TrustStore {
         Self(Repr::FilePath(path.into()))
     }
 
+    /// Use a directory of certificates in PEM format, named by the hash of
+    /// their subject as created by OpenSSL's `c_rehash` or `openssl rehash`
+    /// commands.
+    ///
+    /// Only files named in the form `<hash>.<n>`, such as `4042bcee.0`, are
+    /// used, and other files in the directory are ignored. Unlike OpenSSL,
+    /// which looks certificates up as needed, the directory is read in full
+    /// the first time the store is used, so that the behavior is the same with
+    /// every TLS backend. Changes made to the directory later are not noticed,
+    /// unless the store is watched using
+    /// [`Reloadable::watch`](crate::tls::Reloadable::watch).
+    ///
+    /// If the directory cannot be read, an error will be returned when
+    /// attempting to send a request using the offending store.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::tls::TrustStore;
+    ///
+    /// let store = TrustStore::from_dir("/etc/ssl/certs");
+    /// ```
+    pub fn from_dir<P: Into<PathBuf>>(path: P) -> Self {
+        Self(Repr::Directory {
+            path: path.into(),
+            bundle: Default::default(),
+        })
+    }
+
     /// Return a builder for creating a custom certificate store, which allows
     /// you to supply your own collection of trusted certificates in memory.
     ///
@@ -212,7 +280,59 @@ impl TrustStore {
     ///     .build();
     /// ```
     pub fn builder() -> TrustStoreBuilder {
-        TrustStoreBuilder { pem: Vec::new() }
+        TrustStoreBuilder {
+            pem: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// Get the path of the certificate bundle file or directory this store
+    /// uses, if any.
+    pub(crate) fn path(&self) -> Option<&Path> {
+        match &self.0 {
+            Repr::FilePath(path) | Repr::Directory { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// Read the certificate bundle file or directory this store uses into
+    /// memory, if any.
+    pub(crate) fn load(&self) -> io::Result<Self> {
+        match &self.0 {
+            Repr::FilePath(path) => Ok(Self(Repr::PemBundle {
+                bytes: fs::read(path)?.into(),
+            })),
+            Repr::Directory { path, .. } => Ok(Self(Repr::PemBundle {
+                bytes: read_dir(path)?.into(),
+            })),
+            _ => Ok(self.clone()),
+        }
+    }
+
+    /// Get the in-memory bundle of certificates used by this store, if any.
+    fn bundle(&self) -> Result<Option<&Arc<[u8]>>, Error> {
+        let result = match &self.0 {
+            Repr::PemBundle { bytes } => Ok(bytes),
+            Repr::Directory { path, bundle } => bundle
+                .get_or_init(|| {
+                    read_dir(path)
+                        .map(Into::into)
+                        .map_err(|e| Arc::new(with_path(e, path)))
+                })
+                .as_ref(),
+            Repr::Invalid(error) => Err(error),
+            _ => return Ok(None),
+        };
+
+        result
+            .map(Some)
+            .map_err(|e| Error::new(ErrorKind::InvalidTlsConfiguration, Arc::clone(e)))
+    }
+
+    /// Whether this store asks the TLS backend to use the OS-native store
+    /// directly, rather than providing certificates to it.
+    pub(super) fn is_native_ca(&self) -> bool {
+        matches!(self.0, Repr::NativeCa)
     }
 
     pub(super) fn configure_ssl_options(&self, ssl_opt: &mut SslOpt) {
@@ -239,6 +359,10 @@ impl Default for TrustStore {
 #[derive(Clone, Debug)]
 pub struct TrustStoreBuilder {
     pem: Vec<u8>,
+
+    /// The first error encountered while including another store, which is
+    /// reported when the built store is used.
+    error: Option<Arc<io::Error>>,
 }
 
 impl TrustStoreBuilder {
@@ -274,6 +398,77 @@ impl TrustStoreBuilder {
         self
     }
 
+    /// Add all of the certificates trusted by another store.
+    ///
+    /// Stores using files or directories are read immediately. If reading
+    /// fails, an error will be returned when attempting to send a request
+    /// using the built store.
+    ///
+    /// The operating system's store returned by [`TrustStore::native`] can only
+    /// be included if the `trust-native-roots` feature is enabled, in which
+    /// case this is the same as `TrustStoreBuilder::include_native`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::tls::TrustStore;
+    ///
+    /// let store = TrustStore::builder()
+    ///     .include(TrustStore::from_file("/etc/certs/cabundle.pem"))
+    ///     .include(TrustStore::from_dir("/etc/certs/private"))
+    ///     .build();
+    /// ```
+    pub fn include(mut self, store: TrustStore) -> Self {
+        let result = match &store.0 {
+            Repr::PemBundle { bytes } => Ok(Arc::clone(bytes)),
+            Repr::FilePath(path) => fs::read(path)
+                .map(Into::into)
+                .map_err(|e| Arc::new(with_path(e, path))),
+            Repr::Directory { path, bundle } => match bundle.get() {
+                Some(result) => result.clone(),
+                None => read_dir(path)
+                    .map(Into::into)
+                    .map_err(|e| Arc::new(with_path(e, path))),
+            },
+            Repr::Invalid(error) => Err(Arc::clone(error)),
+            Repr::NativeCa | Repr::Unset | Repr::NoOp => return self.include_native_store(),
+        };
+
+        match result {
+            Ok(bytes) => self.append_pem(&bytes),
+            Err(e) => {
+                self.error.get_or_insert(e);
+            }
+        }
+
+        self
+    }
+
+    #[cfg(feature = "trust-native-roots")]
+    fn include_native_store(self) -> Self {
+        self.include_native()
+    }
+
+    #[cfg(not(feature = "trust-native-roots"))]
+    fn include_native_store(mut self) -> Self {
+        self.error.get_or_insert_with(|| {
+            Arc::new(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "including the native trust store requires the `trust-native-roots` feature",
+            ))
+        });
+        self
+    }
+
+    /// Append a PEM bundle, making sure it starts on a new line.
+    fn append_pem(&mut self, pem: &[u8]) {
+        if !self.pem.is_empty() && !self.pem.ends_with(b"\n") {
+            self.pem.push(b'\n');
+        }
+
+        self.pem.extend_from_slice(pem);
+    }
+
     /// Finalize the builder and return a new trust store.
     ///
     /// # Memory characteristics
@@ -292,12 +487,67 @@ impl TrustStoreBuilder {
     /// certificates will never be freed from memory until the HTTP client that
     /// used them is closed.
     pub fn build(self) -> TrustStore {
+        if let Some(error) = self.error {
+            return TrustStore(Repr::Invalid(error));
+        }
+
         TrustStore(Repr::PemBundle {
             bytes: self.pem.into(),
         })
     }
 }
 
+/// Read all certificates in a hashed certificate directory into a single
+/// bundle.
+fn read_dir(path: &Path) -> io::Result<Vec<u8>> {
+    let mut paths = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .filter(|path| {
+            path.as_ref()
+                .map_or(true, |path| path.file_name().is_some_and(is_hashed_name))
+        })
+        .collect::<io::Result<Vec<_>>>()?;
+
+    // Sort so that the bundle does not depend on the directory order.
+    paths.sort();
+
+    let mut bundle = Vec::new();
+
+    for path in paths {
+        let pem = fs::read(&path)?;
+
+        if !bundle.is_empty() && !bundle.ends_with(b"\n") {
+            bundle.push(b'\n');
+        }
+
+        bundle.extend_from_slice(&pem);
+    }
+
+    Ok(bundle)
+}
+
+/// Check whether a file name is in the form used for certificates in hashed
+/// directories. Names with an `r` before the number are used for revocation
+/// lists and are not certificates.
+fn is_hashed_name(name: &std::ffi::OsStr) -> bool {
+    let Some((hash, n)) = name.to_str().and_then(|name| name.split_once('.')) else {
+        return false;
+    };
+
+    hash.len() == 8
+        && hash.bytes().all(|b| b.is_ascii_hexdigit())
+        && !n.is_empty()
+        && n.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Include the path in an I/O error message.
+fn with_path(error: io::Error, path: &Path) -> io::Error {
+    io::Error::new(
+        error.kind(),
+        format!("failed to read {}: {}", path.display(), error),
+    )
+}
+
 impl SetOpt for TrustStore {
     fn set_opt(&self, easy: &mut EasyHandle) -> Result<(), SetOptError> {
         match &self.0 {
@@ -305,9 +555,13 @@ impl SetOpt for TrustStore {
             Repr::FilePath(path) => {
                 easy.cainfo(path)?;
             }
-            Repr::PemBundle { bytes } => unsafe {
-                easy.setopt_blob_nocopy(CURLOPT_CAINFO_BLOB, bytes)?;
-            },
+            Repr::PemBundle { .. } | Repr::Directory { .. } | Repr::Invalid(_) => {
+                if let Some(bytes) = self.bundle()? {
+                    unsafe {
+                        easy.setopt_blob_nocopy(CURLOPT_CAINFO_BLOB, bytes)?;
+                    }
+                }
+            }
             Repr::Unset => {
                 // safe wrapper does not allow setting to null
                 unsafe {
@@ -344,9 +598,13 @@ impl SetOptProxy for TrustStore {
                     .into());
                 }
             }
-            Repr::PemBundle { bytes } => unsafe {
-                easy.setopt_blob_nocopy(CURLOPT_PROXY_CAINFO_BLOB, bytes)?;
-            },
+            Repr::PemBundle { .. } | Repr::Directory { .. } | Repr::Invalid(_) => {
+                if let Some(bytes) = self.bundle()? {
+                    unsafe {
+                        easy.setopt_blob_nocopy(CURLOPT_PROXY_CAINFO_BLOB, bytes)?;
+                    }
+                }
+            }
             Repr::Unset => {
                 // safe wrapper does not allow setting to null
                 unsafe {
@@ -373,6 +631,10 @@ impl fmt::Debug for TrustStore {
         match &self.0 {
             Repr::FilePath(path) => f.debug_tuple("TrustStore::FilePath").field(path).finish(),
             Repr::PemBundle { .. } => f.debug_tuple("TrustStore::PemBundle").finish(),
+            Repr::Directory { path, .. } => {
+                f.debug_tuple("TrustStore::Directory").field(path).finish()
+            }
+            Repr::Invalid(error) => f.debug_tuple("TrustStore::Invalid").field(error).finish(),
             _ => f.debug_tuple("TrustStore").finish(),
         }
     }
@@ -394,3 +656,74 @@ impl fmt::Display for CertificatePathNotUtf8Error {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ISRG_ROOT_PEM: &str = include_str!("../../../tests/certs/isrgrootx1.pem");
+
+    fn bundle(store: &TrustStore) -> Vec<u8> {
+        store.bundle().unwrap().unwrap().to_vec()
+    }
+
+    #[test]
+    fn hashed_names() {
+        assert!(is_hashed_name("4042bcee.0".as_ref()));
+        assert!(is_hashed_name("4042BCEE.12".as_ref()));
+        assert!(!is_hashed_name("4042bcee.r0".as_ref()));
+        assert!(!is_hashed_name("ISRG_Root_X1.pem".as_ref()));
+        assert!(!is_hashed_name("4042bce.0".as_ref()));
+    }
+
+    #[test]
+    fn directory_only_reads_hashed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("4042bcee.0"), ISRG_ROOT_PEM).unwrap();
+        fs::write(dir.path().join("README"), "not a certificate").unwrap();
+
+        let store = TrustStore::from_dir(dir.path());
+
+        assert_eq!(bundle(&store), ISRG_ROOT_PEM.as_bytes());
+    }
+
+    #[test]
+    fn missing_directory_is_an_error_when_used() {
+        let store = TrustStore::from_dir("/does/not/exist");
+
+        assert_eq!(
+            store.bundle().unwrap_err().kind(),
+            &ErrorKind::InvalidTlsConfiguration
+        );
+    }
+
+    #[test]
+    fn include_merges_bundles() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        fs::write(file.path(), ISRG_ROOT_PEM.trim_end()).unwrap();
+
+        let store = TrustStore::builder()
+            .include(TrustStore::from_file(file.path()))
+            .include(
+                TrustStore::builder()
+                    .certificate_from_pem(ISRG_ROOT_PEM)
+                    .build(),
+            )
+            .build();
+
+        assert_eq!(
+            bundle(&store),
+            format!("{}\n{}", ISRG_ROOT_PEM.trim_end(), ISRG_ROOT_PEM).as_bytes()
+        );
+    }
+
+    #[test]
+    fn include_missing_file_is_an_error_when_used() {
+        let store = TrustStore::builder()
+            .certificate_from_pem(ISRG_ROOT_PEM)
+            .include(TrustStore::from_file("/does/not/exist.pem"))
+            .build();
+
+        assert!(store.bundle().is_err());
+    }
+}
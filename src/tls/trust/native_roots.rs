@@ -0,0 +1,55 @@
+use super::TrustStoreBuilder;
+use crate::tls::TrustStore;
+use std::sync::LazyLock;
+
+impl TrustStoreBuilder {
+    /// Add all of the root certificates trusted by the operating system, via
+    /// the [`rustls-native-certs`](https://crates.io/crates/rustls-native-certs)
+    /// crate.
+    ///
+    /// This method is available when the `trust-native-roots` feature is
+    /// enabled. Combined with other certificates, this allows trusting both
+    /// public certificate authorities and a private one, in a way that behaves
+    /// the same with every TLS backend.
+    ///
+    /// The certificates are loaded the first time this method is called, and
+    /// then reused for the lifetime of the process. Certificates that fail to
+    /// load are skipped and logged.
+    ///
+    /// # Differences from the native store
+    ///
+    /// Unlike [`TrustStore::native`], which may delegate verification to the
+    /// operating system, this takes a snapshot of the operating system's root
+    /// certificates. Any additional policies the operating system applies when
+    /// verifying certificates itself are not applied.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use isahc::tls::TrustStore;
+    ///
+    /// let store = TrustStore::builder()
+    ///     .include_native()
+    ///     .certificate_from_pem(include_str!("../../../tests/certs/isrgrootx1.pem"))
+    ///     .build();
+    /// ```
+    pub fn include_native(self) -> Self {
+        static NATIVE_ROOTS: LazyLock<TrustStore> = LazyLock::new(|| {
+            let result = rustls_native_certs::load_native_certs();
+
+            for error in &result.errors {
+                tracing::warn!("failed to load native root certificate: {}", error);
+            }
+
+            result
+                .certs
+                .iter()
+                .fold(TrustStore::builder(), |builder, cert| {
+                    builder.certificate_from_der(cert)
+                })
+                .build()
+        });
+
+        self.include(NATIVE_ROOTS.clone())
+    }
+}
@@ -0,0 +1,348 @@
+use isahc::{
+    cache::{CacheStatus, DiskStore, MemoryStore},
+    prelude::*,
+    HttpClient, Request,
+};
+use testserver::mock;
+
+fn client() -> HttpClient {
+    HttpClient::builder()
+        .cache(MemoryStore::new(1024 * 1024))
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn no_cache_status_without_cache() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello",
+    };
+
+    let mut response = isahc::get(m.url()).unwrap();
+    response.consume().unwrap();
+
+    assert_eq!(response.cache_status(), None);
+}
+
+#[test]
+fn fresh_response_is_served_from_cache() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello",
+    };
+    let client = client();
+
+    let mut response = client.get(m.url()).unwrap();
+    assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+    assert_eq!(response.text().unwrap(), "hello");
+
+    let mut response = client.get(m.url()).unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.cache_status(), Some(CacheStatus::Hit));
+    assert!(response.headers().contains_key("age"));
+    assert_eq!(response.text().unwrap(), "hello");
+
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn partially_read_response_is_not_stored() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello",
+    };
+    let client = client();
+
+    drop(client.get(m.url()).unwrap());
+
+    let mut response = client.get(m.url()).unwrap();
+    assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+    response.consume().unwrap();
+
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn response_larger_than_max_entry_size_is_not_stored() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello world",
+    };
+    let client = HttpClient::builder()
+        .cache(MemoryStore::new(1024 * 1024))
+        .cache_max_entry_size(5)
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let mut response = client.get(m.url()).unwrap();
+        assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+        assert_eq!(response.text().unwrap(), "hello world");
+    }
+
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn response_of_unknown_length_larger_than_max_entry_size_is_not_stored() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello world",
+        transfer_encoding: true,
+    };
+    let client = HttpClient::builder()
+        .cache(MemoryStore::new(1024 * 1024))
+        .cache_max_entry_size(5)
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        let mut response = client.get(m.url()).unwrap();
+        assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+        assert_eq!(response.text().unwrap(), "hello world");
+    }
+
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn no_store_response_is_not_cached() {
+    let m = mock! {
+        headers {
+            "cache-control": "no-store",
+        }
+        body: "hello",
+    };
+    let client = client();
+
+    client.get(m.url()).unwrap().consume().unwrap();
+
+    let mut response = client.get(m.url()).unwrap();
+    response.consume().unwrap();
+
+    assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn stale_response_is_revalidated_with_etag() {
+    let m = mock! {
+        #0 => {
+            headers {
+                "cache-control": "no-cache",
+                "etag": "\"v1\"",
+            }
+            body: "hello",
+        },
+        _ => {
+            status: 304,
+            headers {
+                "etag": "\"v1\"",
+                "x-revalidated": "yes",
+            }
+        },
+    };
+    let client = client();
+
+    client.get(m.url()).unwrap().consume().unwrap();
+
+    let mut response = client.get(m.url()).unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.cache_status(), Some(CacheStatus::Revalidated));
+    assert_eq!(response.headers()["x-revalidated"], "yes");
+    assert_eq!(response.text().unwrap(), "hello");
+
+    assert_eq!(m.requests_received(), 2);
+    m.requests()[1].expect_header("if-none-match", "\"v1\"");
+}
+
+#[test]
+fn stale_response_is_revalidated_with_last_modified() {
+    let m = mock! {
+        #0 => {
+            headers {
+                "cache-control": "max-age=0",
+                "last-modified": "Wed, 21 Oct 2015 07:28:00 GMT",
+            }
+            body: "hello",
+        },
+        _ => {
+            status: 304,
+        },
+    };
+    let client = client();
+
+    client.get(m.url()).unwrap().consume().unwrap();
+
+    let mut response = client.get(m.url()).unwrap();
+
+    assert_eq!(response.cache_status(), Some(CacheStatus::Revalidated));
+    assert_eq!(response.text().unwrap(), "hello");
+    m.requests()[1].expect_header("if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT");
+}
+
+#[test]
+fn changed_response_replaces_stale_entry() {
+    let m = mock! {
+        #0 => {
+            headers {
+                "cache-control": "no-cache",
+                "etag": "\"v1\"",
+            }
+            body: "old",
+        },
+        _ => {
+            headers {
+                "cache-control": "max-age=60",
+                "etag": "\"v2\"",
+            }
+            body: "new",
+        },
+    };
+    let client = client();
+
+    client.get(m.url()).unwrap().consume().unwrap();
+
+    let mut response = client.get(m.url()).unwrap();
+    assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+    assert_eq!(response.text().unwrap(), "new");
+
+    let mut response = client.get(m.url()).unwrap();
+    assert_eq!(response.cache_status(), Some(CacheStatus::Hit));
+    assert_eq!(response.text().unwrap(), "new");
+
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn vary_headers_must_match() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+            "vary": "accept-language",
+        }
+        body: "hello",
+    };
+    let client = client();
+
+    let get = |language: &str| {
+        let mut response = client
+            .send(
+                Request::get(m.url())
+                    .header("accept-language", language)
+                    .body(())
+                    .unwrap(),
+            )
+            .unwrap();
+        response.consume().unwrap();
+        response.cache_status()
+    };
+
+    assert_eq!(get("en"), Some(CacheStatus::Miss));
+    assert_eq!(get("en"), Some(CacheStatus::Hit));
+    assert_eq!(get("de"), Some(CacheStatus::Miss));
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn request_no_cache_forces_revalidation() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello",
+    };
+    let client = client();
+
+    client.get(m.url()).unwrap().consume().unwrap();
+
+    let mut response = client
+        .send(
+            Request::get(m.url())
+                .header("cache-control", "no-cache")
+                .body(())
+                .unwrap(),
+        )
+        .unwrap();
+    response.consume().unwrap();
+
+    assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn only_if_cached_without_entry_returns_gateway_timeout() {
+    let m = mock! {};
+    let client = client();
+
+    let response = client
+        .send(
+            Request::get(m.url())
+                .header("cache-control", "only-if-cached")
+                .body(())
+                .unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(response.status(), 504);
+    assert_eq!(m.requests_received(), 0);
+}
+
+#[test]
+fn unsafe_request_invalidates_entry() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello",
+    };
+    let client = client();
+
+    client.get(m.url()).unwrap().consume().unwrap();
+    client.post(m.url(), "data").unwrap().consume().unwrap();
+
+    let mut response = client.get(m.url()).unwrap();
+    response.consume().unwrap();
+
+    assert_eq!(response.cache_status(), Some(CacheStatus::Miss));
+    assert_eq!(m.requests_received(), 3);
+}
+
+#[test]
+fn disk_store_persists_across_clients() {
+    let m = mock! {
+        headers {
+            "cache-control": "max-age=60",
+        }
+        body: "hello",
+    };
+    let dir = tempfile::tempdir().unwrap();
+
+    let client = HttpClient::builder()
+        .cache(DiskStore::new(dir.path()).unwrap())
+        .build()
+        .unwrap();
+    client.get(m.url()).unwrap().consume().unwrap();
+
+    let client = HttpClient::builder()
+        .cache(DiskStore::new(dir.path()).unwrap())
+        .build()
+        .unwrap();
+    let mut response = client.get(m.url()).unwrap();
+
+    assert_eq!(response.cache_status(), Some(CacheStatus::Hit));
+    assert_eq!(response.text().unwrap(), "hello");
+    assert_eq!(m.requests_received(), 1);
+}
@@ -0,0 +1,95 @@
+use isahc::{
+    HttpClient,
+    config::{CircuitBreaker, CircuitState},
+    error::ErrorKind,
+    prelude::*,
+};
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use testserver::mock;
+
+#[test]
+fn circuit_opens_after_failures_and_fails_fast() {
+    let m = mock! {
+        status: 503,
+    };
+    let client = HttpClient::builder()
+        .circuit_breaker(
+            CircuitBreaker::new()
+                .minimum_requests(2)
+                .cooldown(Duration::from_secs(60)),
+        )
+        .build()
+        .unwrap();
+
+    for _ in 0..2 {
+        assert_eq!(client.get(m.url()).unwrap().status(), 503);
+    }
+
+    let error = client.get(m.url()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::CircuitOpen);
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn successful_responses_keep_circuit_closed() {
+    let m = mock! {
+        status: 404,
+    };
+    let client = HttpClient::builder()
+        .circuit_breaker(CircuitBreaker::new().minimum_requests(2))
+        .build()
+        .unwrap();
+
+    for _ in 0..4 {
+        client.get(m.url()).unwrap().consume().unwrap();
+    }
+
+    assert_eq!(m.requests_received(), 4);
+}
+
+#[test]
+fn half_open_probe_closes_circuit_on_success() {
+    let m = mock! {
+        #0 => {
+            status: 500,
+        },
+        _ => {
+            status: 200,
+        },
+    };
+    let transitions = Arc::new(Mutex::new(Vec::new()));
+    let client = HttpClient::builder()
+        .circuit_breaker(
+            CircuitBreaker::new()
+                .minimum_requests(1)
+                .cooldown(Duration::from_millis(100))
+                .on_state_change({
+                    let transitions = transitions.clone();
+                    move |_, from, to| transitions.lock().unwrap().push((from, to))
+                }),
+        )
+        .build()
+        .unwrap();
+
+    assert_eq!(client.get(m.url()).unwrap().status(), 500);
+    assert!(client.get(m.url()).is_err());
+
+    thread::sleep(Duration::from_millis(150));
+
+    assert_eq!(client.get(m.url()).unwrap().status(), 200);
+    assert_eq!(client.get(m.url()).unwrap().status(), 200);
+    assert_eq!(m.requests_received(), 3);
+
+    assert_eq!(
+        *transitions.lock().unwrap(),
+        vec![
+            (CircuitState::Closed, CircuitState::Open),
+            (CircuitState::Open, CircuitState::HalfOpen),
+            (CircuitState::HalfOpen, CircuitState::Closed),
+        ]
+    );
+}
@@ -0,0 +1,139 @@
+use futures_lite::future::block_on;
+use isahc::{HttpClient, Request, Validators, error::ErrorKind, prelude::*};
+use std::time::{Duration, UNIX_EPOCH};
+use testserver::mock;
+
+#[test]
+fn conditional_headers_are_sent() {
+    let m = mock! {
+        status: 304,
+    };
+
+    let response = Request::get(m.url())
+        .if_none_match("\"abc\"")
+        .if_modified_since(UNIX_EPOCH)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert!(response.not_modified());
+    m.request().expect_header("if-none-match", "\"abc\"");
+    m.request()
+        .expect_header("if-modified-since", "Thu, 01 Jan 1970 00:00:00 GMT");
+}
+
+#[test]
+fn time_before_epoch_is_rejected() {
+    let m = mock!();
+
+    let error = Request::get(m.url())
+        .if_modified_since(UNIX_EPOCH - Duration::from_secs(1))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidRequest);
+    assert_eq!(m.requests_received(), 0);
+}
+
+#[test]
+fn time_after_year_9999_is_rejected() {
+    let m = mock!();
+
+    let error = Request::get(m.url())
+        .if_modified_since(UNIX_EPOCH + Duration::from_secs(253_402_300_800))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap_err();
+
+    assert_eq!(error.kind(), ErrorKind::InvalidRequest);
+    assert_eq!(m.requests_received(), 0);
+}
+
+#[test]
+fn explicit_header_takes_precedence() {
+    let m = mock!();
+
+    Request::get(m.url())
+        .header("if-none-match", "\"explicit\"")
+        .if_none_match("\"configured\"")
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap()
+        .consume()
+        .unwrap();
+
+    assert_eq!(
+        m.request().get_header("if-none-match").collect::<Vec<_>>(),
+        vec!["\"explicit\""]
+    );
+}
+
+#[test]
+fn fetch_if_changed_updates_validators() {
+    let m = mock! {
+        #0 => {
+            headers {
+                "etag": "\"v1\"",
+                "last-modified": "Wed, 21 Oct 2015 07:28:00 GMT",
+            }
+            body: "hello",
+        },
+        _ => {
+            status: 304,
+            headers {
+                "etag": "\"v1\"",
+            }
+        },
+    };
+    let client = HttpClient::new().unwrap();
+    let mut validators = Validators::new();
+
+    let mut response = client
+        .fetch_if_changed(m.url(), &mut validators)
+        .unwrap()
+        .unwrap();
+    assert_eq!(response.text().unwrap(), "hello");
+    assert_eq!(validators.etag(), Some("\"v1\""));
+    assert!(validators.last_modified().is_some());
+
+    assert!(
+        client
+            .fetch_if_changed(m.url(), &mut validators)
+            .unwrap()
+            .is_none()
+    );
+
+    let requests = m.requests();
+    assert_eq!(requests[0].get_header("if-none-match").count(), 0);
+    requests[1].expect_header("if-none-match", "\"v1\"");
+    requests[1].expect_header("if-modified-since", "Wed, 21 Oct 2015 07:28:00 GMT");
+}
+
+#[test]
+fn fetch_if_changed_async_returns_changed_response() {
+    let m = mock! {
+        headers {
+            "etag": "\"v2\"",
+        }
+        body: "changed",
+    };
+    let client = HttpClient::new().unwrap();
+    let mut validators = Validators::new();
+
+    block_on(async {
+        let mut response = client
+            .fetch_if_changed_async(m.url(), &mut validators)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(response.text().await.unwrap(), "changed");
+    });
+
+    assert_eq!(validators.etag(), Some("\"v2\""));
+}
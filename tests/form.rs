@@ -0,0 +1,102 @@
+#![cfg(feature = "form")]
+
+use futures_lite::future::block_on;
+use isahc::{prelude::*, AsyncBody, Body, Request};
+use std::collections::BTreeMap;
+use testserver::mock;
+
+#[test]
+fn form_body_is_url_encoded() {
+    let m = mock!();
+
+    Request::post(m.url())
+        .body(Body::from_form(&[("name", "Jane Doe"), ("note", "a&b=c/ü")]).unwrap())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    m.request()
+        .expect_header("content-type", "application/x-www-form-urlencoded");
+    m.request().expect_header("content-length", "37");
+    m.request()
+        .expect_body("name=Jane+Doe&note=a%26b%3Dc%2F%C3%BC");
+}
+
+#[test]
+fn async_form_body_is_url_encoded() {
+    let m = mock!();
+    let mut form = BTreeMap::new();
+    form.insert("a", 1);
+    form.insert("b", 2);
+
+    block_on(async {
+        Request::put(m.url())
+            .body(AsyncBody::from_form(&form).unwrap())
+            .unwrap()
+            .send_async()
+            .await
+            .unwrap();
+    });
+
+    m.request()
+        .expect_header("content-type", "application/x-www-form-urlencoded");
+    m.request().expect_body("a=1&b=2");
+}
+
+#[test]
+fn explicit_content_type_is_kept() {
+    let m = mock!();
+
+    Request::post(m.url())
+        .header("content-type", "text/plain")
+        .body(Body::from_form(&[("a", "b")]).unwrap())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    m.request().expect_header("content-type", "text/plain");
+}
+
+#[test]
+fn query_parameters_are_appended() {
+    let m = mock!();
+
+    Request::get(format!("{}search?page=2", m.url()))
+        .query(&[("q", "rust & curl"), ("lang", "en")])
+        .unwrap()
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(m.request().url(), "/search?page=2&q=rust+%26+curl&lang=en");
+}
+
+#[test]
+fn query_without_existing_parameters() {
+    let request = Request::get("http://example.org/a/b")
+        .query(&[("x", "1")])
+        .unwrap()
+        .query(&[("y", "2")])
+        .unwrap()
+        .body(())
+        .unwrap();
+
+    assert_eq!(request.uri(), "http://example.org/a/b?x=1&y=2");
+}
+
+#[test]
+fn empty_query_leaves_uri_unchanged() {
+    let request = Request::get("http://example.org/?")
+        .query(&Vec::<(String, String)>::new())
+        .unwrap()
+        .body(())
+        .unwrap();
+
+    assert_eq!(request.uri(), "http://example.org/?");
+}
+
+#[test]
+fn unsupported_query_value_is_an_error() {
+    assert!(Request::get("http://example.org").query(&42).is_err());
+}
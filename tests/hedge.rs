@@ -0,0 +1,119 @@
+use isahc::{Request, prelude::*};
+use std::{
+    io::{Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+use testserver::mock;
+
+/// Spawn a server whose first connection never gets a response, while later
+/// connections are answered immediately. Returns the server URL and a channel
+/// that reports each accepted connection.
+fn stalling_server() -> (String, mpsc::Receiver<usize>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        let mut stalled = Vec::new();
+
+        for (i, stream) in listener.incoming().enumerate() {
+            let mut stream = stream.unwrap();
+            tx.send(i).unwrap();
+
+            if i == 0 {
+                // Keep the connection open without responding.
+                stalled.push(stream);
+                continue;
+            }
+
+            read_request_head(&stream);
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 6\r\n\r\nhedged")
+                .unwrap();
+            let _ = stream.shutdown(Shutdown::Write);
+        }
+    });
+
+    (url, rx)
+}
+
+fn read_request_head(mut stream: &TcpStream) {
+    let mut head = Vec::new();
+    let mut byte = [0];
+
+    while !head.ends_with(b"\r\n\r\n") {
+        match stream.read(&mut byte) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => head.push(byte[0]),
+        }
+    }
+}
+
+#[test]
+fn slow_request_is_hedged() {
+    let (url, connections) = stalling_server();
+    let start = Instant::now();
+
+    let mut response = Request::get(url)
+        .hedge(Duration::from_millis(100), 1)
+        .timeout(Duration::from_secs(10))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.text().unwrap(), "hedged");
+    assert!(start.elapsed() < Duration::from_secs(5));
+    assert_eq!(connections.try_iter().count(), 2);
+}
+
+#[test]
+fn zero_max_extra_disables_hedging() {
+    let (url, connections) = stalling_server();
+
+    let error = Request::get(url)
+        .hedge(Duration::from_millis(50), 0)
+        .timeout(Duration::from_millis(500))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap_err();
+
+    assert!(error.is_timeout());
+    assert_eq!(connections.try_iter().count(), 1);
+}
+
+#[test]
+fn non_idempotent_request_is_not_hedged() {
+    let (url, connections) = stalling_server();
+
+    let error = Request::post(url)
+        .hedge(Duration::from_millis(50), 2)
+        .timeout(Duration::from_millis(500))
+        .body("data")
+        .unwrap()
+        .send()
+        .unwrap_err();
+
+    assert!(error.is_timeout());
+    assert_eq!(connections.try_iter().count(), 1);
+}
+
+#[test]
+fn fast_response_is_not_hedged() {
+    let m = mock!();
+
+    Request::get(m.url())
+        .hedge(Duration::from_secs(5), 2)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap()
+        .consume()
+        .unwrap();
+
+    assert_eq!(m.requests_received(), 1);
+}
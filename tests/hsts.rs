@@ -0,0 +1,71 @@
+use isahc::{config::RedirectPolicy, hsts::HstsStore, prelude::*, HttpClient, Request};
+use std::{io::Read, net::TcpListener, thread};
+use testserver::mock;
+
+#[test]
+fn header_over_plain_http_is_ignored() {
+    let store = HstsStore::new();
+    let client = HttpClient::builder().hsts(store.clone()).build().unwrap();
+
+    let m = mock! {
+        headers {
+            "Strict-Transport-Security": "max-age=31536000; includeSubDomains",
+        }
+    };
+
+    client.get(m.url()).unwrap();
+
+    assert_eq!(m.requests_received(), 1);
+    assert!(store.entries().is_empty());
+}
+
+#[test]
+fn unknown_hosts_are_not_upgraded() {
+    let store = HstsStore::new();
+    store.preload("example.org", true);
+
+    let client = HttpClient::builder().hsts(store).build().unwrap();
+
+    let m = mock!();
+    let response = client.get(m.url()).unwrap();
+
+    assert_eq!(response.effective_uri().unwrap().scheme_str(), Some("http"));
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn redirect_to_known_host_is_upgraded() {
+    let store = HstsStore::new();
+    store.preload("localhost", false);
+
+    let client = HttpClient::builder().hsts(store).build().unwrap();
+
+    // Check what kind of connection is made to the redirect target.
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let location = format!("http://localhost:{}/", listener.local_addr().unwrap().port());
+    let first_byte = thread::spawn(move || {
+        let mut byte = [0];
+        listener.accept().unwrap().0.read_exact(&mut byte).unwrap();
+        byte[0]
+    });
+
+    let m = mock! {
+        status: 302,
+        headers {
+            "Location": location,
+        }
+    };
+
+    let result = client.send(
+        Request::get(m.url())
+            .redirect_policy(RedirectPolicy::Follow)
+            .body(())
+            .unwrap(),
+    );
+
+    assert!(result.is_err());
+    assert_eq!(m.requests_received(), 1);
+
+    // A TLS handshake record.
+    assert_eq!(first_byte.join().unwrap(), 0x16);
+}
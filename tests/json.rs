@@ -63,3 +63,62 @@ fn deserialize_json_async_io_error() {
         assert_matches!(response.json::<Value>().await, Err(e) if e.is_io());
     });
 }
+
+#[test]
+fn deserialize_json_lines() {
+    let m = mock! {
+        body: "{\"id\":1}\n{\"id\":2}\n\n{\"id\":3}\n",
+    };
+
+    let mut response = isahc::get(m.url()).unwrap();
+    let ids = response
+        .json_lines::<Value>()
+        .map(|value| value.unwrap()["id"].as_u64().unwrap())
+        .collect::<Vec<_>>();
+
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[test]
+fn deserialize_json_lines_async() {
+    use futures_lite::StreamExt;
+
+    let m = mock! {
+        body: "{\"id\":1}\nnot json\n{\"id\":3}",
+    };
+
+    block_on(async move {
+        let mut response = isahc::get_async(m.url()).await.unwrap();
+        let values = response.json_lines::<Value>().collect::<Vec<_>>().await;
+
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[0].as_ref().unwrap()["id"], 1);
+        assert_matches!(&values[1], Err(e) if e.is_syntax() && e.line() == 2);
+        assert_eq!(values[2].as_ref().unwrap()["id"], 3);
+    });
+}
+
+#[test]
+fn deserialize_json_lines_async_io_error() {
+    use futures_lite::StreamExt;
+
+    struct BadReader;
+
+    impl AsyncRead for BadReader {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Ready(Err(io::ErrorKind::UnexpectedEof.into()))
+        }
+    }
+
+    block_on(async move {
+        let mut response = http::Response::new(BadReader);
+        let mut values = response.json_lines::<Value>();
+
+        assert_matches!(values.next().await, Some(Err(e)) if e.is_io());
+        assert_matches!(values.next().await, None);
+    });
+}
@@ -34,3 +34,16 @@ fn enabling_metrics_causes_metrics_to_be_collected() {
     assert_eq!(metrics.download_progress().0, 11);
     assert!(metrics.total_time() > Duration::default());
 }
+
+#[test]
+fn metrics_report_http_version_used() {
+    let m = mock!();
+
+    let client = HttpClient::builder().metrics(true).build().unwrap();
+    let response = client.get(m.url()).unwrap();
+
+    assert_eq!(
+        response.metrics().unwrap().http_version(),
+        Some(response.version())
+    );
+}
@@ -0,0 +1,143 @@
+use futures_lite::future::block_on;
+use isahc::{
+    config::RedirectPolicy,
+    multipart::{Form, Part},
+    prelude::*,
+    Request,
+};
+use test_case::test_case;
+use testserver::mock;
+
+fn form() -> Form {
+    Form::new().text("title", "hello").part(
+        "file",
+        Part::bytes(&b"file contents"[..])
+            .file_name("a.txt")
+            .content_type("text/plain"),
+    )
+}
+
+fn expected_body(boundary: &str) -> String {
+    format!(
+        "--{b}\r\n\
+        content-disposition: form-data; name=\"title\"\r\n\
+        \r\n\
+        hello\r\n\
+        --{b}\r\n\
+        content-disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+        content-type: text/plain\r\n\
+        \r\n\
+        file contents\r\n\
+        --{b}--\r\n",
+        b = boundary
+    )
+}
+
+#[test]
+fn form_sets_content_type_and_length() {
+    let m = mock!();
+    let form = form();
+    let content_type = form.content_type();
+    let expected = expected_body(form.boundary());
+
+    Request::post(m.url()).body(form).unwrap().send().unwrap();
+
+    m.request().expect_header("content-type", content_type);
+    m.request()
+        .expect_header("content-length", expected.len().to_string());
+    m.request().expect_body(expected);
+}
+
+#[test]
+fn explicit_content_type_is_not_overridden() {
+    let m = mock!();
+
+    Request::post(m.url())
+        .header("content-type", "multipart/mixed")
+        .body(form())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    m.request().expect_header("content-type", "multipart/mixed");
+}
+
+#[test]
+fn streaming_part_with_known_length_sends_content_length() {
+    let m = mock!();
+    let form = Form::new().part(
+        "file",
+        Part::reader_sized(&b"file contents"[..], 13).file_name("a.txt"),
+    );
+    let boundary = form.boundary().to_owned();
+
+    block_on(async {
+        Request::post(m.url())
+            .body(form)
+            .unwrap()
+            .send_async()
+            .await
+            .unwrap();
+    });
+
+    let expected = format!(
+        "--{b}\r\n\
+        content-disposition: form-data; name=\"file\"; filename=\"a.txt\"\r\n\
+        \r\n\
+        file contents\r\n\
+        --{b}--\r\n",
+        b = boundary
+    );
+
+    m.request()
+        .expect_header("content-length", expected.len().to_string());
+    assert!(m.request().get_header("transfer-encoding").next().is_none());
+    m.request().expect_body(expected);
+}
+
+#[test]
+fn streaming_part_with_unknown_length_uses_chunked_encoding() {
+    let m = mock!();
+    let form = Form::new().part("data", Part::reader(&b"abc"[..]));
+
+    block_on(async {
+        Request::post(m.url())
+            .body(form)
+            .unwrap()
+            .send_async()
+            .await
+            .unwrap();
+    });
+
+    m.request().expect_header("transfer-encoding", "chunked");
+}
+
+#[test_case(307)]
+#[test_case(308)]
+fn buffered_form_is_resent_on_redirect(status: u16) {
+    let m2 = mock!();
+    let location = m2.url();
+
+    let m1 = mock! {
+        status: status,
+        headers {
+            "Location": location,
+        }
+    };
+
+    let form = form();
+    let expected = expected_body(form.boundary());
+
+    let response = Request::post(m1.url())
+        .redirect_policy(RedirectPolicy::Follow)
+        .body(form)
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    m1.request().expect_body(&expected);
+    m2.request().expect_body(&expected);
+    m2.request()
+        .expect_header_regex("content-type", "^multipart/form-data; boundary=");
+}
@@ -1,10 +1,19 @@
-use isahc::{config::IpVersion, error::ErrorKind, prelude::*, Request};
+use isahc::{
+    config::{Dialer, IpVersion, ProxyConfig},
+    error::ErrorKind,
+    net::{DnsOverHttps, Resolve, ResolveFuture, StaticResolver},
+    prelude::*,
+    HttpClient,
+    Request,
+};
 use std::{
+    error::Error,
     io::{self, Read, Write},
     net::{Ipv4Addr, Ipv6Addr, Shutdown, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
     thread,
 };
-use testserver::mock;
+use testserver::{doh::DohServer, mock};
 
 #[macro_use]
 mod utils;
@@ -155,3 +164,319 @@ fn any_ip_version_uses_ipv4_or_ipv6() {
         assert!(response.local_addr().unwrap().is_ipv4());
     }
 }
+
+#[test]
+fn resolver_addresses_are_used() {
+    let m = mock!();
+
+    let client = HttpClient::builder()
+        .resolver(StaticResolver::new().add("service.test", m.addr().port(), Ipv4Addr::LOCALHOST))
+        .build()
+        .unwrap();
+
+    let response = client
+        .get(format!("http://service.test:{}", m.addr().port()))
+        .unwrap();
+
+    assert_eq!(response.remote_addr(), Some(m.addr()));
+    assert_eq!(m.requests_received(), 1);
+    m.request()
+        .expect_header("host", format!("service.test:{}", m.addr().port()));
+}
+
+#[test]
+fn resolver_can_choose_port() {
+    struct Discovery(std::net::SocketAddr);
+
+    impl Resolve for Discovery {
+        fn resolve<'a>(&'a self, host: &'a str, port: u16) -> ResolveFuture<'a> {
+            assert_eq!((host, port), ("service.test", 80));
+            Box::pin(async move { Ok(vec![self.0]) })
+        }
+    }
+
+    let m = mock!();
+
+    let client = HttpClient::builder()
+        .resolver(Discovery(m.addr()))
+        .build()
+        .unwrap();
+
+    let response = client.get("http://service.test").unwrap();
+
+    assert_eq!(response.remote_addr(), Some(m.addr()));
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn resolver_error_is_name_resolution_error() {
+    let client = HttpClient::builder()
+        .resolver(StaticResolver::new())
+        .build()
+        .unwrap();
+
+    let result = client.get("http://service.test");
+
+    assert_matches!(result, Err(e) if e == ErrorKind::NameResolution);
+}
+
+#[test]
+fn resolver_is_not_used_for_proxied_requests() {
+    let m = mock!();
+    let proxy = m.url().parse::<http::Uri>().unwrap();
+
+    // A resolver that does not know the host would fail the request if used.
+    let client = HttpClient::builder()
+        .resolver(StaticResolver::new())
+        .build()
+        .unwrap();
+
+    let response = client
+        .send(
+            Request::get("http://service.test")
+                .proxy(proxy.clone())
+                .body(())
+                .unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+
+    let response = client
+        .send(
+            Request::get("http://service.test")
+                .proxy_config(ProxyConfig::new().http(proxy))
+                .body(())
+                .unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(m.requests_received(), 2);
+    m.request().expect_header("host", "service.test");
+}
+
+#[test]
+fn dns_over_https_addresses_are_used() {
+    let m = mock!();
+    let doh = DohServer::new();
+    doh.add("service.test", Ipv4Addr::LOCALHOST);
+
+    let client = HttpClient::builder()
+        .dns_over_https(DnsOverHttps::new(doh.url().parse().unwrap()))
+        .build()
+        .unwrap();
+
+    let url = format!("http://service.test:{}", m.addr().port());
+    let response = client.get(&url).unwrap();
+
+    assert_eq!(response.remote_addr(), Some(m.addr()));
+
+    // Both IPv4 and IPv6 addresses are queried.
+    let mut queries = doh.queries();
+    queries.sort();
+    assert_eq!(
+        queries,
+        [("service.test".to_owned(), 1), ("service.test".to_owned(), 28)]
+    );
+
+    // Addresses are cached.
+    client.get(&url).unwrap();
+
+    assert_eq!(m.requests_received(), 2);
+    assert_eq!(doh.queries().len(), 2);
+}
+
+#[test]
+fn dns_over_https_unknown_host_is_name_resolution_error() {
+    let doh = DohServer::new();
+
+    let client = HttpClient::builder()
+        .dns_over_https(DnsOverHttps::new(doh.url().parse().unwrap()))
+        .build()
+        .unwrap();
+
+    let result = client.get("http://service.test");
+
+    assert_matches!(result, Err(e) if e == ErrorKind::NameResolution);
+    assert_eq!(doh.queries().len(), 2);
+}
+
+#[test]
+fn dns_over_https_can_be_set_per_request() {
+    let m = mock!();
+    let client_doh = DohServer::new();
+    let request_doh = DohServer::new();
+    request_doh.add("service.test", Ipv4Addr::LOCALHOST);
+
+    let client = HttpClient::builder()
+        .dns_over_https(DnsOverHttps::new(client_doh.url().parse().unwrap()))
+        .build()
+        .unwrap();
+
+    let response = client
+        .send(
+            Request::get(format!("http://service.test:{}", m.addr().port()))
+                .dns_over_https(DnsOverHttps::new(request_doh.url().parse().unwrap()))
+                .body(())
+                .unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(response.remote_addr(), Some(m.addr()));
+    assert!(client_doh.queries().is_empty());
+    assert_eq!(request_doh.queries().len(), 2);
+}
+
+#[test]
+fn custom_dialer_socket_is_used() {
+    let m = mock!();
+    let addr = m.addr();
+    let uris = Arc::new(Mutex::new(Vec::new()));
+
+    let dialer = Dialer::custom({
+        let uris = uris.clone();
+        move |uri| {
+            uris.lock().unwrap().push(uri.to_string());
+            TcpStream::connect(addr)
+        }
+    });
+
+    let response = Request::get("http://service.test/foo")
+        .dial(dialer)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.remote_addr(), Some(addr));
+    assert_eq!(*uris.lock().unwrap(), ["http://service.test/foo"]);
+    assert_eq!(m.requests_received(), 1);
+    m.request().expect_header("host", "service.test");
+}
+
+#[test]
+fn custom_dialer_socket_to_ipv6_peer_is_used() {
+    // Skip the test if IPv6 is not available.
+    let Ok(server) = TcpListener::bind((Ipv6Addr::LOCALHOST, 0)) else {
+        return;
+    };
+    let addr = server.local_addr().unwrap();
+
+    thread::spawn(move || {
+        for _ in 0..2 {
+            let (mut client, _) = server.accept().unwrap();
+            let _ = client.read(&mut [0; 8192]).unwrap();
+            client
+                .write_all(b"HTTP/1.1 204 No Content\r\n\r\n")
+                .unwrap();
+        }
+    });
+
+    let response = Request::get("http://service.test")
+        .dial(Dialer::custom(move |_| TcpStream::connect(addr)))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 204);
+    assert_eq!(response.remote_addr(), Some(addr));
+
+    // Failover targets are connected the same way.
+    let response = Request::get("http://service.test")
+        .dial(format!("tcp:{},tcp:{}", addr, addr).parse::<Dialer>().unwrap())
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 204);
+    assert_eq!(response.remote_addr(), Some(addr));
+}
+
+#[test]
+fn custom_dialer_is_called_for_each_request() {
+    let m = mock!();
+    let addr = m.addr();
+    let dials = Arc::new(Mutex::new(0));
+
+    let client = HttpClient::builder()
+        .dial(Dialer::custom_async({
+            let dials = dials.clone();
+            move |_| {
+                *dials.lock().unwrap() += 1;
+                async move { TcpStream::connect(addr) }
+            }
+        }))
+        .build()
+        .unwrap();
+
+    client.get("http://service.test").unwrap();
+    client.get("http://service.test").unwrap();
+
+    assert_eq!(*dials.lock().unwrap(), 2);
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn custom_dialer_error_is_connection_failed_error() {
+    let dialer = Dialer::custom(|_| -> io::Result<TcpStream> {
+        Err(io::Error::new(io::ErrorKind::PermissionDenied, "no route"))
+    });
+
+    let result = Request::get("http://service.test")
+        .dial(dialer)
+        .body(())
+        .unwrap()
+        .send();
+
+    assert_matches!(result, Err(e) if e == ErrorKind::ConnectionFailed && {
+        let source = e.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        source.kind() == io::ErrorKind::PermissionDenied
+    });
+}
+
+#[test]
+fn failover_dialer_uses_first_reachable_target() {
+    let m = mock!();
+
+    // Find a port that nothing is listening on.
+    let closed = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let dialer = format!("tcp:{},tcp:localhost:{}", closed, m.addr().port())
+        .parse::<Dialer>()
+        .unwrap();
+
+    let response = Request::get("http://service.test")
+        .dial(dialer)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.remote_addr().unwrap().port(), m.addr().port());
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn failover_dialer_fails_if_no_target_is_reachable() {
+    let closed = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let result = Request::get("http://service.test")
+        .dial(Dialer::failover([Dialer::ip_socket(closed), Dialer::ip_socket(closed)]))
+        .body(())
+        .unwrap()
+        .send();
+
+    assert_matches!(result, Err(e) if e == ErrorKind::ConnectionFailed && {
+        let source = e.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        source.kind() == io::ErrorKind::ConnectionRefused
+    });
+}
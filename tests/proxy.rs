@@ -1,4 +1,4 @@
-use isahc::{prelude::*, Request};
+use isahc::{config::ProxyConfig, prelude::*, HttpClient, Request};
 use testserver::{mock, socks4::Socks4Server};
 
 #[test]
@@ -95,3 +95,134 @@ fn proxy_blacklist_works() {
 
     assert_eq!(m.requests_received(), 1);
 }
+
+#[test]
+fn http_proxy_is_reported_on_response() {
+    let m = mock!();
+    let proxy = m.url().parse::<http::Uri>().unwrap();
+
+    let response = Request::get("http://127.0.0.2:1234/")
+        .proxy(proxy.clone())
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.proxy(), Some(&proxy));
+}
+
+#[test]
+fn proxy_credentials_are_not_reported_on_response() {
+    let m = mock!();
+    let proxy = m.url().parse::<http::Uri>().unwrap();
+    let authority = proxy.authority().unwrap();
+
+    let response = Request::get("http://127.0.0.2:1234/")
+        .proxy(
+            format!("http://user:secret@{}/", authority)
+                .parse::<http::Uri>()
+                .unwrap(),
+        )
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.proxy(), Some(&proxy));
+}
+
+#[test]
+fn blacklisted_proxy_is_not_reported_on_response() {
+    let m = mock!();
+    let upstream = m.url().parse::<http::Uri>().unwrap();
+
+    let response = Request::get(&upstream)
+        .proxy("http://127.0.0.2:1234/".parse::<http::Uri>().unwrap())
+        .proxy_blacklist(Some(upstream.host().unwrap().to_string()))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.proxy(), None);
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn proxy_config_uses_proxy_for_scheme() {
+    let m = mock!();
+    let proxy = m.url().parse::<http::Uri>().unwrap();
+    let upstream = "http://127.0.0.2:1234/";
+
+    let client = HttpClient::builder()
+        .proxy_config(
+            ProxyConfig::new()
+                .http(proxy.clone())
+                .https("http://127.0.0.3:1234".parse().unwrap()),
+        )
+        .build()
+        .unwrap();
+
+    let response = client.get(upstream).unwrap();
+
+    assert_eq!(m.request().url(), upstream);
+    assert_eq!(response.proxy(), Some(&proxy));
+}
+
+#[test]
+fn proxy_config_no_proxy_connects_directly() {
+    let m = mock!();
+
+    let client = HttpClient::builder()
+        .proxy_config(
+            ProxyConfig::new()
+                .http("http://127.0.0.2:1234".parse().unwrap())
+                .no_proxy(["127.0.0.0/8"]),
+        )
+        .build()
+        .unwrap();
+
+    let response = client.get(m.url()).unwrap();
+
+    assert_eq!(response.proxy(), None);
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn proxy_config_selector_is_called_for_each_request() {
+    let proxy = mock!();
+    let proxy_uri = proxy.url().parse::<http::Uri>().unwrap();
+    let direct = mock!();
+
+    let client = HttpClient::builder()
+        .proxy_config(ProxyConfig::new().selector({
+            let proxy_uri = proxy_uri.clone();
+            move |uri| (uri.host() == Some("127.0.0.2")).then(|| proxy_uri.clone())
+        }))
+        .build()
+        .unwrap();
+
+    let response = client.get("http://127.0.0.2:1234/").unwrap();
+    assert_eq!(response.proxy(), Some(&proxy_uri));
+    assert_eq!(proxy.requests_received(), 1);
+
+    let response = client.get(direct.url()).unwrap();
+    assert_eq!(response.proxy(), None);
+    assert_eq!(direct.requests_received(), 1);
+}
+
+#[test]
+fn proxy_config_takes_precedence_over_proxy() {
+    let m = mock!();
+
+    let response = Request::get(m.url())
+        .proxy("http://127.0.0.2:1234/".parse::<http::Uri>().unwrap())
+        .proxy_config(ProxyConfig::new())
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.proxy(), None);
+    assert_eq!(m.requests_received(), 1);
+}
@@ -0,0 +1,90 @@
+use futures_lite::future::{block_on, poll_once};
+use isahc::{HttpClient, config::RateLimit, prelude::*};
+use std::time::{Duration, Instant};
+use testserver::mock;
+
+#[test]
+fn requests_over_rate_are_delayed() {
+    let m = mock!();
+    let client = HttpClient::builder()
+        .rate_limit(RateLimit::new().requests_per(2, Duration::from_millis(400)))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+
+    for _ in 0..3 {
+        client.get(m.url()).unwrap().consume().unwrap();
+    }
+
+    // The first two requests use the initial burst, while the third has to
+    // wait for a token to be replenished.
+    assert!(start.elapsed() >= Duration::from_millis(180));
+    assert_eq!(m.requests_received(), 3);
+}
+
+#[test]
+fn different_hosts_are_limited_separately() {
+    let m1 = mock!();
+    let m2 = mock!();
+    let client = HttpClient::builder()
+        .rate_limit(RateLimit::new().requests_per(1, Duration::from_secs(10)))
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+
+    client.get(m1.url()).unwrap().consume().unwrap();
+    client.get(m2.url()).unwrap().consume().unwrap();
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn unkeyed_requests_are_not_limited() {
+    let m = mock!();
+    let client = HttpClient::builder()
+        .rate_limit(
+            RateLimit::new()
+                .requests_per(1, Duration::from_secs(10))
+                .key_fn(|_| None),
+        )
+        .build()
+        .unwrap();
+
+    let start = Instant::now();
+
+    for _ in 0..3 {
+        client.get(m.url()).unwrap().consume().unwrap();
+    }
+
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn in_flight_limit_waits_for_response_body() {
+    let m = mock! {
+        body: "hello",
+    };
+    let client = HttpClient::builder()
+        .rate_limit(RateLimit::new().max_in_flight(1))
+        .build()
+        .unwrap();
+
+    block_on(async {
+        let mut first = client.get_async(m.url()).await.unwrap();
+
+        // The first response body has not been read yet, so the second
+        // request cannot be sent.
+        let mut second = client.get_async(m.url());
+        assert!(poll_once(&mut second).await.is_none());
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(poll_once(&mut second).await.is_none());
+        assert_eq!(m.requests_received(), 1);
+
+        first.consume().await.unwrap();
+
+        second.await.unwrap().consume().await.unwrap();
+        assert_eq!(m.requests_received(), 2);
+    });
+}
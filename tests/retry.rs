@@ -0,0 +1,216 @@
+use isahc::{config::RetryPolicy, error::ErrorKind, prelude::*, Body, HttpClient, Request};
+use std::time::Duration;
+use testserver::mock;
+
+fn fast_policy(max_retries: u32) -> RetryPolicy {
+    RetryPolicy::new(max_retries).backoff(Duration::from_millis(1), Duration::from_millis(10))
+}
+
+#[test]
+fn no_retry_by_default() {
+    let m = mock! {
+        status: 503,
+    };
+
+    let response = isahc::get(m.url()).unwrap();
+
+    assert_eq!(response.status(), 503);
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn retry_on_status_until_success() {
+    let m = mock! {
+        #0 => {
+            status: 503,
+        },
+        #1 => {
+            status: 503,
+        },
+        _ => {
+            status: 200,
+            body: "ok",
+        },
+    };
+
+    let mut response = Request::get(m.url())
+        .retry_policy(fast_policy(3))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.text().unwrap(), "ok");
+    assert_eq!(m.requests_received(), 3);
+}
+
+#[test]
+fn retry_limit_is_respected() {
+    let m = mock! {
+        status: 503,
+    };
+
+    let response = Request::get(m.url())
+        .retry_policy(fast_policy(2))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 503);
+    assert_eq!(m.requests_received(), 3);
+}
+
+#[test]
+fn status_not_in_policy_is_not_retried() {
+    let m = mock! {
+        status: 500,
+    };
+
+    let response = Request::get(m.url())
+        .retry_policy(fast_policy(2))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 500);
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn non_idempotent_methods_are_not_retried() {
+    let m = mock! {
+        status: 503,
+    };
+
+    let response = Request::post(m.url())
+        .retry_policy(fast_policy(2))
+        .body("hello")
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 503);
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn retry_policy_from_client() {
+    let m = mock! {
+        #0 => {
+            status: 429,
+        },
+        _ => {
+            status: 200,
+        },
+    };
+
+    let client = HttpClient::builder()
+        .retry_policy(fast_policy(1))
+        .build()
+        .unwrap();
+
+    let response = client.get(m.url()).unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(m.requests_received(), 2);
+}
+
+#[test]
+fn retry_resends_rewindable_body() {
+    let m = mock! {
+        #0 => {
+            status: 503,
+        },
+        _ => {
+            status: 200,
+        },
+    };
+
+    let response = Request::put(m.url())
+        .retry_policy(fast_policy(1))
+        .body("hello world")
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(m.requests_received(), 2);
+    m.request().expect_body("hello world");
+}
+
+#[test]
+fn retry_after_longer_than_max_backoff_is_not_retried() {
+    let m = mock! {
+        status: 503,
+        headers {
+            "Retry-After": "3600",
+        }
+    };
+
+    let response = Request::get(m.url())
+        .retry_policy(fast_policy(3))
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.status(), 503);
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn retry_non_rewindable_body_returns_error() {
+    let m = mock! {
+        status: 503,
+    };
+
+    let result = Request::put(m.url())
+        .retry_policy(fast_policy(1))
+        .body(Body::from_reader("hello world".as_bytes()))
+        .unwrap()
+        .send();
+
+    assert_eq!(result.unwrap_err(), ErrorKind::RequestBodyNotRewindable);
+    assert_eq!(m.requests_received(), 1);
+}
+
+#[test]
+fn retry_on_connection_failure() {
+    use isahc::config::Dialer;
+    use std::{
+        net::{TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+    };
+
+    // Find a port that nothing is listening on.
+    let addr = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    // Count each attempt to connect to it.
+    let attempts = Arc::new(AtomicUsize::new(0));
+    let dialer = Dialer::custom({
+        let attempts = attempts.clone();
+        move |_| {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            TcpStream::connect(addr)
+        }
+    });
+
+    let result = Request::get(format!("http://{}", addr))
+        .dial(dialer)
+        .retry_policy(fast_policy(2))
+        .body(())
+        .unwrap()
+        .send();
+
+    assert_eq!(result.unwrap_err(), ErrorKind::ConnectionFailed);
+    assert_eq!(attempts.load(Ordering::SeqCst), 3);
+}
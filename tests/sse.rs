@@ -0,0 +1,181 @@
+use futures_lite::future::block_on;
+use isahc::{HttpClient, Request, prelude::*, sse::EventSource};
+use std::time::Duration;
+use testserver::mock;
+
+#[test]
+fn events_are_read_from_response() {
+    let m = mock! {
+        headers {
+            "content-type": "text/event-stream",
+        }
+        body: ": hello\nevent: greeting\ndata: hello\ndata: world\n\nid: 2\ndata: bye\n\n",
+    };
+
+    let mut response = isahc::get(m.url()).unwrap();
+    let events = response.sse().collect::<Result<Vec<_>, _>>().unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].event(), "greeting");
+    assert_eq!(events[0].data(), "hello\nworld");
+    assert_eq!(events[0].id(), None);
+    assert_eq!(events[1].event(), "message");
+    assert_eq!(events[1].data(), "bye");
+    assert_eq!(events[1].id(), Some("2"));
+}
+
+#[test]
+fn events_are_read_from_async_response() {
+    let m = mock! {
+        headers {
+            "content-type": "text/event-stream",
+        }
+        body: "data: a\r\n\r\ndata: b\r\n\r\n",
+    };
+
+    use futures_lite::StreamExt;
+
+    block_on(async {
+        let mut response = isahc::get_async(m.url()).await.unwrap();
+        let events = response.sse().collect::<Vec<_>>().await;
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].as_ref().unwrap().data(), "a");
+        assert_eq!(events[1].as_ref().unwrap().data(), "b");
+    });
+}
+
+#[test]
+fn event_source_reconnects_with_last_event_id() {
+    let m = mock! {
+        #0 => {
+            headers {
+                "content-type": "text/event-stream",
+            }
+            body: "id: 1\ndata: first\n\n",
+        },
+        #1 => {
+            headers {
+                "content-type": "text/event-stream; charset=utf-8",
+            }
+            body: "retry: 10\nid: 2\ndata: second\n\n",
+        },
+        _ => {
+            status: 204,
+        },
+    };
+
+    let client = HttpClient::new().unwrap();
+    let request = Request::get(m.url()).body(()).unwrap();
+    let events = EventSource::new(&client, request)
+        .reconnection_time(Duration::from_millis(10))
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].data(), "first");
+    assert_eq!(events[1].data(), "second");
+    assert_eq!(events[1].retry(), Some(Duration::from_millis(10)));
+
+    let requests = m.requests();
+    assert_eq!(requests.len(), 3);
+    requests[0].expect_header("accept", "text/event-stream");
+    assert!(requests[0].get_header("last-event-id").next().is_none());
+    requests[1].expect_header("last-event-id", "1");
+    requests[2].expect_header("last-event-id", "2");
+}
+
+#[test]
+fn event_source_resets_last_event_id_on_empty_id() {
+    let m = mock! {
+        #0 => {
+            headers {
+                "content-type": "text/event-stream",
+            }
+            body: "id: 1\ndata: first\n\n",
+        },
+        #1 => {
+            headers {
+                "content-type": "text/event-stream",
+            }
+            body: "id:\ndata: second\n\n",
+        },
+        #2 => {
+            headers {
+                "content-type": "text/event-stream",
+            }
+            body: "data: third\n\n",
+        },
+        _ => {
+            status: 204,
+        },
+    };
+
+    let client = HttpClient::new().unwrap();
+    let request = Request::get(m.url()).body(()).unwrap();
+    let events = EventSource::new(&client, request)
+        .reconnection_time(Duration::from_millis(10))
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+
+    assert_eq!(events.len(), 3);
+    assert_eq!(events[0].id(), Some("1"));
+    assert_eq!(events[1].id(), None);
+    assert_eq!(events[2].id(), None);
+
+    let requests = m.requests();
+    assert_eq!(requests.len(), 4);
+    requests[1].expect_header("last-event-id", "1");
+    assert!(requests[2].get_header("last-event-id").next().is_none());
+    assert!(requests[3].get_header("last-event-id").next().is_none());
+}
+
+#[test]
+fn event_source_as_async_stream() {
+    let m = mock! {
+        #0 => {
+            headers {
+                "content-type": "text/event-stream",
+            }
+            body: "data: only\n\n",
+        },
+        _ => {
+            status: 204,
+        },
+    };
+
+    use futures_lite::StreamExt;
+
+    block_on(async {
+        let client = HttpClient::new().unwrap();
+        let request = Request::get(m.url()).body(()).unwrap();
+        let mut source =
+            EventSource::new(&client, request).reconnection_time(Duration::from_millis(10));
+
+        assert_eq!(source.next().await.unwrap().unwrap().data(), "only");
+        assert!(source.next().await.is_none());
+    });
+}
+
+#[test]
+fn event_source_fails_on_wrong_content_type() {
+    let m = mock! {
+        headers {
+            "content-type": "text/plain",
+        }
+        body: "data: nope\n\n",
+    };
+
+    let client = HttpClient::new().unwrap();
+    let request = Request::get(m.url()).body(()).unwrap();
+    let mut source = EventSource::new(&client, request).into_iter();
+
+    assert_eq!(
+        source.next().unwrap().unwrap_err().kind(),
+        &isahc::error::ErrorKind::ProtocolViolation
+    );
+    assert!(source.next().is_none());
+    assert_eq!(m.requests_received(), 1);
+}
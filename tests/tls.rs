@@ -4,7 +4,7 @@ use isahc::{
     Request,
     error::ErrorKind,
     prelude::*,
-    tls::{ProtocolVersion, TlsConfig},
+    tls::{KeyLog, ProtocolVersion, PublicKeyPin, TlsConfig},
 };
 
 #[test]
@@ -85,6 +85,173 @@ fn tls_less_than_min_version_is_rejected() {
     assert_eq!(error, ErrorKind::ConnectionFailed);
 }
 
+/// Start a local server presenting a certificate for `localhost`, returning
+/// its URL and a TLS configuration that trusts it.
+fn local_tls_server() -> (String, TlsConfig) {
+    use isahc::tls::TrustStore;
+    use testserver::tls::TlsServer;
+
+    let server = TlsServer::new(
+        "127.0.0.1:0",
+        include_bytes!("certs/localhost.pem"),
+        include_bytes!("certs/localhost.key"),
+    )
+    .unwrap();
+    let url = format!("https://localhost:{}", server.addr().port());
+    server.spawn();
+
+    let config = TlsConfig::builder()
+        .trust_store(
+            TrustStore::builder()
+                .certificate_from_pem(include_bytes!("certs/test-ca.pem"))
+                .build(),
+        )
+        .build();
+
+    (url, config)
+}
+
+#[test]
+fn tls_info_is_captured_when_enabled() {
+    let (url, config) = local_tls_server();
+
+    let response = Request::get(url)
+        .tls_config(config)
+        .tls_info(true)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    let info = response.tls_info().expect("TLS info should be captured");
+
+    assert!(info.protocol_version().is_some());
+    assert!(info.cipher_suite().is_some());
+    assert!(!info.peer_certificates().is_empty());
+    assert!(info.peer_certificates()[0].to_der().is_some());
+}
+
+#[test]
+fn tls_info_is_not_captured_by_default() {
+    let (url, config) = local_tls_server();
+
+    let response = Request::get(url)
+        .tls_config(config)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert!(response.tls_info().is_none());
+}
+
+#[test]
+fn tls_info_is_not_captured_for_plain_http() {
+    let m = testserver::mock!();
+
+    let response = Request::get(m.url())
+        .tls_info(true)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert!(response.tls_info().is_none());
+}
+
+#[test]
+#[cfg(not(feature = "rustls-tls"))]
+fn pinned_public_key_mismatch_is_rejected() {
+    let error = Request::get("https://pinning-test.badssl.com")
+        .tls_config(
+            TlsConfig::builder()
+                .pinned_public_keys([PublicKeyPin::from_sha256([0; 32])])
+                .build(),
+        )
+        .body(())
+        .unwrap()
+        .send()
+        .expect_err("public key should have been rejected");
+
+    assert_eq!(error, ErrorKind::PinnedPublicKeyMismatch);
+}
+
+#[test]
+#[cfg(feature = "rustls-tls")]
+fn pinned_public_keys_are_not_ignored_when_unsupported() {
+    let error = Request::get("https://example.org")
+        .tls_config(
+            TlsConfig::builder()
+                .pinned_public_keys([PublicKeyPin::from_sha256([0; 32])])
+                .build(),
+        )
+        .body(())
+        .unwrap()
+        .send()
+        .expect_err("request should have failed");
+
+    assert_eq!(error, ErrorKind::TlsEngine);
+}
+
+#[test]
+fn invalid_pinned_public_key_is_rejected() {
+    let error = Request::get("https://example.org")
+        .tls_config(
+            TlsConfig::builder()
+                .pinned_public_keys([PublicKeyPin::from_sha256_base64("not a hash")])
+                .build(),
+        )
+        .body(())
+        .unwrap()
+        .send()
+        .expect_err("pin should have been rejected");
+
+    assert_eq!(error, ErrorKind::InvalidTlsConfiguration);
+}
+
+#[test]
+#[cfg(feature = "rustls-tls")]
+fn ocsp_stapling_is_not_ignored_when_unsupported() {
+    let error = Request::get("https://example.org")
+        .tls_config(TlsConfig::builder().require_ocsp_stapling(true).build())
+        .body(())
+        .unwrap()
+        .send()
+        .expect_err("request should have failed");
+
+    assert_eq!(error, ErrorKind::TlsEngine);
+}
+
+#[test]
+#[cfg(feature = "rustls-tls")]
+fn key_log_callback_is_not_ignored_when_unsupported() {
+    let error = Request::get("https://example.org")
+        .tls_config(
+            TlsConfig::builder()
+                .key_log(KeyLog::callback(|_| {}))
+                .build(),
+        )
+        .body(())
+        .unwrap()
+        .send()
+        .expect_err("request should have failed");
+
+    assert_eq!(error, ErrorKind::TlsEngine);
+}
+
+#[test]
+fn ocsp_stapling_is_not_ignored_for_proxies() {
+    let error = Request::get("https://example.org")
+        .proxy(Some("https://localhost:1".parse().unwrap()))
+        .proxy_tls_config(TlsConfig::builder().require_ocsp_stapling(true).build())
+        .body(())
+        .unwrap()
+        .send()
+        .expect_err("request should have failed");
+
+    assert_eq!(error, ErrorKind::InvalidTlsConfiguration);
+}
+
 #[test]
 #[cfg(feature = "trust-webpki-roots")]
 fn trust_webpki_roots() {
@@ -101,3 +268,272 @@ fn trust_webpki_roots() {
         .send()
         .unwrap();
 }
+
+#[test]
+fn identity_resolver_is_called_for_each_request() {
+    use isahc::{HttpClient, config::RedirectPolicy, tls::Identity};
+    use std::sync::{Arc, Mutex};
+
+    let target = testserver::mock!();
+    let location = target.url();
+    let m = testserver::mock! {
+        status: 302,
+        headers {
+            "Location": location,
+        }
+    };
+
+    let uris = Arc::new(Mutex::new(Vec::new()));
+    let client = HttpClient::builder()
+        .redirect_policy(RedirectPolicy::Follow)
+        .identity_resolver({
+            let uris = uris.clone();
+            move |uri| {
+                uris.lock().unwrap().push(uri.to_string());
+                Some(Identity::from_pem("<<PEM DATA>>", None))
+            }
+        })
+        .build()
+        .unwrap();
+
+    // The identity is only used when connecting using TLS.
+    client.get(m.url()).unwrap();
+
+    assert_eq!(*uris.lock().unwrap(), [m.url(), target.url()]);
+}
+
+/// Get the identity of one of the test client certificates.
+fn client_identity(name: &str) -> isahc::tls::Identity {
+    use isahc::tls::{Identity, PrivateKey};
+
+    let path = format!("{}/tests/certs/{}", env!("CARGO_MANIFEST_DIR"), name);
+
+    Identity::from_pem_file(
+        format!("{}.pem", path),
+        Some(PrivateKey::from_pem_file(format!("{}.key", path), None)),
+    )
+}
+
+/// Start a local server that requires a certificate issued by the test client
+/// CA, and build a client trusting the test CA with the given resolver.
+fn client_auth_server<F>(resolver: F) -> (testserver::tls::TlsServer, isahc::HttpClient)
+where
+    F: Fn(&http::Uri) -> Option<isahc::tls::Identity> + Send + Sync + 'static,
+{
+    use isahc::{HttpClient, tls::TrustStore};
+    use testserver::tls::TlsServer;
+
+    let server = TlsServer::with_client_auth(
+        "127.0.0.1:0",
+        include_bytes!("certs/localhost.pem"),
+        include_bytes!("certs/localhost.key"),
+        include_bytes!("certs/client-ca.pem"),
+    )
+    .unwrap();
+    server.clone().spawn();
+
+    let client = HttpClient::builder()
+        .tls_config(
+            TlsConfig::builder()
+                .trust_store(
+                    TrustStore::builder()
+                        .certificate_from_pem(include_bytes!("certs/test-ca.pem"))
+                        .build(),
+                )
+                .build(),
+        )
+        .identity_resolver(resolver)
+        .build()
+        .unwrap();
+
+    (server, client)
+}
+
+#[test]
+fn identity_resolver_picks_identity_for_each_connection() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let calls = AtomicUsize::new(0);
+    let (server, client) =
+        client_auth_server(move |_| match calls.fetch_add(1, Ordering::SeqCst) {
+            0 => Some(client_identity("client-a")),
+            _ => Some(client_identity("client-b")),
+        });
+    let url = format!("https://localhost:{}", server.addr().port());
+
+    assert_eq!(client.get(&url).unwrap().status(), 204);
+    assert_eq!(client.get(&url).unwrap().status(), 204);
+
+    let certs = server.client_certificates();
+
+    assert_eq!(certs.len(), 2);
+    assert!(certs[0].is_some());
+    assert!(certs[1].is_some());
+    assert_ne!(certs[0], certs[1]);
+}
+
+#[test]
+fn identity_set_on_request_takes_precedence_over_resolver() {
+    use isahc::tls::TrustStore;
+    use std::sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    };
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let (server, client) = client_auth_server({
+        let calls = calls.clone();
+        move |_| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Some(client_identity("client-a"))
+        }
+    });
+    let url = format!("https://localhost:{}", server.addr().port());
+
+    client.get(&url).unwrap();
+
+    // The TLS configuration of the request replaces that of the client, so
+    // the test CA has to be trusted again.
+    let request = Request::get(&url)
+        .tls_config(
+            TlsConfig::builder()
+                .trust_store(
+                    TrustStore::builder()
+                        .certificate_from_pem(include_bytes!("certs/test-ca.pem"))
+                        .build(),
+                )
+                .identity(client_identity("client-b"))
+                .build(),
+        )
+        .body(())
+        .unwrap();
+    client.send(request).unwrap();
+
+    let certs = server.client_certificates();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(certs.len(), 2);
+    assert!(certs[1].is_some());
+    assert_ne!(certs[0], certs[1]);
+}
+
+/// Send a request to a local server presenting the given certificate, trusting
+/// only the test CA and checking revocation against the given CRL file.
+fn send_with_crl(
+    cert: &[u8],
+    key: &[u8],
+    crl: &str,
+) -> Result<isahc::Response<isahc::Body>, isahc::Error> {
+    use isahc::tls::TrustStore;
+    use testserver::tls::TlsServer;
+
+    let server = TlsServer::new("127.0.0.1:0", cert, key).unwrap();
+    let port = server.addr().port();
+    server.spawn();
+
+    Request::get(format!("https://localhost:{}", port))
+        .tls_config(
+            TlsConfig::builder()
+                .trust_store(
+                    TrustStore::builder()
+                        .certificate_from_pem(include_bytes!("certs/test-ca.pem"))
+                        .build(),
+                )
+                .crl_file(format!(
+                    "{}/tests/certs/{}",
+                    env!("CARGO_MANIFEST_DIR"),
+                    crl
+                ))
+                .build(),
+        )
+        .body(())
+        .unwrap()
+        .send()
+}
+
+#[test]
+fn certificate_not_in_crl_is_accepted() {
+    let response = send_with_crl(
+        include_bytes!("certs/localhost.pem"),
+        include_bytes!("certs/localhost.key"),
+        "test-ca-crl.pem",
+    )
+    .unwrap();
+
+    assert_eq!(response.status(), 204);
+}
+
+#[test]
+fn revoked_certificate_is_rejected() {
+    let error = send_with_crl(
+        include_bytes!("certs/localhost-revoked.pem"),
+        include_bytes!("certs/localhost-revoked.key"),
+        "test-ca-crl.pem",
+    )
+    .expect_err("certificate should have been rejected");
+
+    assert_eq!(error, ErrorKind::CertificateRevoked);
+}
+
+#[test]
+fn certificate_not_covered_by_crl_is_rejected() {
+    // The CRL is issued by a different CA, so the revocation status of the
+    // server certificate cannot be determined.
+    let error = send_with_crl(
+        include_bytes!("certs/localhost.pem"),
+        include_bytes!("certs/localhost.key"),
+        "other-ca-crl.pem",
+    )
+    .expect_err("certificate should have been rejected");
+
+    assert_eq!(error, ErrorKind::BadServerCertificate);
+}
+
+#[test]
+fn replaced_trust_store_is_used_by_running_client() {
+    use isahc::{
+        HttpClient,
+        tls::{Reloadable, TrustStore},
+    };
+    use testserver::tls::TlsServer;
+
+    let server = TlsServer::new(
+        "127.0.0.1:0",
+        include_bytes!("certs/localhost.pem"),
+        include_bytes!("certs/localhost.key"),
+    )
+    .unwrap();
+    let url = format!("https://localhost:{}", server.addr().port());
+    server.spawn();
+
+    let untrusted = || {
+        TrustStore::builder()
+            .certificate_from_pem(include_bytes!("certs/isrgrootx1.pem"))
+            .build()
+    };
+    let trusted = || {
+        TrustStore::builder()
+            .certificate_from_pem(include_bytes!("certs/test-ca.pem"))
+            .build()
+    };
+
+    let trust_store = Reloadable::new(untrusted());
+    let client = HttpClient::builder()
+        .tls_config(
+            TlsConfig::builder()
+                .trust_store_source(trust_store.clone())
+                .build(),
+        )
+        .build()
+        .unwrap();
+
+    let error = client.get(&url).expect_err("server should not be trusted");
+    assert_eq!(error, ErrorKind::BadServerCertificate);
+
+    trust_store.set(trusted());
+    assert_eq!(client.get(&url).unwrap().status(), 204);
+
+    trust_store.set(untrusted());
+    let error = client.get(&url).expect_err("server should not be trusted");
+    assert_eq!(error, ErrorKind::BadServerCertificate);
+}
@@ -0,0 +1,40 @@
+//! Tests using the default native trust store with rustls, which uses the
+//! platform verifier unless `SSL_CERT_FILE` is set. Since the native store is
+//! only probed once per process, these live in their own test binary so that
+//! the environment can be changed before it is first used.
+
+#![cfg(feature = "rustls-tls")]
+
+use isahc::{Request, error::ErrorKind, prelude::*, tls::TlsConfig};
+use testserver::tls::TlsServer;
+
+#[test]
+fn crl_with_native_trust_store_is_rejected() {
+    // SAFETY: This is the only test in this binary, so no other threads are
+    // reading the environment.
+    unsafe {
+        std::env::remove_var("SSL_CERT_FILE");
+    }
+
+    let server = TlsServer::new(
+        "127.0.0.1:0",
+        include_bytes!("certs/localhost.pem"),
+        include_bytes!("certs/localhost.key"),
+    )
+    .unwrap();
+    let port = server.addr().port();
+    server.spawn();
+
+    let error = Request::get(format!("https://localhost:{}", port))
+        .tls_config(
+            TlsConfig::builder()
+                .crl_pem(include_bytes!("certs/test-ca-crl.pem"))
+                .build(),
+        )
+        .body(())
+        .unwrap()
+        .send()
+        .expect_err("CRL should have been rejected");
+
+    assert_eq!(error, ErrorKind::InvalidTlsConfiguration);
+}
@@ -3,7 +3,7 @@
 use isahc::{config::Dialer, prelude::*, Request};
 use std::{
     io::{self, Write},
-    os::unix::net::UnixListener,
+    os::unix::net::{UnixListener, UnixStream},
     thread,
 };
 use tempfile::TempDir;
@@ -40,3 +40,72 @@ fn send_request_to_unix_socket() {
 
     assert_eq!(response.text().unwrap(), "success\n");
 }
+
+#[test]
+#[rustfmt::skip]
+fn send_request_to_custom_socket_pair() {
+    let dialer = Dialer::custom(|_| {
+        let (client, mut server) = UnixStream::pair()?;
+
+        thread::spawn(move || {
+            let mut reader = server.try_clone().unwrap();
+
+            thread::spawn(move || {
+                io::copy(&mut reader, &mut io::sink()).unwrap();
+            });
+
+            server.write_all(b"\
+                HTTP/1.1 200 OK\r\n\
+                Content-Length: 8\r\n\
+                \r\n\
+                success\n\
+            ").unwrap();
+        });
+
+        Ok(client)
+    });
+
+    let mut response = Request::get("http://localhost")
+        .dial(dialer)
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.text().unwrap(), "success\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+#[rustfmt::skip]
+fn send_request_to_abstract_unix_socket() {
+    use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+
+    let name = format!("isahc-test-{}", std::process::id());
+    let listener = UnixListener::bind_addr(&SocketAddr::from_abstract_name(&name).unwrap()).unwrap();
+
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = stream.try_clone().unwrap();
+
+        thread::spawn(move || {
+            io::copy(&mut reader, &mut io::sink()).unwrap();
+        });
+
+        stream.write_all(b"\
+            HTTP/1.1 200 OK\r\n\
+            Content-Length: 8\r\n\
+            \r\n\
+            success\n\
+        ").unwrap();
+    });
+
+    let mut response = Request::get("http://localhost")
+        .dial(format!("unix-abstract:{}", name).parse::<Dialer>().unwrap())
+        .body(())
+        .unwrap()
+        .send()
+        .unwrap();
+
+    assert_eq!(response.text().unwrap(), "success\n");
+}
@@ -0,0 +1,173 @@
+//! A simple DNS over HTTPS (DoH) server implementation, answering A and AAAA
+//! queries from a fixed set of records.
+//!
+//! Queries are accepted as `POST` requests using the
+//! `application/dns-message` media type, as described in RFC 8484. The server
+//! only speaks plain HTTP, which is enough for testing clients that do not
+//! require HTTPS for the DoH endpoint.
+
+use crate::{mock::Mock, request::Request, responder::*, response::Response};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+
+#[derive(Clone)]
+pub struct DohServer {
+    mock: Mock,
+    records: Arc<Mutex<HashMap<String, Vec<IpAddr>>>>,
+    queries: Arc<Mutex<Vec<(String, u16)>>>,
+}
+
+impl DohServer {
+    /// Start a new DoH server without any records.
+    pub fn new() -> Self {
+        let records = Arc::new(Mutex::new(HashMap::new()));
+        let queries = Arc::new(Mutex::new(Vec::new()));
+
+        let mock = Mock::new(DohResponder {
+            records: records.clone(),
+            queries: queries.clone(),
+        });
+
+        Self {
+            mock,
+            records,
+            queries,
+        }
+    }
+
+    /// Add an address record for the given name. Names without any records
+    /// are answered with `NXDOMAIN`.
+    pub fn add(&self, name: &str, addr: impl Into<IpAddr>) -> &Self {
+        self.records
+            .lock()
+            .unwrap()
+            .entry(name.to_ascii_lowercase())
+            .or_default()
+            .push(addr.into());
+        self
+    }
+
+    /// Get the URL of the DoH endpoint.
+    pub fn url(&self) -> String {
+        format!("{}dns-query", self.mock.url())
+    }
+
+    /// Get the name and record type of every query received so far, in the
+    /// order they were received.
+    pub fn queries(&self) -> Vec<(String, u16)> {
+        self.queries.lock().unwrap().clone()
+    }
+
+    /// Get the raw HTTP requests received so far.
+    pub fn requests(&self) -> Vec<Request> {
+        self.mock.requests()
+    }
+}
+
+impl Default for DohServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct DohResponder {
+    records: Arc<Mutex<HashMap<String, Vec<IpAddr>>>>,
+    queries: Arc<Mutex<Vec<(String, u16)>>>,
+}
+
+impl Responder for DohResponder {
+    fn respond(&self, ctx: &mut RequestContext<'_>) {
+        let request = ctx.request();
+
+        if request.method() != "POST" || !request.url().starts_with("/dns-query") {
+            ctx.send(Response {
+                status_code: 404,
+                ..Response::default()
+            });
+            return;
+        }
+
+        let query = request.body.clone().unwrap_or_default();
+        let (name, record_type, question_end) = match parse_question(&query) {
+            Some(question) => question,
+            None => {
+                ctx.send(Response {
+                    status_code: 400,
+                    ..Response::default()
+                });
+                return;
+            }
+        };
+
+        self.queries
+            .lock()
+            .unwrap()
+            .push((name.clone(), record_type));
+
+        let addrs = self.records.lock().unwrap().get(&name).cloned();
+
+        // Echo the header and question, marking the message as a response.
+        let mut message = query[..question_end].to_vec();
+        message[2] |= 0x80;
+        message[3] = 0x80;
+        message[6..12].fill(0);
+
+        match addrs {
+            Some(addrs) => {
+                let answers = addrs
+                    .iter()
+                    .filter_map(|addr| match (addr, record_type) {
+                        (IpAddr::V4(addr), 1) => Some(addr.octets().to_vec()),
+                        (IpAddr::V6(addr), 28) => Some(addr.octets().to_vec()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>();
+
+                message[7] = answers.len() as u8;
+
+                for data in answers {
+                    // Name pointer to the question, type, class, TTL, data.
+                    message.extend_from_slice(&[0xc0, 0x0c]);
+                    message.extend_from_slice(&record_type.to_be_bytes());
+                    message.extend_from_slice(&[0, 1, 0, 0, 0, 60]);
+                    message.extend_from_slice(&(data.len() as u16).to_be_bytes());
+                    message.extend_from_slice(&data);
+                }
+            }
+            None => message[3] |= 3,
+        }
+
+        let mut response = Response::new().with_body_buf(message);
+        response
+            .headers
+            .push(("content-type".into(), "application/dns-message".into()));
+        ctx.send(response);
+    }
+}
+
+/// Parse the first question of a DNS query, returning the name, record type,
+/// and the offset where the question ends.
+fn parse_question(message: &[u8]) -> Option<(String, u16, usize)> {
+    let mut labels = Vec::new();
+    let mut pos = 12;
+
+    loop {
+        let len = *message.get(pos)? as usize;
+        pos += 1;
+
+        if len == 0 {
+            break;
+        }
+
+        labels.push(String::from_utf8_lossy(message.get(pos..pos + len)?).to_ascii_lowercase());
+        pos += len;
+    }
+
+    let question = message.get(pos..pos + 4)?;
+    let record_type = u16::from_be_bytes([question[0], question[1]]);
+
+    Some((labels.join("."), record_type, pos + 4))
+}
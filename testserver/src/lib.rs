@@ -8,7 +8,9 @@ mod request;
 mod responder;
 mod response;
 
+pub mod doh;
 pub mod socks4;
+pub mod tls;
 
 pub use macros::macro_api;
 pub use mock::Mock;
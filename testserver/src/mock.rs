@@ -71,6 +71,12 @@ impl Mock {
         request.expect("no request received")
     }
 
+    /// Get all requests received by this mock so far, in the order they were
+    /// received.
+    pub fn requests(&self) -> Vec<Request> {
+        self.0.requests.lock().unwrap().iter().cloned().collect()
+    }
+
     #[rustfmt::skip]
     fn is_ready(&self) -> bool {
         TcpStream::connect(self.addr())
@@ -0,0 +1,144 @@
+//! A minimal HTTPS server that answers every request with an empty response.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+    sync::{Arc, Mutex},
+};
+
+use rustls::{
+    RootCertStore, ServerConfig, ServerConnection, StreamOwned,
+    pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject},
+    server::WebPkiClientVerifier,
+};
+
+use crate::pool::pool;
+
+#[derive(Clone, Debug)]
+pub struct TlsServer {
+    listener: Arc<TcpListener>,
+    addr: SocketAddr,
+    config: Arc<ServerConfig>,
+    client_certificates: Arc<Mutex<Vec<Option<Vec<u8>>>>>,
+}
+
+impl TlsServer {
+    /// Create a new server listening at the given address, presenting the
+    /// given PEM-encoded certificate chain and private key.
+    pub fn new(addr: impl ToSocketAddrs, cert_chain: &[u8], key: &[u8]) -> io::Result<Self> {
+        let config = ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(parse_certs(cert_chain)?, parse_key(key)?)
+            .map_err(invalid_data)?;
+
+        Self::with_config(addr, config)
+    }
+
+    /// Create a new server like [`TlsServer::new`] that also requires clients
+    /// to present a certificate issued by one of the given PEM-encoded CAs.
+    pub fn with_client_auth(
+        addr: impl ToSocketAddrs,
+        cert_chain: &[u8],
+        key: &[u8],
+        client_ca: &[u8],
+    ) -> io::Result<Self> {
+        let mut roots = RootCertStore::empty();
+
+        for cert in parse_certs(client_ca)? {
+            roots.add(cert).map_err(invalid_data)?;
+        }
+
+        let verifier = WebPkiClientVerifier::builder(Arc::new(roots))
+            .build()
+            .map_err(invalid_data)?;
+
+        let config = ServerConfig::builder()
+            .with_client_cert_verifier(verifier)
+            .with_single_cert(parse_certs(cert_chain)?, parse_key(key)?)
+            .map_err(invalid_data)?;
+
+        Self::with_config(addr, config)
+    }
+
+    fn with_config(addr: impl ToSocketAddrs, config: ServerConfig) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+
+        Ok(Self {
+            addr: listener.local_addr()?,
+            listener: Arc::new(listener),
+            config: Arc::new(config),
+            client_certificates: Default::default(),
+        })
+    }
+
+    /// Get the address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Get the DER-encoded certificates presented by clients, one entry for
+    /// each connection that completed a request, in order.
+    pub fn client_certificates(&self) -> Vec<Option<Vec<u8>>> {
+        self.client_certificates.lock().unwrap().clone()
+    }
+
+    pub fn run(&self) {
+        for connection in self.listener.incoming().flatten() {
+            let s = self.clone();
+
+            pool().execute(move || {
+                // Handshake failures are expected when testing certificate
+                // validation, so ignore errors.
+                let _ = s.handle(connection);
+            });
+        }
+    }
+
+    pub fn spawn(self) {
+        pool().execute(move || self.run());
+    }
+
+    fn handle(&self, connection: TcpStream) -> io::Result<()> {
+        let tls = ServerConnection::new(self.config.clone())
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        let mut stream = BufReader::new(StreamOwned::new(tls, connection));
+
+        // Read the request head, ignoring any body.
+        let mut line = String::new();
+        loop {
+            line.clear();
+
+            if stream.read_line(&mut line)? == 0 || line == "\r\n" {
+                break;
+            }
+        }
+
+        let stream = stream.get_mut();
+
+        self.client_certificates.lock().unwrap().push(
+            stream
+                .conn
+                .peer_certificates()
+                .and_then(|certs| certs.first())
+                .map(|cert| cert.to_vec()),
+        );
+
+        stream.write_all(b"HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n")?;
+        stream.conn.send_close_notify();
+        stream.flush()
+    }
+}
+
+fn parse_certs(pem: &[u8]) -> io::Result<Vec<CertificateDer<'static>>> {
+    CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid_data)
+}
+
+fn parse_key(pem: &[u8]) -> io::Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_slice(pem).map_err(invalid_data)
+}
+
+fn invalid_data(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}